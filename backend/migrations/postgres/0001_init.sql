@@ -0,0 +1,97 @@
+-- ประเภทสินค้า
+CREATE TABLE IF NOT EXISTS products_type(
+    id BIGSERIAL PRIMARY KEY,
+    products_type_name TEXT NOT NULL UNIQUE
+);
+
+-- สินค้า
+CREATE TABLE IF NOT EXISTS products(
+    id BIGSERIAL PRIMARY KEY,
+    name_products TEXT NOT NULL,
+    price NUMERIC(10, 2) NOT NULL,
+    detail JSONB NOT NULL,
+    image_path TEXT NOT NULL,
+    stock BIGINT NOT NULL,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    products_type_id BIGINT,
+    FOREIGN KEY(products_type_id) REFERENCES products_type(id)
+);
+
+CREATE TABLE IF NOT EXISTS images(
+    id BIGSERIAL PRIMARY KEY,
+    image_path TEXT NOT NULL,
+    product_id BIGINT,
+    product_type_id BIGINT,
+    FOREIGN KEY(product_id) REFERENCES products(id),
+    FOREIGN KEY(product_type_id) REFERENCES products_type(id)
+);
+
+-- ที่อยู่
+CREATE TABLE IF NOT EXISTS address(
+    id BIGSERIAL PRIMARY KEY,
+    address_main TEXT NOT NULL,
+    district TEXT NOT NULL,
+    province TEXT NOT NULL,
+    zip_code BIGINT NOT NULL
+);
+
+-- ข้อมูลผู้ใช้
+CREATE TABLE IF NOT EXISTS personal_data(
+    id BIGSERIAL PRIMARY KEY,
+    fullname TEXT NOT NULL,
+    lastname TEXT NOT NULL,
+    birthday DATE NOT NULL,
+    address_id BIGINT,
+    FOREIGN KEY(address_id) REFERENCES address(id)
+);
+
+-- ประเภทบัญชี (คนขาย, คนซื้อ)
+CREATE TABLE IF NOT EXISTS account_type(
+    id BIGSERIAL PRIMARY KEY,
+    account_type TEXT NOT NULL
+);
+
+-- ข้อมูลบัญชีผู้ใช้
+CREATE TABLE IF NOT EXISTS account(
+    id BIGSERIAL PRIMARY KEY,
+    email TEXT NOT NULL UNIQUE,
+    username TEXT NOT NULL UNIQUE,
+    password TEXT NOT NULL,
+    account_type_id BIGINT NOT NULL,
+    personal_data_id BIGINT,
+    FOREIGN KEY(account_type_id) REFERENCES account_type(id),
+    FOREIGN KEY(personal_data_id) REFERENCES personal_data(id)
+);
+
+-- ออเดอร์
+CREATE TABLE IF NOT EXISTS order_data(
+    id BIGSERIAL PRIMARY KEY,
+    products_id BIGINT NOT NULL,
+    personal_data_id BIGINT NOT NULL,
+    FOREIGN KEY(products_id) REFERENCES products(id),
+    FOREIGN KEY(personal_data_id) REFERENCES personal_data(id)
+);
+
+-- สถานะออเดอร์
+CREATE TABLE IF NOT EXISTS order_status(
+    id BIGSERIAL PRIMARY KEY,
+    status TEXT NOT NULL
+);
+
+-- ออเดอร์
+CREATE TABLE IF NOT EXISTS orders(
+    id BIGSERIAL PRIMARY KEY,
+    order_data_id BIGINT NOT NULL,
+    quantity BIGINT NOT NULL,
+    order_status_id BIGINT NOT NULL,
+    FOREIGN KEY(order_data_id) REFERENCES order_data(id),
+    FOREIGN KEY(order_status_id) REFERENCES order_status(id)
+);
+
+-- วันที่จัดส่ง
+CREATE TABLE IF NOT EXISTS delivery(
+    Sent_date TIMESTAMP NOT NULL,
+    Delivery_date TIMESTAMP NOT NULL,
+    orders_id BIGINT NOT NULL,
+    FOREIGN KEY(orders_id) REFERENCES orders(id)
+);
@@ -0,0 +1,3 @@
+-- รูปภาพสินค้าเก็บอยู่ในตาราง images แล้ว คอลัมน์ products.image_path จึงไม่ถูกใช้
+ALTER TABLE products DROP COLUMN image_path;
+ALTER TABLE products ALTER COLUMN price TYPE DOUBLE PRECISION;
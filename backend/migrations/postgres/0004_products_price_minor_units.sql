@@ -0,0 +1,7 @@
+-- ราคาเก็บเป็นจำนวนเต็มหน่วยย่อย (สตางค์) พร้อมสกุลเงิน
+ALTER TABLE products ADD COLUMN price_minor BIGINT NOT NULL DEFAULT 0;
+ALTER TABLE products ADD COLUMN currency TEXT NOT NULL DEFAULT 'THB';
+
+UPDATE products SET price_minor = ROUND(price * 100)::BIGINT;
+
+ALTER TABLE products DROP COLUMN price;
@@ -0,0 +1,16 @@
+-- กฎ ON DELETE เหมือนฝั่ง SQLite: ลบประเภท -> สินค้าเป็น other, ลบสินค้า/ประเภท -> รูปถูกลบตาม
+ALTER TABLE products DROP CONSTRAINT products_products_type_id_fkey;
+ALTER TABLE products ADD CONSTRAINT products_products_type_id_fkey
+    FOREIGN KEY(products_type_id) REFERENCES products_type(id) ON DELETE SET NULL;
+
+ALTER TABLE images DROP CONSTRAINT images_product_id_fkey;
+ALTER TABLE images ADD CONSTRAINT images_product_id_fkey
+    FOREIGN KEY(product_id) REFERENCES products(id) ON DELETE CASCADE;
+
+ALTER TABLE images DROP CONSTRAINT images_product_type_id_fkey;
+ALTER TABLE images ADD CONSTRAINT images_product_type_id_fkey
+    FOREIGN KEY(product_type_id) REFERENCES products_type(id) ON DELETE CASCADE;
+
+CREATE INDEX idx_images_product_id ON images(product_id);
+CREATE INDEX idx_images_product_type_id ON images(product_type_id);
+CREATE INDEX idx_products_products_type_id ON products(products_type_id);
@@ -0,0 +1,14 @@
+-- ประวัติการแก้ไขแคตตาล็อก เขียนใน transaction เดียวกับการแก้ไขข้อมูล
+CREATE TABLE audit_log(
+    id BIGSERIAL PRIMARY KEY,
+    actor TEXT NOT NULL,
+    action TEXT NOT NULL,
+    entity_type TEXT NOT NULL,
+    entity_id BIGINT NOT NULL,
+    before_data JSONB,
+    after_data JSONB,
+    created_at TIMESTAMP NOT NULL
+);
+
+CREATE INDEX idx_audit_log_entity ON audit_log(entity_type, entity_id);
+CREATE INDEX idx_audit_log_created_at ON audit_log(created_at);
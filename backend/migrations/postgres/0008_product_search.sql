@@ -0,0 +1,43 @@
+-- ดัชนีค้นหาสินค้าแบบ full-text เก็บเป็น tsvector ในคอลัมน์ search_vector
+-- น้ำหนัก: ชื่อสินค้า A, ชื่อประเภท B, ค่าใน detail C อัปเดตด้วย trigger
+
+-- ค่าทุกตัวใน JSON ต่อกันเป็นข้อความ (ไม่รวมชื่อ key)
+CREATE FUNCTION jsonb_values_text(j JSONB) RETURNS TEXT AS $$
+    SELECT COALESCE(string_agg(v #>> '{}', ' '), '')
+    FROM jsonb_path_query(j, 'strict $.**') AS v
+    WHERE jsonb_typeof(v) NOT IN ('object', 'array', 'null')
+$$ LANGUAGE sql IMMUTABLE;
+
+ALTER TABLE products ADD COLUMN search_vector TSVECTOR;
+
+CREATE FUNCTION products_search_vector_update() RETURNS TRIGGER AS $$
+BEGIN
+    NEW.search_vector :=
+        setweight(to_tsvector('simple', NEW.name_products), 'A') ||
+        setweight(to_tsvector('simple', COALESCE(
+            (SELECT products_type_name FROM products_type WHERE id = NEW.products_type_id), ''
+        )), 'B') ||
+        setweight(to_tsvector('simple', jsonb_values_text(NEW.detail)), 'C');
+    RETURN NEW;
+END
+$$ LANGUAGE plpgsql;
+
+-- ON DELETE SET NULL ของ products_type_id ก็ทำให้ trigger นี้ทำงานด้วย
+CREATE TRIGGER products_search_vector
+BEFORE INSERT OR UPDATE OF name_products, detail, products_type_id ON products
+FOR EACH ROW EXECUTE FUNCTION products_search_vector_update();
+
+CREATE FUNCTION products_type_search_vector_update() RETURNS TRIGGER AS $$
+BEGIN
+    UPDATE products SET products_type_id = products_type_id WHERE products_type_id = NEW.id;
+    RETURN NULL;
+END
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER products_type_search_vector
+AFTER UPDATE OF products_type_name ON products_type
+FOR EACH ROW EXECUTE FUNCTION products_type_search_vector_update();
+
+UPDATE products SET products_type_id = products_type_id;
+
+CREATE INDEX idx_products_search_vector ON products USING GIN (search_vector);
@@ -0,0 +1,18 @@
+-- สินค้าที่ขายเกิน stock ได้ (backorder) ค่าเริ่มต้นคือไม่ได้
+ALTER TABLE products ADD COLUMN allow_backorder BOOLEAN NOT NULL DEFAULT FALSE;
+
+-- ประวัติการเปลี่ยน stock เขียนใน transaction เดียวกับการแก้ stock ของสินค้า
+-- quantity เป็นจำนวนที่เปลี่ยน (ลบ = ลด) stock_after คือ stock หลังรายการนั้น
+CREATE TABLE stock_movements(
+    id BIGSERIAL PRIMARY KEY,
+    product_id BIGINT NOT NULL,
+    quantity BIGINT NOT NULL,
+    reason TEXT NOT NULL,
+    note TEXT,
+    stock_after BIGINT NOT NULL,
+    actor TEXT NOT NULL,
+    created_at TIMESTAMP NOT NULL,
+    FOREIGN KEY(product_id) REFERENCES products(id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_stock_movements_product ON stock_movements(product_id, id);
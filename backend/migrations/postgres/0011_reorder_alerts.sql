@@ -0,0 +1,23 @@
+-- จุดสั่งซื้อ: stock <= reorder_threshold ถือว่าใกล้หมด ควรสั่งเพิ่ม reorder_quantity ชิ้น
+-- NULL ในสินค้า = ใช้ค่าของประเภท
+ALTER TABLE products ADD COLUMN reorder_threshold BIGINT;
+ALTER TABLE products ADD COLUMN reorder_quantity BIGINT;
+ALTER TABLE products_type ADD COLUMN reorder_threshold BIGINT;
+ALTER TABLE products_type ADD COLUMN reorder_quantity BIGINT;
+
+-- outbox ของการแจ้งเตือนสินค้าใกล้หมด เขียนใน transaction เดียวกับรายการใน stock_movements ที่ทำให้เกิด
+CREATE TABLE stock_alerts(
+    id BIGSERIAL PRIMARY KEY,
+    product_id BIGINT NOT NULL,
+    stock_movement_id BIGINT NOT NULL,
+    stock BIGINT NOT NULL,
+    reorder_threshold BIGINT NOT NULL,
+    reorder_quantity BIGINT,
+    created_at TIMESTAMP NOT NULL,
+    acknowledged_at TIMESTAMP,
+    FOREIGN KEY(product_id) REFERENCES products(id) ON DELETE CASCADE,
+    FOREIGN KEY(stock_movement_id) REFERENCES stock_movements(id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_stock_alerts_acknowledged_at ON stock_alerts(acknowledged_at, id);
+CREATE INDEX idx_stock_alerts_product_id ON stock_alerts(product_id);
@@ -0,0 +1,30 @@
+-- แกนตัวเลือกของ variant เช่น [{"name":"size","values":["S","M","L"]}] ([] = สินค้าไม่มี variant)
+ALTER TABLE products ADD COLUMN variant_options JSONB NOT NULL DEFAULT '[]';
+
+CREATE TABLE product_variants(
+    id BIGSERIAL PRIMARY KEY,
+    product_id BIGINT NOT NULL,
+    sku TEXT NOT NULL,
+    -- ค่าของทุกแกน เช่น {"colour":"red","size":"M"} (key เรียงแล้ว จึงเทียบความซ้ำได้ตรง ๆ)
+    options JSONB NOT NULL,
+    -- NULL = ใช้ราคาของสินค้า ค่าเป็นหน่วยย่อยในสกุลเงินของสินค้า
+    price_minor BIGINT,
+    stock BIGINT NOT NULL DEFAULT 0,
+    created_at TIMESTAMP NOT NULL,
+    FOREIGN KEY(product_id) REFERENCES products(id) ON DELETE CASCADE
+);
+
+CREATE UNIQUE INDEX idx_product_variants_sku ON product_variants(sku);
+CREATE UNIQUE INDEX idx_product_variants_options ON product_variants(product_id, options);
+
+-- รูปของ variant เลือกจากรูปของสินค้า ลบรูปแล้วความเชื่อมโยงหายตาม
+CREATE TABLE product_variant_images(
+    variant_id BIGINT NOT NULL,
+    image_id BIGINT NOT NULL,
+    position BIGINT NOT NULL,
+    PRIMARY KEY(variant_id, image_id),
+    FOREIGN KEY(variant_id) REFERENCES product_variants(id) ON DELETE CASCADE,
+    FOREIGN KEY(image_id) REFERENCES images(id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_product_variant_images_image_id ON product_variant_images(image_id);
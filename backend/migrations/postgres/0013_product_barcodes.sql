@@ -0,0 +1,8 @@
+-- รหัสสินค้าของร้าน (ไม่ซ้ำกับ sku ของ variant ด้วย ตรวจใน repository) NULL = ยังไม่ได้ตั้ง
+ALTER TABLE products ADD COLUMN sku TEXT;
+-- barcode ที่พิมพ์บนฉลาก barcode_format คือ ean13, upca หรือ code128
+ALTER TABLE products ADD COLUMN barcode TEXT;
+ALTER TABLE products ADD COLUMN barcode_format TEXT;
+
+CREATE UNIQUE INDEX idx_products_sku ON products(sku);
+CREATE UNIQUE INDEX idx_products_barcode ON products(barcode);
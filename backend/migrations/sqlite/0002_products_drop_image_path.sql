@@ -0,0 +1,20 @@
+-- รูปภาพสินค้าเก็บอยู่ในตาราง images แล้ว คอลัมน์ products.image_path จึงไม่ถูกใช้
+-- (post_products ไม่เคยเขียนค่านี้ ทำให้ insert ล้มเหลวเพราะ NOT NULL)
+CREATE TABLE products_new(
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name_products TEXT NOT NULL,
+    price REAL NOT NULL,
+    detail TEXT NOT NULL,
+    stock INTEGER NOT NULL,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    products_type_id INTEGER,
+    FOREIGN KEY(products_type_id) REFERENCES products_type(id)
+);
+
+INSERT INTO products_new (id, name_products, price, detail, stock, created_at, products_type_id)
+SELECT id, name_products, price, detail, stock, created_at, products_type_id
+FROM products;
+
+DROP TABLE products;
+
+ALTER TABLE products_new RENAME TO products;
@@ -0,0 +1,4 @@
+-- เก็บ path รูปแบบสัมพันธ์กับโฟลเดอร์รูป (images_dir ใน config) แทน ../databases/dbimages/
+UPDATE images
+SET image_path = substr(image_path, length('../databases/dbimages/') + 1)
+WHERE image_path LIKE '../databases/dbimages/%';
@@ -0,0 +1,8 @@
+-- ราคาเก็บเป็นจำนวนเต็มหน่วยย่อย (สตางค์) พร้อมสกุลเงิน แทน REAL
+-- ข้อมูลเดิมถือเป็น THB และปัดด้วย ROUND (half away from zero)
+ALTER TABLE products ADD COLUMN price_minor INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE products ADD COLUMN currency TEXT NOT NULL DEFAULT 'THB';
+
+UPDATE products SET price_minor = CAST(ROUND(price * 100) AS INTEGER);
+
+ALTER TABLE products DROP COLUMN price;
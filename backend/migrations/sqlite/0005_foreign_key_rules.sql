@@ -0,0 +1,57 @@
+-- กำหนดกฎ ON DELETE ให้ฐานข้อมูลจัดการแทนการลบทีละแถวใน handler
+-- ลบประเภทสินค้า -> สินค้าในประเภทนั้นกลายเป็น other (SET NULL), รูปของประเภทถูกลบตาม
+-- ลบสินค้า -> รูปของสินค้าถูกลบตาม
+CREATE TABLE products_new(
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name_products TEXT NOT NULL,
+    detail TEXT NOT NULL,
+    stock INTEGER NOT NULL,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    products_type_id INTEGER,
+    price_minor INTEGER NOT NULL DEFAULT 0,
+    currency TEXT NOT NULL DEFAULT 'THB',
+    FOREIGN KEY(products_type_id) REFERENCES products_type(id) ON DELETE SET NULL
+);
+
+INSERT INTO products_new (id, name_products, detail, stock, created_at, products_type_id, price_minor, currency)
+SELECT id, name_products, detail, stock, created_at, products_type_id, price_minor, currency
+FROM products;
+
+DROP TABLE products;
+ALTER TABLE products_new RENAME TO products;
+
+CREATE TABLE images_new(
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    image_path TEXT NOT NULL,
+    product_id INTEGER,
+    product_type_id INTEGER,
+    FOREIGN KEY(product_id) REFERENCES products(id) ON DELETE CASCADE,
+    FOREIGN KEY(product_type_id) REFERENCES products_type(id) ON DELETE CASCADE
+);
+
+INSERT INTO images_new (id, image_path, product_id, product_type_id)
+SELECT id, image_path, product_id, product_type_id
+FROM images;
+
+DROP TABLE images;
+ALTER TABLE images_new RENAME TO images;
+
+CREATE INDEX idx_images_product_id ON images(product_id);
+CREATE INDEX idx_images_product_type_id ON images(product_type_id);
+CREATE INDEX idx_products_products_type_id ON products(products_type_id);
+
+-- order_data ในฐานข้อมูลเดิมอ้างถึงตาราง products_old ที่ไม่มีอยู่แล้ว
+CREATE TABLE order_data_new(
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    products_id INTEGER NOT NULL,
+    personal_data_id INTEGER NOT NULL,
+    FOREIGN KEY(products_id) REFERENCES products(id),
+    FOREIGN KEY(personal_data_id) REFERENCES personal_data(id)
+);
+
+INSERT INTO order_data_new (id, products_id, personal_data_id)
+SELECT id, products_id, personal_data_id
+FROM order_data;
+
+DROP TABLE order_data;
+ALTER TABLE order_data_new RENAME TO order_data;
@@ -0,0 +1,6 @@
+-- ถังขยะ: แถวที่มี deleted_at ถูกซ่อนจากรายการ กู้คืนได้จนกว่าจะถูก purge
+ALTER TABLE products ADD COLUMN deleted_at DATETIME;
+ALTER TABLE products_type ADD COLUMN deleted_at DATETIME;
+
+CREATE INDEX idx_products_deleted_at ON products(deleted_at);
+CREATE INDEX idx_products_type_deleted_at ON products_type(deleted_at);
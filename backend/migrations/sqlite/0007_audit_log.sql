@@ -0,0 +1,14 @@
+-- ประวัติการแก้ไขแคตตาล็อก เขียนใน transaction เดียวกับการแก้ไขข้อมูล
+CREATE TABLE audit_log(
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    actor TEXT NOT NULL,
+    action TEXT NOT NULL,
+    entity_type TEXT NOT NULL,
+    entity_id INTEGER NOT NULL,
+    before_data TEXT,
+    after_data TEXT,
+    created_at DATETIME NOT NULL
+);
+
+CREATE INDEX idx_audit_log_entity ON audit_log(entity_type, entity_id);
+CREATE INDEX idx_audit_log_created_at ON audit_log(created_at);
@@ -0,0 +1,58 @@
+-- ดัชนีค้นหาสินค้าแบบ full-text (FTS5) rowid = products.id
+-- ข้อมูลในดัชนีมาจาก view ด้านล่าง และถูกอัปเดตด้วย trigger ทุกครั้งที่สินค้าหรือชื่อประเภทเปลี่ยน
+CREATE VIRTUAL TABLE products_fts USING fts5(
+    name,
+    type_name,
+    detail,
+    tokenize = 'unicode61 remove_diacritics 2'
+);
+
+-- detail ถูกแปลงเป็นข้อความจากค่าทุกตัวใน JSON (ไม่รวมชื่อ key)
+CREATE VIEW products_search_source AS
+SELECT
+    p.id,
+    p.name_products AS name,
+    COALESCE(pt.products_type_name, '') AS type_name,
+    CASE
+        WHEN json_valid(p.detail) THEN (
+            SELECT COALESCE(group_concat(value, ' '), '')
+            FROM json_tree(p.detail)
+            WHERE type NOT IN ('object', 'array')
+        )
+        ELSE COALESCE(p.detail, '')
+    END AS detail
+FROM products p
+LEFT JOIN products_type pt ON pt.id = p.products_type_id;
+
+CREATE TRIGGER products_fts_insert AFTER INSERT ON products
+BEGIN
+    INSERT INTO products_fts (rowid, name, type_name, detail)
+    SELECT id, name, type_name, detail FROM products_search_source WHERE id = NEW.id;
+END;
+
+-- ON DELETE SET NULL ของ products_type_id ก็ทำให้ trigger นี้ทำงานด้วย
+CREATE TRIGGER products_fts_update AFTER UPDATE OF name_products, detail, products_type_id ON products
+BEGIN
+    DELETE FROM products_fts WHERE rowid = OLD.id;
+    INSERT INTO products_fts (rowid, name, type_name, detail)
+    SELECT id, name, type_name, detail FROM products_search_source WHERE id = NEW.id;
+END;
+
+CREATE TRIGGER products_fts_delete AFTER DELETE ON products
+BEGIN
+    DELETE FROM products_fts WHERE rowid = OLD.id;
+END;
+
+CREATE TRIGGER products_type_fts_update AFTER UPDATE OF products_type_name ON products_type
+BEGIN
+    DELETE FROM products_fts
+    WHERE rowid IN (SELECT id FROM products WHERE products_type_id = NEW.id);
+    INSERT INTO products_fts (rowid, name, type_name, detail)
+    SELECT s.id, s.name, s.type_name, s.detail
+    FROM products_search_source s
+    JOIN products p ON p.id = s.id
+    WHERE p.products_type_id = NEW.id;
+END;
+
+INSERT INTO products_fts (rowid, name, type_name, detail)
+SELECT id, name, type_name, detail FROM products_search_source;
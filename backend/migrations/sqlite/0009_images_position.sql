@@ -0,0 +1,4 @@
+-- ลำดับรูปของสินค้า เรียงตาม position แล้วตาม id (รูปเดิมเป็น 0 ทั้งหมด จึงยังเรียงตามลำดับที่เพิ่ม)
+ALTER TABLE images ADD COLUMN position INTEGER NOT NULL DEFAULT 0;
+
+CREATE INDEX idx_images_product_position ON images(product_id, position, id);
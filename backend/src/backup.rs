@@ -0,0 +1,653 @@
+use crate::config::Config;
+use crate::db::{DbPool, init_db, schema_version};
+use crate::models::BackupInfo;
+use crate::repository::now;
+use chrono::NaiveDateTime;
+use flate2::Compression;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs::{self, File, TryLockError};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use tokio::sync::Mutex;
+
+// ไฟล์ backup คือ tar.gz ที่มี
+//   database.db    สำเนาฐานข้อมูลจาก online backup API ของ SQLite (ไม่ต้องปิด server)
+//   dbimages/...   รูปภาพทั้งหมดใน images_dir
+//   manifest.json  ขนาดและ sha256 ของทุกไฟล์ด้านบน (อยู่ท้าย archive)
+const MANIFEST_ENTRY: &str = "manifest.json";
+const DATABASE_ENTRY: &str = "database.db";
+const IMAGES_ENTRY: &str = "dbimages";
+const MANIFEST_FORMAT: u32 = 1;
+
+const ARCHIVE_PREFIX: &str = "shop-backup-";
+const ARCHIVE_SUFFIX: &str = ".tar.gz";
+const TIMESTAMP_FORMAT: &str = "%Y%m%d-%H%M%S";
+
+// backup สร้างได้ครั้งละหนึ่งไฟล์ ทั้งจาก endpoint และจากคำสั่ง
+static BACKUP_LOCK: Mutex<()> = Mutex::const_new(());
+
+// online backup และ restore ใช้ได้เฉพาะ build ที่ใช้ SQLite (Postgres ใช้ pg_dump แทน)
+pub const SUPPORTED: bool = cfg!(feature = "sqlite");
+
+#[derive(Serialize, Deserialize)]
+struct Manifest {
+    format: u32,
+    created_at: NaiveDateTime,
+    schema_version: i64,
+    files: Vec<ManifestFile>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ManifestFile {
+    path: String,
+    size: u64,
+    sha256: String,
+}
+
+// อ่านไฟล์ทีละส่วนเพื่อหาขนาดและ sha256 โดยไม่โหลดทั้งไฟล์เข้าหน่วยความจำ
+fn file_checksum(path: &Path) -> io::Result<(u64, String)> {
+    let mut hasher = Sha256::new();
+    let size = io::copy(&mut File::open(path)?, &mut hasher)?;
+    Ok((size, format!("{:x}", hasher.finalize())))
+}
+
+// ส่งข้อมูลต่อให้ tar พร้อมนับขนาดและคำนวณ sha256 ของข้อมูลที่ถูกเขียนลง archive จริง
+struct HashingReader<R> {
+    inner: R,
+    hasher: Sha256,
+    size: u64,
+}
+
+impl<R: Read> Read for HashingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.hasher.update(&buf[..read]);
+        self.size += read as u64;
+        Ok(read)
+    }
+}
+
+// shop-backup-20240501-093000.tar.gz -> 2024-05-01 09:30:00
+fn archive_created_at(file_name: &str) -> Option<NaiveDateTime> {
+    let stamp = file_name
+        .strip_prefix(ARCHIVE_PREFIX)?
+        .strip_suffix(ARCHIVE_SUFFIX)?;
+    NaiveDateTime::parse_from_str(stamp, TIMESTAMP_FORMAT).ok()
+}
+
+// ไฟล์ backup ใน backup_dir เรียงจากใหม่ไปเก่า
+pub fn list_backups(config: &Config) -> Result<Vec<BackupInfo>, String> {
+    if !config.backup_dir.exists() {
+        return Ok(Vec::new());
+    }
+    let entries = fs::read_dir(&config.backup_dir)
+        .map_err(|e| format!("Failed to read {}: {}", config.backup_dir.display(), e))?;
+
+    let mut backups = Vec::new();
+    for entry in entries.flatten() {
+        let file = entry.file_name().to_string_lossy().to_string();
+        let Some(created_at) = archive_created_at(&file) else {
+            continue;
+        };
+        let size = entry.metadata().map(|m| m.len()).unwrap_or(0);
+        backups.push(BackupInfo {
+            file,
+            size,
+            created_at,
+        });
+    }
+    backups.sort_by_key(|backup| std::cmp::Reverse(backup.created_at));
+    Ok(backups)
+}
+
+// เก็บเฉพาะ backup ล่าสุด backup_keep ไฟล์ (0 = เก็บทั้งหมด)
+fn apply_retention(config: &Config) -> Result<(), String> {
+    if config.backup_keep == 0 {
+        return Ok(());
+    }
+    for old in list_backups(config)?
+        .iter()
+        .skip(config.backup_keep as usize)
+    {
+        let path = config.backup_dir.join(&old.file);
+        match fs::remove_file(&path) {
+            Ok(()) => println!("🗑️ Removed old backup {}", old.file),
+            Err(e) => eprintln!("⚠️ Failed to remove old backup {}: {}", path.display(), e),
+        }
+    }
+    Ok(())
+}
+
+// คัดลอกฐานข้อมูลด้วย sqlite3_backup_* ระหว่างที่ connection ใน pool ยังอ่านเขียนได้ตามปกติ
+// ใช้ connection ของตัวเองและทำใน thread สำหรับงาน blocking เพราะต้องรอ lock ด้วย sqlite3_sleep
+#[cfg(feature = "sqlite")]
+async fn copy_database(config: &Config, dest: &Path) -> Result<(), String> {
+    let source = crate::db::database_file(config)?;
+    let dest = dest.to_path_buf();
+    actix_web::web::block(move || copy_database_file(&source, &dest))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+#[cfg(feature = "sqlite")]
+fn copy_database_file(source: &Path, dest: &Path) -> Result<(), String> {
+    use libsqlite3_sys as ffi;
+    use std::ffi::{CStr, CString};
+
+    let c_path = |path: &Path| {
+        path.to_str()
+            .and_then(|path| CString::new(path).ok())
+            .ok_or_else(|| format!("Invalid database path: {}", path.display()))
+    };
+    let source_path = c_path(source)?;
+    let dest_path = c_path(dest)?;
+
+    // SAFETY: ทั้ง source และ target เปิด/ปิดภายในบล็อกนี้เท่านั้น
+    unsafe {
+        let error_message = |db: *mut ffi::sqlite3| {
+            CStr::from_ptr(ffi::sqlite3_errmsg(db))
+                .to_string_lossy()
+                .to_string()
+        };
+
+        let mut source = std::ptr::null_mut();
+        if ffi::sqlite3_open_v2(
+            source_path.as_ptr(),
+            &mut source,
+            ffi::SQLITE_OPEN_READONLY,
+            std::ptr::null(),
+        ) != ffi::SQLITE_OK
+        {
+            let message = error_message(source);
+            ffi::sqlite3_close(source);
+            return Err(format!("Failed to open database: {}", message));
+        }
+
+        let mut target = std::ptr::null_mut();
+        if ffi::sqlite3_open(dest_path.as_ptr(), &mut target) != ffi::SQLITE_OK {
+            let message = error_message(target);
+            ffi::sqlite3_close(target);
+            ffi::sqlite3_close(source);
+            return Err(format!("Failed to open backup database: {}", message));
+        }
+
+        let backup = ffi::sqlite3_backup_init(target, c"main".as_ptr(), source, c"main".as_ptr());
+        if backup.is_null() {
+            let message = error_message(target);
+            ffi::sqlite3_close(target);
+            ffi::sqlite3_close(source);
+            return Err(format!("Failed to start backup: {}", message));
+        }
+
+        // -1 = คัดลอกทุกหน้าในรอบเดียว ถ้ามีคนถือ lock อยู่ให้รอแล้วลองใหม่
+        let mut step = ffi::sqlite3_backup_step(backup, -1);
+        for _ in 0..50 {
+            if step != ffi::SQLITE_BUSY && step != ffi::SQLITE_LOCKED {
+                break;
+            }
+            ffi::sqlite3_sleep(100);
+            step = ffi::sqlite3_backup_step(backup, -1);
+        }
+        ffi::sqlite3_backup_finish(backup);
+
+        let result = if step == ffi::SQLITE_DONE {
+            Ok(())
+        } else {
+            Err(format!("Backup failed: {}", error_message(target)))
+        };
+        ffi::sqlite3_close(target);
+        ffi::sqlite3_close(source);
+        result
+    }
+}
+
+#[cfg(feature = "postgres")]
+async fn copy_database(_config: &Config, _dest: &Path) -> Result<(), String> {
+    Err("Online backup is only supported for SQLite, use pg_dump for PostgreSQL".to_string())
+}
+
+// ไฟล์ทั้งหมดใต้ dir เป็นคู่ (path ใน archive, path บน disk) เรียงตามชื่อ
+fn collect_files(dir: &Path, prefix: &str, out: &mut Vec<(String, PathBuf)>) -> Result<(), String> {
+    let mut entries: Vec<_> = fs::read_dir(dir)
+        .map_err(|e| format!("Failed to read {}: {}", dir.display(), e))?
+        .flatten()
+        .collect();
+    entries.sort_by_key(|entry| entry.file_name());
+
+    for entry in entries {
+        let name = format!("{}/{}", prefix, entry.file_name().to_string_lossy());
+        let path = entry.path();
+        if path.is_dir() {
+            collect_files(&path, &name, out)?;
+        } else if path.is_file() {
+            out.push((name, path));
+        }
+    }
+    Ok(())
+}
+
+// เขียน archive เป็นไฟล์ .partial ก่อนแล้วค่อยเปลี่ยนชื่อ ไฟล์ที่ไม่สมบูรณ์จึงไม่ถูกนับเป็น backup
+// แต่ละไฟล์ถูกอ่านทีละส่วนไปลง archive และคำนวณ checksum จากข้อมูลชุดเดียวกับที่เขียน
+// manifest จึงตรงกับ archive เสมอ ถ้ารูปถูกแก้จนขนาดเปลี่ยนระหว่าง backup จะล้มเหลวแทนการได้ archive ที่เสีย
+fn write_archive(
+    database: &Path,
+    images_dir: &Path,
+    archive: &Path,
+    mut manifest: Manifest,
+) -> Result<(), String> {
+    let mut files = vec![(DATABASE_ENTRY.to_string(), database.to_path_buf())];
+    if images_dir.exists() {
+        collect_files(images_dir, IMAGES_ENTRY, &mut files)?;
+    }
+
+    let partial = PathBuf::from(format!("{}.partial", archive.display()));
+    let mut write = || -> io::Result<()> {
+        let encoder = GzEncoder::new(File::create(&partial)?, Compression::default());
+        let mut builder = tar::Builder::new(encoder);
+
+        for (name, path) in &files {
+            let file = File::open(path)?;
+            let size = file.metadata()?.len();
+            let mut reader = HashingReader {
+                inner: file.take(size),
+                hasher: Sha256::new(),
+                size: 0,
+            };
+            append_entry(&mut builder, name, size, &mut reader)?;
+            if reader.size != size {
+                return Err(io::Error::other(format!(
+                    "{} changed during backup",
+                    path.display()
+                )));
+            }
+            manifest.files.push(ManifestFile {
+                path: name.clone(),
+                size,
+                sha256: format!("{:x}", reader.hasher.finalize()),
+            });
+        }
+
+        let manifest_json = serde_json::to_vec_pretty(&manifest)?;
+        append_entry(
+            &mut builder,
+            MANIFEST_ENTRY,
+            manifest_json.len() as u64,
+            manifest_json.as_slice(),
+        )?;
+
+        builder.into_inner()?.finish()?.sync_all()
+    };
+
+    if let Err(e) = write() {
+        let _ = fs::remove_file(&partial);
+        return Err(format!("Failed to write {}: {}", archive.display(), e));
+    }
+    fs::rename(&partial, archive).map_err(|e| format!("Failed to finish backup: {}", e))
+}
+
+fn append_entry<W: io::Write>(
+    builder: &mut tar::Builder<W>,
+    name: &str,
+    size: u64,
+    data: impl Read,
+) -> io::Result<()> {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(size);
+    header.set_mode(0o644);
+    header.set_mtime(chrono::Utc::now().timestamp().max(0) as u64);
+    header.set_cksum();
+    builder.append_data(&mut header, name, data)
+}
+
+// สร้าง backup ใหม่ใน backup_dir แล้วลบ backup เก่าตาม backup_keep
+pub async fn create_backup(pool: &DbPool, config: &Config) -> Result<BackupInfo, String> {
+    let _guard = BACKUP_LOCK.lock().await;
+
+    let created_at = now();
+    let file = format!(
+        "{}{}{}",
+        ARCHIVE_PREFIX,
+        created_at.format(TIMESTAMP_FORMAT),
+        ARCHIVE_SUFFIX
+    );
+    let archive = config.backup_dir.join(&file);
+    if archive.exists() {
+        return Err(format!("Backup {} already exists, try again", file));
+    }
+
+    let staging = config.backup_dir.join(format!(".{}.tmp", file));
+    fs::create_dir_all(&staging)
+        .map_err(|e| format!("Failed to create {}: {}", staging.display(), e))?;
+
+    let result = async {
+        let database = staging.join(DATABASE_ENTRY);
+        copy_database(config, &database).await?;
+
+        let manifest = Manifest {
+            format: MANIFEST_FORMAT,
+            created_at,
+            schema_version: schema_version(pool).await.map_err(|e| e.to_string())?,
+            files: Vec::new(),
+        };
+        let images_dir = config.images_dir.clone();
+        let target = archive.clone();
+        actix_web::web::block(move || write_archive(&database, &images_dir, &target, manifest))
+            .await
+            .map_err(|e| e.to_string())?
+    }
+    .await;
+
+    if let Err(e) = fs::remove_dir_all(&staging) {
+        eprintln!("⚠️ Failed to remove folder {}: {}", staging.display(), e);
+    }
+    result?;
+
+    let size = fs::metadata(&archive).map(|m| m.len()).unwrap_or(0);
+    println!("💾 Created backup {} ({} bytes)", file, size);
+    apply_retention(config)?;
+
+    Ok(BackupInfo {
+        file,
+        size,
+        created_at,
+    })
+}
+
+// แตก archive ลง dir โดยรับเฉพาะไฟล์และโฟลเดอร์ธรรมดา (ไม่รับ symlink หรือ path ที่ออกนอก dir)
+fn unpack_archive(archive: &Path, dir: &Path) -> Result<(), String> {
+    let file =
+        File::open(archive).map_err(|e| format!("Failed to open {}: {}", archive.display(), e))?;
+    let mut reader = tar::Archive::new(GzDecoder::new(file));
+    let entries = reader
+        .entries()
+        .map_err(|e| format!("Invalid archive: {}", e))?;
+
+    for entry in entries {
+        let mut entry = entry.map_err(|e| format!("Invalid archive: {}", e))?;
+        let kind = entry.header().entry_type();
+        if !(kind.is_file() || kind.is_dir()) {
+            return Err(format!(
+                "Unexpected entry in archive: {}",
+                entry
+                    .path()
+                    .map(|p| p.display().to_string())
+                    .unwrap_or_default()
+            ));
+        }
+        let unpacked = entry
+            .unpack_in(dir)
+            .map_err(|e| format!("Failed to unpack archive: {}", e))?;
+        if !unpacked {
+            return Err("Archive contains a path outside the backup".to_string());
+        }
+    }
+    Ok(())
+}
+
+// ตรวจว่าไฟล์ที่แตกออกมาตรงกับ manifest ทุกไฟล์ และไม่มีไฟล์อื่นปน
+fn verify_manifest(dir: &Path) -> Result<Manifest, String> {
+    let manifest_text = fs::read(dir.join(MANIFEST_ENTRY))
+        .map_err(|_| "Archive has no manifest.json".to_string())?;
+    let manifest: Manifest =
+        serde_json::from_slice(&manifest_text).map_err(|e| format!("Invalid manifest: {}", e))?;
+    if manifest.format != MANIFEST_FORMAT {
+        return Err(format!("Unsupported backup format {}", manifest.format));
+    }
+
+    for file in &manifest.files {
+        let (size, sha256) = file_checksum(&dir.join(&file.path))
+            .map_err(|_| format!("Missing file in archive: {}", file.path))?;
+        if size != file.size || sha256 != file.sha256 {
+            return Err(format!("Checksum mismatch: {}", file.path));
+        }
+    }
+
+    let mut found = Vec::new();
+    collect_files(dir, "", &mut found)?;
+    for (name, _) in found {
+        let name = name.trim_start_matches('/');
+        if name != MANIFEST_ENTRY && !manifest.files.iter().any(|f| f.path == name) {
+            return Err(format!("File not listed in manifest: {}", name));
+        }
+    }
+
+    if !manifest.files.iter().any(|f| f.path == DATABASE_ENTRY) {
+        return Err("Archive has no database".to_string());
+    }
+    Ok(manifest)
+}
+
+// ฐานข้อมูลใน backup ต้องผ่าน integrity_check และ schema ต้องไม่ใหม่กว่าที่ไบนารีนี้รู้จัก
+#[cfg(feature = "sqlite")]
+async fn verify_database(path: &Path) -> Result<i64, String> {
+    use crate::db::latest_version;
+    use sqlx::Connection;
+
+    let options = sqlx::sqlite::SqliteConnectOptions::new()
+        .filename(path)
+        .read_only(true);
+    let mut conn = sqlx::SqliteConnection::connect_with(&options)
+        .await
+        .map_err(|e| format!("Failed to open backup database: {}", e))?;
+
+    let integrity: String = sqlx::query_scalar("PRAGMA integrity_check")
+        .fetch_one(&mut conn)
+        .await
+        .map_err(|e| format!("Failed to check backup database: {}", e))?;
+    if integrity != "ok" {
+        return Err(format!("Backup database is corrupt: {}", integrity));
+    }
+
+    let version = schema_version(&mut conn)
+        .await
+        .map_err(|_| "Backup database has no migration history".to_string())?;
+    let _ = conn.close().await;
+
+    if version > latest_version() {
+        return Err(format!(
+            "Backup schema version {} is newer than this build ({})",
+            version,
+            latest_version()
+        ));
+    }
+    Ok(version)
+}
+
+#[cfg(feature = "postgres")]
+async fn verify_database(_path: &Path) -> Result<i64, String> {
+    Err("Restore is only supported for SQLite, use pg_restore for PostgreSQL".to_string())
+}
+
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    PathBuf::from(format!("{}{}", path.display(), suffix))
+}
+
+// ย้ายของเดิมไปไว้ข้าง ๆ (*.before-restore-<เวลา>) แล้วย้ายของจาก backup เข้าแทน
+// ถ้าย้ายไม่สำเร็จกลางทาง ย้ายของเดิมกลับคืนทั้งหมด
+fn swap_in(moves: &[(PathBuf, PathBuf)], suffix: &str) -> Result<Vec<PathBuf>, String> {
+    let mut set_aside: Vec<(PathBuf, PathBuf)> = Vec::new();
+    let mut placed: Vec<PathBuf> = Vec::new();
+
+    let result = (|| {
+        for (_, target) in moves {
+            if target.exists() {
+                let aside = with_suffix(target, suffix);
+                fs::rename(target, &aside)
+                    .map_err(|e| format!("Failed to move {}: {}", target.display(), e))?;
+                set_aside.push((target.clone(), aside));
+            }
+        }
+        for (source, target) in moves {
+            fs::rename(source, target)
+                .map_err(|e| format!("Failed to move {}: {}", target.display(), e))?;
+            placed.push(target.clone());
+        }
+        Ok(())
+    })();
+
+    if let Err(e) = result {
+        for target in &placed {
+            let _ = fs::remove_dir_all(target).or_else(|_| fs::remove_file(target));
+        }
+        for (target, aside) in &set_aside {
+            let _ = fs::rename(aside, target);
+        }
+        return Err(e);
+    }
+    Ok(set_aside.into_iter().map(|(_, aside)| aside).collect())
+}
+
+// ไฟล์ <ฐานข้อมูล>.lock ข้างไฟล์ฐานข้อมูล: server ถือ shared lock ตลอดเวลาที่ทำงาน
+// restore ต้องได้ exclusive lock จึงทำงานไม่ได้ระหว่างที่มี server ใช้ฐานข้อมูลนี้อยู่
+pub fn lock_database(config: &Config, exclusive: bool) -> Result<File, String> {
+    let path = with_suffix(&crate::db::database_file(config)?, ".lock");
+    let file = File::options()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(&path)
+        .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    let locked = if exclusive {
+        file.try_lock()
+    } else {
+        file.try_lock_shared()
+    };
+    match locked {
+        Ok(()) => Ok(file),
+        Err(TryLockError::WouldBlock) if exclusive => {
+            Err("Database is in use by a running server, stop it before restoring".to_string())
+        }
+        Err(TryLockError::WouldBlock) => {
+            Err("Database is being restored, try again when it finishes".to_string())
+        }
+        Err(TryLockError::Error(e)) => Err(format!("Failed to lock {}: {}", path.display(), e)),
+    }
+}
+
+// กู้คืนฐานข้อมูลและรูปภาพจาก archive ต้องปิด server ก่อน (ดู lock_database)
+// migration ที่ backup ยังไม่มีจะถูก apply ตอนเปิด server ครั้งถัดไป
+pub async fn restore_backup(config: &Config, archive: &Path) -> Result<(), String> {
+    let _lock = lock_database(config, true)?;
+    let database = crate::db::database_file(config)?;
+    let parent = database
+        .parent()
+        .filter(|p| !p.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    let stamp = now().format(TIMESTAMP_FORMAT).to_string();
+
+    // แตกไฟล์ไว้ข้างฐานข้อมูลเพื่อให้ย้ายเข้าที่ด้วย rename ได้
+    let staging = parent.join(format!(".restore-{}", stamp));
+    fs::create_dir_all(&staging)
+        .map_err(|e| format!("Failed to create {}: {}", staging.display(), e))?;
+
+    let result = async {
+        unpack_archive(archive, &staging)?;
+        let manifest = verify_manifest(&staging)?;
+        let version = verify_database(&staging.join(DATABASE_ENTRY)).await?;
+        println!(
+            "✅ Verified backup from {} ({} files, schema version {})",
+            manifest.created_at,
+            manifest.files.len(),
+            version
+        );
+
+        let images = staging.join(IMAGES_ENTRY);
+        if !images.exists() {
+            fs::create_dir_all(&images).map_err(|e| e.to_string())?;
+        }
+
+        // ไฟล์ -wal / -shm ของฐานข้อมูลเดิมต้องย้ายออกไปด้วย ไม่อย่างนั้น SQLite จะนำมาใช้กับไฟล์ใหม่
+        let suffix = format!(".before-restore-{}", stamp);
+        for sidecar in ["-wal", "-shm"] {
+            let path = with_suffix(&database, sidecar);
+            if path.exists() {
+                fs::rename(&path, with_suffix(&path, &suffix))
+                    .map_err(|e| format!("Failed to move {}: {}", path.display(), e))?;
+            }
+        }
+
+        let moves = [
+            (staging.join(DATABASE_ENTRY), database.clone()),
+            (images, config.images_dir.clone()),
+        ];
+        for aside in swap_in(&moves, &suffix)? {
+            println!("📦 Previous data kept at {}", aside.display());
+        }
+        Ok(())
+    }
+    .await;
+
+    if let Err(e) = fs::remove_dir_all(&staging) {
+        eprintln!("⚠️ Failed to remove folder {}: {}", staging.display(), e);
+    }
+    result
+}
+
+const USAGE: &str = concat!(
+    "Usage: backend [backup | backups | restore <archive>]\n",
+    "       backend import <file> [--dry-run] [--create-types] [--format csv|ndjson]"
+);
+
+// คำสั่งบรรทัดคำสั่ง (ไม่มีคำสั่ง = เปิด server ตามปกติ)
+pub async fn run_command(config: &Config, args: &[String]) -> Result<(), String> {
+    match args {
+        [command] if command == "backup" => {
+            let pool = init_db(config).await;
+            let info = create_backup(&pool, config).await?;
+            println!("{}", config.backup_dir.join(info.file).display());
+            Ok(())
+        }
+        [command] if command == "backups" => {
+            for backup in list_backups(config)? {
+                println!("{}\t{}\t{}", backup.created_at, backup.size, backup.file);
+            }
+            Ok(())
+        }
+        [command, archive] if command == "restore" => {
+            restore_backup(config, Path::new(archive)).await?;
+            println!("✅ Restored {}", archive);
+            Ok(())
+        }
+        _ => Err(USAGE.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn archive_round_trips_through_manifest_check() {
+        let root = std::env::temp_dir().join(format!("shop-backup-test-{}", std::process::id()));
+        let images = root.join("images");
+        fs::create_dir_all(images.join("game/main")).unwrap();
+        fs::write(root.join("shop.db"), b"database").unwrap();
+        fs::write(images.join("game/main/game_0.jpg"), vec![7u8; 100_000]).unwrap();
+
+        let archive = root.join("backup.tar.gz");
+        let manifest = Manifest {
+            format: MANIFEST_FORMAT,
+            created_at: now(),
+            schema_version: 1,
+            files: Vec::new(),
+        };
+        write_archive(&root.join("shop.db"), &images, &archive, manifest).unwrap();
+
+        let unpacked = root.join("unpacked");
+        fs::create_dir_all(&unpacked).unwrap();
+        unpack_archive(&archive, &unpacked).unwrap();
+        let manifest = verify_manifest(&unpacked).unwrap();
+        let paths: Vec<&str> = manifest.files.iter().map(|f| f.path.as_str()).collect();
+        assert_eq!(paths, [DATABASE_ENTRY, "dbimages/game/main/game_0.jpg"]);
+        assert_eq!(manifest.files[1].size, 100_000);
+
+        // ไฟล์ที่ไม่ตรงกับ checksum ใน manifest ต้องไม่ผ่าน
+        fs::write(unpacked.join("dbimages/game/main/game_0.jpg"), b"changed").unwrap();
+        assert!(verify_manifest(&unpacked).is_err());
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
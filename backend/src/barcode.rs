@@ -0,0 +1,330 @@
+use crate::models::BarcodeFormat;
+use std::fmt::Write;
+
+// barcode ที่รองรับ
+//   ean13    ตัวเลข 13 หลัก หลักสุดท้ายเป็น check digit
+//   upca     ตัวเลข 12 หลัก หลักสุดท้ายเป็น check digit (คือ EAN-13 ที่ขึ้นต้นด้วย 0)
+//   code128  ASCII ที่พิมพ์ได้ 1-48 ตัว check symbol คำนวณตอนวาด ไม่ได้อยู่ในข้อความ
+const MAX_CODE128_CHARS: usize = 48;
+
+// ความกว้างเป็นจำนวนแท่งที่บางที่สุด (module) ขอบขาวซ้ายขวาตามที่ EAN-13 กำหนด (ใช้กับทุกแบบ)
+const QUIET_ZONE: usize = 11;
+const BAR_HEIGHT: usize = 60;
+// พื้นที่ใต้แท่งสำหรับข้อความ (เฉพาะ SVG)
+const TEXT_HEIGHT: usize = 12;
+pub const MAX_SCALE: u32 = 10;
+pub const DEFAULT_SCALE: u32 = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ImageFormat {
+    Svg,
+    Png,
+}
+
+impl ImageFormat {
+    pub fn from_name(name: &str) -> Option<ImageFormat> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "svg" => Some(ImageFormat::Svg),
+            "png" => Some(ImageFormat::Png),
+            _ => None,
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            ImageFormat::Svg => "image/svg+xml",
+            ImageFormat::Png => "image/png",
+        }
+    }
+}
+
+// รูปแบบเมื่อไม่ได้ระบุ barcode_format
+pub fn detect_format(code: &str) -> BarcodeFormat {
+    match (code.len(), is_digits(code)) {
+        (13, true) => BarcodeFormat::Ean13,
+        (12, true) => BarcodeFormat::Upca,
+        _ => BarcodeFormat::Code128,
+    }
+}
+
+pub fn barcode_error(format: BarcodeFormat, code: &str) -> Option<String> {
+    let (name, length) = match format {
+        BarcodeFormat::Ean13 => ("EAN-13", 13),
+        BarcodeFormat::Upca => ("UPC-A", 12),
+        BarcodeFormat::Code128 => {
+            if code.is_empty()
+                || code.len() > MAX_CODE128_CHARS
+                || !code.bytes().all(|b| (b' '..=b'~').contains(&b))
+            {
+                return Some(format!(
+                    "Code 128 barcode must be 1-{} printable ASCII characters",
+                    MAX_CODE128_CHARS
+                ));
+            }
+            return None;
+        }
+    };
+    if code.len() != length || !is_digits(code) {
+        return Some(format!("{} barcode must be {} digits", name, length));
+    }
+    let digits = code.as_bytes();
+    let expected = check_digit(&digits[..length - 1]);
+    if digits[length - 1] != expected {
+        return Some(format!(
+            "Invalid {} check digit, expected {}",
+            name, expected as char
+        ));
+    }
+    None
+}
+
+// ค่าที่สแกนได้จากฉลากเดียวกัน: เครื่องสแกนบางรุ่นอ่าน UPC-A เป็น EAN-13 ที่มี 0 นำหน้า
+pub fn equivalent_codes(code: &str) -> Vec<String> {
+    let mut codes = vec![code.to_string()];
+    if is_digits(code) {
+        match code.len() {
+            12 => codes.push(format!("0{}", code)),
+            13 if code.starts_with('0') => codes.push(code[1..].to_string()),
+            _ => {}
+        }
+    }
+    codes
+}
+
+fn is_digits(code: &str) -> bool {
+    !code.is_empty() && code.bytes().all(|b| b.is_ascii_digit())
+}
+
+// check digit ของ EAN/UPC: นับจากหลักขวาสุด น้ำหนัก 3, 1, 3, ... (ASCII)
+fn check_digit(digits: &[u8]) -> u8 {
+    let sum: u32 = digits
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, d)| u32::from(d - b'0') * if i % 2 == 0 { 3 } else { 1 })
+        .sum();
+    b'0' + ((10 - sum % 10) % 10) as u8
+}
+
+// แท่งของ EAN-13 ฝั่งซ้ายแบบ L (ฝั่งขวาคือกลับสีของ L, แบบ G คือ R กลับด้าน)
+const EAN_L: [&str; 10] = [
+    "0001101", "0011001", "0010011", "0111101", "0100011", "0110001", "0101111", "0111011",
+    "0110111", "0001011",
+];
+// แบบ L/G ของ 6 หลักซ้ายตามหลักแรก (1 = G)
+const EAN_PARITY: [&str; 10] = [
+    "000000", "001011", "001101", "001110", "010011", "011001", "011100", "010101", "010110",
+    "011010",
+];
+
+fn ean13_modules(code: &str) -> Vec<bool> {
+    let digits: Vec<usize> = code.bytes().map(|b| (b - b'0') as usize).collect();
+    let l = |d: usize| EAN_L[d].bytes().map(|b| b == b'1').collect::<Vec<_>>();
+    let r = |d: usize| l(d).into_iter().map(|m| !m).collect::<Vec<_>>();
+    let g = |d: usize| r(d).into_iter().rev().collect::<Vec<_>>();
+    let guard = |pattern: &str| pattern.bytes().map(|b| b == b'1').collect::<Vec<_>>();
+
+    let mut modules = guard("101");
+    for (i, parity) in EAN_PARITY[digits[0]].bytes().enumerate() {
+        let digit = digits[i + 1];
+        modules.extend(if parity == b'1' { g(digit) } else { l(digit) });
+    }
+    modules.extend(guard("01010"));
+    for digit in &digits[7..] {
+        modules.extend(r(*digit));
+    }
+    modules.extend(guard("101"));
+    modules
+}
+
+// ความกว้างของแท่ง/ช่องว่างสลับกัน (เริ่มด้วยแท่ง) ของ symbol 0-105 และ stop (106)
+const CODE128_PATTERNS: [&str; 107] = [
+    "212222", "222122", "222221", "121223", "121322", "131222", "122213", "122312", "132212",
+    "221213", "221312", "231212", "112232", "122132", "122231", "113222", "123122", "123221",
+    "223211", "221132", "221231", "213212", "223112", "312131", "311222", "321122", "321221",
+    "312212", "322112", "322211", "212123", "212321", "232121", "111323", "131123", "131321",
+    "112313", "132113", "132311", "211313", "231113", "231311", "112133", "112331", "132131",
+    "113123", "113321", "133121", "313121", "211331", "231131", "213113", "213311", "213131",
+    "311123", "311321", "331121", "312113", "312311", "332111", "314111", "221411", "431111",
+    "111224", "111422", "121124", "121421", "141122", "141221", "112214", "112412", "122114",
+    "122411", "142112", "142211", "241211", "221114", "413111", "241112", "134111", "111242",
+    "121142", "121241", "114212", "124112", "124211", "411212", "421112", "421211", "212141",
+    "214121", "412121", "111143", "111341", "131141", "114113", "114311", "411113", "411311",
+    "113141", "114131", "311141", "411131", "211412", "211214", "211232", "2331112",
+];
+const CODE128_START_B: usize = 104;
+const CODE128_START_C: usize = 105;
+const CODE128_STOP: usize = 106;
+
+// ตัวเลขล้วนจำนวนคู่ใช้ code set C (สองหลักต่อ symbol) แบบอื่นใช้ code set B
+fn code128_symbols(code: &str) -> Vec<usize> {
+    let bytes = code.as_bytes();
+    let mut symbols = if is_digits(code) && bytes.len().is_multiple_of(2) && bytes.len() >= 4 {
+        let mut symbols = vec![CODE128_START_C];
+        symbols.extend(
+            bytes
+                .chunks(2)
+                .map(|pair| ((pair[0] - b'0') * 10 + (pair[1] - b'0')) as usize),
+        );
+        symbols
+    } else {
+        let mut symbols = vec![CODE128_START_B];
+        symbols.extend(bytes.iter().map(|b| (b - b' ') as usize));
+        symbols
+    };
+    let checksum = symbols
+        .iter()
+        .enumerate()
+        .map(|(i, symbol)| symbol * i.max(1))
+        .sum::<usize>()
+        % 103;
+    symbols.push(checksum);
+    symbols.push(CODE128_STOP);
+    symbols
+}
+
+fn code128_modules(code: &str) -> Vec<bool> {
+    let mut modules = Vec::new();
+    for symbol in code128_symbols(code) {
+        for (i, width) in CODE128_PATTERNS[symbol].bytes().enumerate() {
+            modules.extend(std::iter::repeat_n(i % 2 == 0, (width - b'0') as usize));
+        }
+    }
+    modules
+}
+
+// true = แท่งสีดำ ไม่รวมขอบขาว (code ต้องผ่าน barcode_error แล้ว)
+fn modules(format: BarcodeFormat, code: &str) -> Vec<bool> {
+    match format {
+        BarcodeFormat::Ean13 => ean13_modules(code),
+        BarcodeFormat::Upca => ean13_modules(&format!("0{}", code)),
+        BarcodeFormat::Code128 => code128_modules(code),
+    }
+}
+
+// SVG ขนาด module ละ scale pixel พร้อมข้อความใต้แท่ง
+pub fn svg(format: BarcodeFormat, code: &str, scale: u32) -> String {
+    let modules = modules(format, code);
+    let width = modules.len() + QUIET_ZONE * 2;
+    let height = BAR_HEIGHT + TEXT_HEIGHT;
+
+    let mut bars = String::new();
+    let mut x = 0;
+    while x < modules.len() {
+        let run = modules[x..]
+            .iter()
+            .take_while(|m| **m == modules[x])
+            .count();
+        if modules[x] {
+            let _ = write!(
+                bars,
+                "M{},0h{}v{}h-{}z",
+                x + QUIET_ZONE,
+                run,
+                BAR_HEIGHT,
+                run
+            );
+        }
+        x += run;
+    }
+
+    let text = code
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;");
+    format!(
+        concat!(
+            r##"<svg xmlns="http://www.w3.org/2000/svg" width="{pw}" height="{ph}" viewBox="0 0 {w} {h}" shape-rendering="crispEdges">"##,
+            r##"<rect width="{w}" height="{h}" fill="#fff"/>"##,
+            r##"<path d="{bars}" fill="#000"/>"##,
+            r##"<text x="{cx}" y="{ty}" font-family="monospace" font-size="10" text-anchor="middle" fill="#000">{text}</text>"##,
+            "</svg>\n"
+        ),
+        pw = width * scale as usize,
+        ph = height * scale as usize,
+        w = width,
+        h = height,
+        bars = bars,
+        cx = width as f64 / 2.0,
+        ty = BAR_HEIGHT + TEXT_HEIGHT - 2,
+        text = text,
+    )
+}
+
+// PNG ขาวดำ ขนาด module ละ scale pixel มีเฉพาะแท่ง (ไม่มีข้อความเพราะไม่มี font ฝั่ง server)
+pub fn png(format: BarcodeFormat, code: &str, scale: u32) -> Result<Vec<u8>, png::EncodingError> {
+    let scale = scale as usize;
+    let modules = modules(format, code);
+    let mut row = vec![0xffu8; (modules.len() + QUIET_ZONE * 2) * scale];
+    for (x, dark) in modules.iter().enumerate() {
+        if *dark {
+            let start = (x + QUIET_ZONE) * scale;
+            row[start..start + scale].fill(0);
+        }
+    }
+    let height = BAR_HEIGHT * scale;
+
+    let mut bytes = Vec::new();
+    let mut encoder = png::Encoder::new(&mut bytes, row.len() as u32, height as u32);
+    encoder.set_color(png::ColorType::Grayscale);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(&row.repeat(height))?;
+    writer.finish()?;
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_digit_matches_published_codes() {
+        assert_eq!(check_digit(b"400638133393"), b'1');
+        assert_eq!(check_digit(b"03600029145"), b'2');
+        assert_eq!(check_digit(b"978030640615"), b'7');
+    }
+
+    #[test]
+    fn barcode_error_checks_length_digits_and_check_digit() {
+        assert_eq!(barcode_error(BarcodeFormat::Ean13, "4006381333931"), None);
+        assert_eq!(barcode_error(BarcodeFormat::Upca, "036000291452"), None);
+        assert!(barcode_error(BarcodeFormat::Ean13, "4006381333932").is_some());
+        assert!(barcode_error(BarcodeFormat::Ean13, "400638133393").is_some());
+        assert!(barcode_error(BarcodeFormat::Upca, "03600029145a").is_some());
+
+        assert_eq!(barcode_error(BarcodeFormat::Code128, "SKU-001 a"), None);
+        assert!(barcode_error(BarcodeFormat::Code128, "").is_some());
+        assert!(barcode_error(BarcodeFormat::Code128, "สินค้า").is_some());
+        assert!(
+            barcode_error(BarcodeFormat::Code128, &"A".repeat(MAX_CODE128_CHARS + 1)).is_some()
+        );
+    }
+
+    #[test]
+    fn detect_format_and_equivalent_codes() {
+        assert_eq!(detect_format("4006381333931"), BarcodeFormat::Ean13);
+        assert_eq!(detect_format("036000291452"), BarcodeFormat::Upca);
+        assert_eq!(detect_format("SKU-001"), BarcodeFormat::Code128);
+        assert_eq!(
+            equivalent_codes("036000291452"),
+            ["036000291452", "0036000291452"]
+        );
+        assert_eq!(
+            equivalent_codes("0036000291452"),
+            ["0036000291452", "036000291452"]
+        );
+        assert_eq!(equivalent_codes("4006381333931"), ["4006381333931"]);
+    }
+
+    #[test]
+    fn modules_have_standard_widths() {
+        // EAN-13 และ UPC-A กว้าง 95 module, Code 128 กว้าง 11 ต่อ symbol และ stop 13
+        assert_eq!(modules(BarcodeFormat::Ean13, "4006381333931").len(), 95);
+        assert_eq!(modules(BarcodeFormat::Upca, "036000291452").len(), 95);
+        // start B + 3 ตัวอักษร + checksum + stop
+        assert_eq!(modules(BarcodeFormat::Code128, "ABC").len(), 11 * 5 + 13);
+        // ตัวเลขจำนวนคู่ใช้ code set C: start C + 2 คู่ + checksum + stop
+        assert_eq!(modules(BarcodeFormat::Code128, "1234").len(), 11 * 4 + 13);
+    }
+}
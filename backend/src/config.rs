@@ -0,0 +1,162 @@
+use serde::Deserialize;
+use std::env;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+
+// ค่าตั้งค่าของ backend อ่านจากไฟล์ toml แล้วทับด้วย environment variable (SHOP_*)
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct Config {
+    pub database_url: String,
+    pub images_dir: PathBuf,
+    pub host: String,
+    pub port: u16,
+    pub public_url: String,
+    pub frontend_url: String,
+    // จำนวนวันที่เก็บรายการในถังขยะก่อน purge อัตโนมัติ (0 = ไม่ purge อัตโนมัติ)
+    pub trash_retention_days: u32,
+    // โฟลเดอร์เก็บไฟล์ backup และจำนวนไฟล์ล่าสุดที่เก็บไว้ (0 = เก็บทั้งหมด)
+    pub backup_dir: PathBuf,
+    pub backup_keep: u32,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            database_url: "sqlite:../databases/scr/shop-system.db".to_string(),
+            images_dir: PathBuf::from("../databases/dbimages"),
+            host: "127.0.0.1".to_string(),
+            port: 2001,
+            public_url: "http://localhost:2001".to_string(),
+            frontend_url: "http://localhost:8080".to_string(),
+            trash_retention_days: 30,
+            backup_dir: PathBuf::from("../databases/backups"),
+            backup_keep: 7,
+        }
+    }
+}
+
+impl Config {
+    // ไฟล์ตั้งค่าระบุได้ด้วย SHOP_CONFIG ถ้าไม่ระบุจะลองอ่าน config.toml ถ้ามี
+    pub fn load() -> Result<Config, String> {
+        let mut config = match env::var("SHOP_CONFIG") {
+            Ok(path) => Self::from_file(Path::new(&path))?,
+            Err(_) if Path::new("config.toml").exists() => {
+                Self::from_file(Path::new("config.toml"))?
+            }
+            Err(_) => Config::default(),
+        };
+
+        config.apply_env()?;
+        Ok(config)
+    }
+
+    fn from_file(path: &Path) -> Result<Config, String> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read config {}: {}", path.display(), e))?;
+        toml::from_str(&text).map_err(|e| format!("Invalid config {}: {}", path.display(), e))
+    }
+
+    fn apply_env(&mut self) -> Result<(), String> {
+        if let Ok(v) = env::var("SHOP_DATABASE_URL") {
+            self.database_url = v;
+        }
+        if let Ok(v) = env::var("SHOP_IMAGES_DIR") {
+            self.images_dir = PathBuf::from(v);
+        }
+        if let Ok(v) = env::var("SHOP_HOST") {
+            self.host = v;
+        }
+        if let Ok(v) = env::var("SHOP_PORT") {
+            self.port = v.parse().map_err(|_| format!("Invalid SHOP_PORT: {}", v))?;
+        }
+        if let Ok(v) = env::var("SHOP_PUBLIC_URL") {
+            self.public_url = v;
+        }
+        if let Ok(v) = env::var("SHOP_FRONTEND_URL") {
+            self.frontend_url = v;
+        }
+        if let Ok(v) = env::var("SHOP_TRASH_RETENTION_DAYS") {
+            self.trash_retention_days = v
+                .parse()
+                .map_err(|_| format!("Invalid SHOP_TRASH_RETENTION_DAYS: {}", v))?;
+        }
+        if let Ok(v) = env::var("SHOP_BACKUP_DIR") {
+            self.backup_dir = PathBuf::from(v);
+        }
+        if let Ok(v) = env::var("SHOP_BACKUP_KEEP") {
+            self.backup_keep = v
+                .parse()
+                .map_err(|_| format!("Invalid SHOP_BACKUP_KEEP: {}", v))?;
+        }
+        Ok(())
+    }
+
+    // path จริงบน disk ของรูปภาพ (ในฐานข้อมูลเก็บเป็น path ที่สัมพันธ์กับ images_dir)
+    // ปฏิเสธ path ที่ออกนอก images_dir ทั้ง .., path เต็ม และ symlink ที่ชี้ออกไปข้างนอก
+    pub fn image_file(&self, rel_path: &str) -> io::Result<PathBuf> {
+        let outside = || {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Image path {} is outside the images folder", rel_path),
+            )
+        };
+        // ต้องเป็น path ย่อยจริง ๆ ("" หรือ "." คือ images_dir เอง)
+        let rel = Path::new(rel_path);
+        if !rel
+            .components()
+            .all(|c| matches!(c, Component::Normal(_) | Component::CurDir))
+            || !rel.components().any(|c| matches!(c, Component::Normal(_)))
+        {
+            return Err(outside());
+        }
+
+        // ไฟล์หรือโฟลเดอร์ที่กำลังจะสร้างอาจยังไม่มี จึงตรวจจากส่วนที่มีอยู่แล้วบน disk
+        let file = self.images_dir.join(rel);
+        let existing = file
+            .ancestors()
+            .take_while(|dir| *dir != self.images_dir.as_path())
+            .find(|path| path.symlink_metadata().is_ok());
+        if let Some(existing) = existing
+            && !existing
+                .canonicalize()?
+                .starts_with(self.images_dir.canonicalize()?)
+        {
+            return Err(outside());
+        }
+        Ok(file)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn image_file_stays_inside_images_dir() {
+        let root = env::temp_dir().join(format!("shop-config-test-{}", std::process::id()));
+        std::fs::create_dir_all(root.join("game")).unwrap();
+        let config = Config {
+            images_dir: root.clone(),
+            ..Config::default()
+        };
+
+        assert_eq!(
+            config.image_file("game/minecraft/minecraft_0.jpg").unwrap(),
+            root.join("game/minecraft/minecraft_0.jpg")
+        );
+        assert!(config.image_file("../escape_0.jpg").is_err());
+        assert!(config.image_file("game/../../escape_0.jpg").is_err());
+        assert!(config.image_file("/escape_0.jpg").is_err());
+        assert!(config.image_file("").is_err());
+        assert!(config.image_file(".").is_err());
+
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(env::temp_dir(), root.join("link")).unwrap();
+            assert!(config.image_file("link/escape_0.jpg").is_err());
+        }
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
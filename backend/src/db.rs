@@ -1,10 +1,291 @@
-use sqlx::sqlite::{SqliteConnectOptions, SqlitePool};
+use crate::config::Config;
+use sqlx::{Connection, Executor};
+use std::path::PathBuf;
 use std::str::FromStr;
 
-pub async fn init_db() -> SqlitePool {
-    let opt = SqliteConnectOptions::from_str("sqlite:../databases/scr/shop-system.db")
+#[cfg(all(feature = "sqlite", feature = "postgres"))]
+compile_error!("เลือกได้แค่ feature \"sqlite\" หรือ \"postgres\" อย่างใดอย่างหนึ่ง");
+
+#[cfg(not(any(feature = "sqlite", feature = "postgres")))]
+compile_error!("ต้องเปิด feature \"sqlite\" หรือ \"postgres\"");
+
+// ฐานข้อมูลที่ใช้ถูกเลือกตอน build ด้วย cargo feature (ค่าเริ่มต้นคือ sqlite)
+#[cfg(feature = "sqlite")]
+pub type Db = sqlx::Sqlite;
+#[cfg(feature = "postgres")]
+pub type Db = sqlx::Postgres;
+
+pub type DbPool = sqlx::Pool<Db>;
+
+#[cfg(feature = "sqlite")]
+type DbConnection = sqlx::SqliteConnection;
+#[cfg(feature = "postgres")]
+type DbConnection = sqlx::PgConnection;
+
+// LIKE ของ SQLite ไม่สนตัวพิมพ์เล็กใหญ่อยู่แล้ว ฝั่ง Postgres ต้องใช้ ILIKE ให้ผลเหมือนกัน
+#[cfg(feature = "sqlite")]
+pub const LIKE: &str = "LIKE";
+#[cfg(feature = "postgres")]
+pub const LIKE: &str = "ILIKE";
+
+// transaction ที่อ่านก่อนแล้วจึงเขียน SQLite ต้องจอง lock สำหรับเขียนตั้งแต่ BEGIN
+// ไม่อย่างนั้นเมื่อเขียนพร้อมกันจะได้ "database is locked" ทันทีแทนที่จะรอ busy_timeout
+#[cfg(feature = "sqlite")]
+pub const BEGIN_WRITE: &str = "BEGIN IMMEDIATE";
+#[cfg(feature = "postgres")]
+pub const BEGIN_WRITE: &str = "BEGIN";
+
+// migration ที่ฝังอยู่ในไบนารี เรียงตามเลขเวอร์ชัน ห้ามแก้ไฟล์ที่ถูก apply ไปแล้ว ให้เพิ่มไฟล์ใหม่แทน
+// แต่ละเวอร์ชันมีไฟล์ของทั้ง migrations/sqlite และ migrations/postgres
+pub struct Migration {
+    pub version: i64,
+    pub name: &'static str,
+    pub sql: &'static str,
+}
+
+#[cfg(feature = "sqlite")]
+macro_rules! migration_sql {
+    ($file:literal) => {
+        include_str!(concat!("../migrations/sqlite/", $file))
+    };
+}
+#[cfg(feature = "postgres")]
+macro_rules! migration_sql {
+    ($file:literal) => {
+        include_str!(concat!("../migrations/postgres/", $file))
+    };
+}
+
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        name: "init",
+        sql: migration_sql!("0001_init.sql"),
+    },
+    Migration {
+        version: 2,
+        name: "products_drop_image_path",
+        sql: migration_sql!("0002_products_drop_image_path.sql"),
+    },
+    Migration {
+        version: 3,
+        name: "images_relative_path",
+        sql: migration_sql!("0003_images_relative_path.sql"),
+    },
+    Migration {
+        version: 4,
+        name: "products_price_minor_units",
+        sql: migration_sql!("0004_products_price_minor_units.sql"),
+    },
+    Migration {
+        version: 5,
+        name: "foreign_key_rules",
+        sql: migration_sql!("0005_foreign_key_rules.sql"),
+    },
+    Migration {
+        version: 6,
+        name: "soft_delete",
+        sql: migration_sql!("0006_soft_delete.sql"),
+    },
+    Migration {
+        version: 7,
+        name: "audit_log",
+        sql: migration_sql!("0007_audit_log.sql"),
+    },
+    Migration {
+        version: 8,
+        name: "product_search",
+        sql: migration_sql!("0008_product_search.sql"),
+    },
+    Migration {
+        version: 9,
+        name: "images_position",
+        sql: migration_sql!("0009_images_position.sql"),
+    },
+    Migration {
+        version: 10,
+        name: "stock_movements",
+        sql: migration_sql!("0010_stock_movements.sql"),
+    },
+    Migration {
+        version: 11,
+        name: "reorder_alerts",
+        sql: migration_sql!("0011_reorder_alerts.sql"),
+    },
+    Migration {
+        version: 12,
+        name: "product_variants",
+        sql: migration_sql!("0012_product_variants.sql"),
+    },
+    Migration {
+        version: 13,
+        name: "product_barcodes",
+        sql: migration_sql!("0013_product_barcodes.sql"),
+    },
+];
+
+#[cfg(feature = "sqlite")]
+fn connect_options(url: &str) -> sqlx::sqlite::SqliteConnectOptions {
+    if !url.starts_with("sqlite:") {
+        panic!(
+            "❌ This build supports SQLite only, got database_url: {}",
+            url
+        );
+    }
+    sqlx::sqlite::SqliteConnectOptions::from_str(url)
         .unwrap()
-        .create_if_missing(true);
+        .create_if_missing(true)
+        // SQLite ปิด foreign key เป็นค่าเริ่มต้น ต้องเปิดทุก connection ใน pool
+        .foreign_keys(true)
+}
+
+#[cfg(feature = "postgres")]
+fn connect_options(url: &str) -> sqlx::postgres::PgConnectOptions {
+    if !(url.starts_with("postgres://") || url.starts_with("postgresql://")) {
+        panic!(
+            "❌ This build supports PostgreSQL only, got database_url: {}",
+            url
+        );
+    }
+    sqlx::postgres::PgConnectOptions::from_str(url).unwrap()
+}
+
+// path ของไฟล์ฐานข้อมูล SQLite ตาม database_url (ใช้ตอน restore backup)
+#[cfg(feature = "sqlite")]
+pub fn database_file(config: &Config) -> Result<PathBuf, String> {
+    if !config.database_url.starts_with("sqlite:") {
+        return Err(format!(
+            "Not a SQLite database_url: {}",
+            config.database_url
+        ));
+    }
+    Ok(connect_options(&config.database_url)
+        .get_filename()
+        .to_path_buf())
+}
+
+#[cfg(feature = "postgres")]
+pub fn database_file(_config: &Config) -> Result<PathBuf, String> {
+    Err("Restore is only supported for SQLite, use pg_restore for PostgreSQL".to_string())
+}
+
+pub async fn init_db(config: &Config) -> DbPool {
+    let opt = connect_options(&config.database_url);
+
+    // ใช้ connection แยกสำหรับ migration เพราะ SQLite ต้องปิด foreign_keys ระหว่างสร้างตารางใหม่
+    let mut conn = DbConnection::connect_with(&opt).await.unwrap();
+    if let Err(e) = run_migrations(&mut conn).await {
+        panic!("❌ Failed to run migrations: {:?}", e);
+    }
+    #[cfg(feature = "sqlite")]
+    if let Err(e) = report_foreign_key_violations(&mut conn).await {
+        eprintln!("❌ Failed to run foreign_key_check: {:?}", e);
+    }
+    conn.close().await.unwrap();
+
+    DbPool::connect_with(opt).await.unwrap()
+}
+
+// ฐานข้อมูล SQLite ในหน่วยความจำสำหรับ test ทุก connection เห็นฐานข้อมูลของตัวเอง
+// จึงใช้ connection เดียวที่ไม่ถูกปิดตลอดอายุของ pool
+#[cfg(all(test, feature = "sqlite"))]
+pub async fn memory_pool() -> DbPool {
+    let pool = sqlx::pool::PoolOptions::new()
+        .max_connections(1)
+        .min_connections(1)
+        .idle_timeout(None)
+        .max_lifetime(None)
+        .acquire_timeout(std::time::Duration::from_secs(5))
+        .connect_with(connect_options("sqlite::memory:"))
+        .await
+        .unwrap();
+    run_migrations(&mut pool.acquire().await.unwrap())
+        .await
+        .unwrap();
+    pool
+}
+
+async fn run_migrations(conn: &mut DbConnection) -> Result<(), sqlx::Error> {
+    conn.execute(
+        r#"
+        CREATE TABLE IF NOT EXISTS _migrations(
+            version BIGINT PRIMARY KEY,
+            name TEXT NOT NULL,
+            applied_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+        )
+        "#,
+    )
+    .await?;
+
+    let current = schema_version(&mut *conn).await?;
+
+    // PRAGMA foreign_keys เปลี่ยนใน transaction ไม่ได้ ต้องปิดก่อนเริ่ม
+    #[cfg(feature = "sqlite")]
+    conn.execute("PRAGMA foreign_keys = OFF").await?;
+
+    for migration in MIGRATIONS.iter().filter(|m| m.version > current) {
+        let mut tx = conn.begin().await?;
+
+        sqlx::raw_sql(migration.sql).execute(&mut *tx).await?;
+
+        sqlx::query("INSERT INTO _migrations (version, name) VALUES ($1, $2)")
+            .bind(migration.version)
+            .bind(migration.name)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        println!(
+            "✅ Applied migration {:04}_{}",
+            migration.version, migration.name
+        );
+    }
+
+    #[cfg(feature = "sqlite")]
+    conn.execute("PRAGMA foreign_keys = ON").await?;
+
+    Ok(())
+}
+
+// แสดงแถวที่อ้างถึง parent ที่ไม่มีอยู่จริง (ข้อมูลเก่าก่อนเปิด foreign key) ไม่แก้ไขให้อัตโนมัติ
+#[cfg(feature = "sqlite")]
+async fn report_foreign_key_violations(conn: &mut DbConnection) -> Result<(), sqlx::Error> {
+    use sqlx::Row;
+
+    let rows = sqlx::query("PRAGMA foreign_key_check")
+        .fetch_all(&mut *conn)
+        .await?;
+
+    for row in &rows {
+        let table: String = row.get("table");
+        let rowid: Option<i64> = row.get("rowid");
+        let parent: String = row.get("parent");
+        println!(
+            "⚠️ Orphan row: {} rowid={} references missing {}",
+            table,
+            rowid.map(|id| id.to_string()).unwrap_or_default(),
+            parent
+        );
+    }
+
+    if rows.is_empty() {
+        println!("✅ foreign_key_check passed");
+    }
+
+    Ok(())
+}
+
+// เวอร์ชันล่าสุดของ schema ที่ apply แล้ว (0 = ยังไม่มี migration)
+pub async fn schema_version<'e, E>(executor: E) -> Result<i64, sqlx::Error>
+where
+    E: Executor<'e, Database = Db>,
+{
+    sqlx::query_scalar::<_, i64>("SELECT COALESCE(MAX(version), 0) FROM _migrations")
+        .fetch_one(executor)
+        .await
+}
 
-    SqlitePool::connect_with(opt).await.unwrap()
+// เวอร์ชันล่าสุดที่ไบนารีนี้รู้จัก
+pub fn latest_version() -> i64 {
+    MIGRATIONS.last().map(|m| m.version).unwrap_or(0)
 }
@@ -0,0 +1,485 @@
+use crate::models::Products;
+use crate::repository::{
+    ImageRepository, PageSeek, ProductFilter, ProductRepository, RepoError, now,
+};
+use actix_web::web::Bytes;
+use futures_util::{Stream, stream};
+use itertools::Itertools;
+use rust_xlsxwriter::{Format, Workbook, Worksheet, XlsxError};
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::sync::Arc;
+
+// ไฟล์ export มีสินค้าแถวละหนึ่งชิ้น เรียงตาม sort เดียวกับ GET /api/products
+//   CSV, XLSX  คอลัมน์ตาม COLUMNS ตามด้วย detail.<key> ของทุก key ที่พบ (object ซ้อนต่อด้วย . เช่น detail.spec.cpu)
+//              array ของค่าเดี่ยวคั่นด้วย ", " แบบอื่นเขียนเป็นข้อความ JSON ส่วน images เป็น URL คั่นด้วย |
+//   NDJSON     สินค้าแบบเดียวกับ GET /api/products/{id} ต่อบรรทัด (detail ไม่แตกคอลัมน์) images_path เป็น URL เต็ม
+const COLUMNS: &[&str] = &[
+    "id",
+    "name",
+    "price",
+    "currency",
+    "stock",
+    "product_type_name",
+    "created_at",
+    "images",
+];
+const DETAIL_COLUMN: &str = "detail";
+const IMAGE_SEPARATOR: &str = "|";
+const DATE_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+const BATCH_SIZE: i64 = 500;
+// จำนวนแถวสูงสุดของ worksheet รวมแถวหัวตาราง
+const MAX_XLSX_ROWS: usize = 1_048_576;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExportFormat {
+    Csv,
+    Ndjson,
+    Xlsx,
+}
+
+impl ExportFormat {
+    pub fn from_name(name: &str) -> Option<ExportFormat> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "csv" => Some(ExportFormat::Csv),
+            "ndjson" | "jsonl" => Some(ExportFormat::Ndjson),
+            "xlsx" => Some(ExportFormat::Xlsx),
+            _ => None,
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "text/csv; charset=utf-8",
+            ExportFormat::Ndjson => "application/x-ndjson",
+            ExportFormat::Xlsx => {
+                "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet"
+            }
+        }
+    }
+
+    // products-20250101-120000.csv
+    pub fn file_name(&self) -> String {
+        let extension = match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Ndjson => "ndjson",
+            ExportFormat::Xlsx => "xlsx",
+        };
+        format!("products-{}.{}", now().format("%Y%m%d-%H%M%S"), extension)
+    }
+}
+
+#[derive(Debug)]
+pub enum ExportError {
+    // export ในรูปแบบที่ขอไม่ได้ เช่น สินค้าเกินจำนวนแถวของ XLSX
+    Invalid(String),
+    Repo(RepoError),
+    Write(String),
+}
+
+impl fmt::Display for ExportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExportError::Invalid(reason) => write!(f, "{}", reason),
+            ExportError::Repo(e) => write!(f, "{}", e),
+            ExportError::Write(e) => write!(f, "failed to write export: {}", e),
+        }
+    }
+}
+
+// body แบบ stream ของ actix ต้องการ error ที่เป็น std::error::Error
+impl std::error::Error for ExportError {}
+
+impl From<RepoError> for ExportError {
+    fn from(e: RepoError) -> Self {
+        ExportError::Repo(e)
+    }
+}
+
+impl From<csv::Error> for ExportError {
+    fn from(e: csv::Error) -> Self {
+        ExportError::Write(e.to_string())
+    }
+}
+
+impl From<XlsxError> for ExportError {
+    fn from(e: XlsxError) -> Self {
+        ExportError::Write(e.to_string())
+    }
+}
+
+// อ่านสินค้าตามตัวกรองทีละชุดแบบ keyset ต่อจากรายการสุดท้ายของชุดก่อน
+// สินค้าที่เพิ่มหรือลบระหว่าง export จึงไม่ทำให้รายการอื่นถูกข้ามหรือซ้ำ
+pub struct Exporter {
+    products_repo: Arc<dyn ProductRepository>,
+    images_repo: Arc<dyn ImageRepository>,
+    filter: ProductFilter,
+    public_url: String,
+    // None = อ่านครบแล้ว
+    seek: Option<PageSeek>,
+}
+
+impl Exporter {
+    pub fn new(
+        products_repo: Arc<dyn ProductRepository>,
+        images_repo: Arc<dyn ImageRepository>,
+        filter: ProductFilter,
+        public_url: &str,
+    ) -> Exporter {
+        Exporter {
+            products_repo,
+            images_repo,
+            filter,
+            public_url: public_url.trim_end_matches('/').to_string(),
+            seek: Some(PageSeek::Offset(0)),
+        }
+    }
+
+    async fn next_products(&mut self) -> Result<Option<Vec<Products>>, RepoError> {
+        let Some(seek) = self.seek.take() else {
+            return Ok(None);
+        };
+        let rows = self
+            .products_repo
+            .list(&self.filter, &seek, BATCH_SIZE)
+            .await?;
+        if rows.len() as i64 == BATCH_SIZE
+            && let Some((_, key)) = rows.last()
+        {
+            self.seek = Some(PageSeek::After(key.clone()));
+        }
+        if rows.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(rows.into_iter().map(|(product, _)| product).collect()))
+    }
+
+    // ชุดถัดไปพร้อม URL ของรูปตามลำดับ
+    async fn next_batch(&mut self) -> Result<Option<Vec<Products>>, RepoError> {
+        let Some(mut products) = self.next_products().await? else {
+            return Ok(None);
+        };
+        let ids: Vec<i64> = products.iter().map(|p| p.id).collect();
+        for (product_id, image_path) in self.images_repo.paths_for_products(&ids).await? {
+            if let Some(product) = products.iter_mut().find(|p| p.id == product_id) {
+                product
+                    .images_path
+                    .push(format!("{}/images/{}", self.public_url, image_path));
+            }
+        }
+        for product in &mut products {
+            product.snippet = None;
+        }
+        Ok(Some(products))
+    }
+
+    // CSV และ XLSX ต้องรู้คอลัมน์ detail ทั้งหมดก่อนเขียนหัวตาราง จึงอ่านสินค้าทั้งหมดหนึ่งรอบก่อน
+    // key ที่เพิ่งมีในสินค้าที่แก้ระหว่าง export จะไม่มีคอลัมน์
+    async fn scan_detail_columns(&mut self) -> Result<(usize, Vec<String>), RepoError> {
+        let mut count = 0;
+        let mut columns = BTreeSet::new();
+        while let Some(products) = self.next_products().await? {
+            count += products.len();
+            for product in &products {
+                let mut cells = BTreeMap::new();
+                flatten_detail(DETAIL_COLUMN, &product.detail, &mut cells);
+                columns.extend(cells.into_keys());
+            }
+        }
+        self.seek = Some(PageSeek::Offset(0));
+        Ok((count, columns.into_iter().collect()))
+    }
+
+    // CSV และ NDJSON ส่งทีละชุดระหว่างอ่าน error กลางทางจะตัดการเชื่อมต่อ (หัวตอบกลับส่งไปแล้ว)
+    pub async fn stream(
+        mut self,
+        format: ExportFormat,
+    ) -> Result<impl Stream<Item = Result<Bytes, ExportError>>, ExportError> {
+        let (header, columns) = match format {
+            ExportFormat::Csv => {
+                let (_, columns) = self.scan_detail_columns().await?;
+                (Some(Bytes::from(csv_header(&columns)?)), Some(columns))
+            }
+            _ => (None, None),
+        };
+
+        Ok(stream::unfold(
+            (self, columns, header),
+            |(mut exporter, columns, header)| async move {
+                if let Some(header) = header {
+                    return Some((Ok(header), (exporter, columns, None)));
+                }
+                let chunk = match exporter.next_batch().await {
+                    Ok(None) => return None,
+                    Ok(Some(products)) => match &columns {
+                        Some(columns) => csv_rows(&products, columns),
+                        None => ndjson_rows(&products),
+                    },
+                    Err(e) => Err(e.into()),
+                };
+                if let Err(e) = &chunk {
+                    eprintln!("❌ Failed to export products: {}", e);
+                    exporter.seek = None;
+                }
+                Some((chunk.map(Bytes::from), (exporter, columns, None)))
+            },
+        ))
+    }
+
+    // XLSX เป็นไฟล์ zip ต้องเขียนครบก่อนจึงส่งได้ ไม่ได้ส่งทีละชุด
+    pub async fn xlsx(mut self) -> Result<Vec<u8>, ExportError> {
+        let (count, columns) = self.scan_detail_columns().await?;
+        if count >= MAX_XLSX_ROWS {
+            return Err(ExportError::Invalid(format!(
+                "Too many products for XLSX (max {}), use format=csv or format=ndjson",
+                MAX_XLSX_ROWS - 1
+            )));
+        }
+
+        let mut sheet = XlsxSheet::new(columns)?;
+        while let Some(products) = self.next_batch().await? {
+            for product in &products {
+                sheet.write_product(product)?;
+            }
+        }
+
+        // บีบอัด zip ใช้ CPU นาน จึงทำนอก thread ของ async
+        tokio::task::spawn_blocking(move || sheet.finish())
+            .await
+            .map_err(|e| ExportError::Write(e.to_string()))?
+    }
+}
+
+// detail เป็นคู่ (ชื่อคอลัมน์, ค่า) object ซ้อนต่อชื่อด้วย . ค่าอื่น (รวม array) เป็นหนึ่งคอลัมน์
+fn flatten_detail<'a>(name: &str, value: &'a Value, cells: &mut BTreeMap<String, &'a Value>) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map {
+                flatten_detail(&format!("{}.{}", name, key), value, cells);
+            }
+        }
+        value => {
+            cells.insert(name.to_string(), value);
+        }
+    }
+}
+
+fn is_scalar(value: &Value) -> bool {
+    !matches!(value, Value::Array(_) | Value::Object(_))
+}
+
+// ค่าใน detail เป็นข้อความของหนึ่งช่อง null = ช่องว่าง
+fn cell_text(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(text) => text.clone(),
+        Value::Array(items) if items.iter().all(is_scalar) => {
+            items.iter().map(cell_text).join(", ")
+        }
+        _ => value.to_string(),
+    }
+}
+
+// ข้อความที่ขึ้นต้นด้วย = + - @ ถูก Excel ตีความเป็นสูตร จึงเติม ' นำหน้า (ใช้กับช่องที่เป็นข้อความเท่านั้น)
+fn csv_text(text: String) -> String {
+    match text.chars().next() {
+        Some('=' | '+' | '-' | '@' | '\t' | '\r') => format!("'{}", text),
+        _ => text,
+    }
+}
+
+fn csv_detail_cell(value: Option<&&Value>) -> String {
+    match value {
+        None => String::new(),
+        Some(value @ (Value::Number(_) | Value::Bool(_))) => value.to_string(),
+        Some(value) => csv_text(cell_text(value)),
+    }
+}
+
+// ขึ้นต้นด้วย BOM ให้ Excel อ่านภาษาไทยเป็น UTF-8
+fn csv_header(detail_columns: &[String]) -> Result<Vec<u8>, ExportError> {
+    let mut writer = csv::Writer::from_writer(b"\xEF\xBB\xBF".to_vec());
+    writer.write_record(
+        COLUMNS
+            .iter()
+            .copied()
+            .chain(detail_columns.iter().map(String::as_str)),
+    )?;
+    writer
+        .into_inner()
+        .map_err(|e| ExportError::Write(e.to_string()))
+}
+
+fn csv_rows(products: &[Products], detail_columns: &[String]) -> Result<Vec<u8>, ExportError> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    for product in products {
+        let mut cells = BTreeMap::new();
+        flatten_detail(DETAIL_COLUMN, &product.detail, &mut cells);
+        let record = [
+            product.id.to_string(),
+            csv_text(product.name_product.clone()),
+            product.price.to_decimal_string(),
+            product.price.currency.code().to_string(),
+            product.stock.to_string(),
+            csv_text(product.products_type_name.clone().unwrap_or_default()),
+            product.create_at.format(DATE_FORMAT).to_string(),
+            product.images_path.join(IMAGE_SEPARATOR),
+        ]
+        .into_iter()
+        .chain(
+            detail_columns
+                .iter()
+                .map(|column| csv_detail_cell(cells.get(column))),
+        );
+        writer.write_record(record)?;
+    }
+    writer
+        .into_inner()
+        .map_err(|e| ExportError::Write(e.to_string()))
+}
+
+fn ndjson_rows(products: &[Products]) -> Result<Vec<u8>, ExportError> {
+    let mut bytes = Vec::new();
+    for product in products {
+        serde_json::to_writer(&mut bytes, product)
+            .map_err(|e| ExportError::Write(e.to_string()))?;
+        bytes.push(b'\n');
+    }
+    Ok(bytes)
+}
+
+// worksheet เดียวชื่อ Products ราคา stock และตัวเลขใน detail เป็นช่องตัวเลข created_at เป็นช่องวันที่
+struct XlsxSheet {
+    worksheet: Worksheet,
+    detail_columns: Vec<String>,
+    row: u32,
+    date: Format,
+    // ราคาตามจำนวนหลักทศนิยมของสกุลเงิน
+    price: Format,
+    whole_price: Format,
+}
+
+impl XlsxSheet {
+    fn new(detail_columns: Vec<String>) -> Result<XlsxSheet, XlsxError> {
+        let mut worksheet = Worksheet::new();
+        worksheet.set_name("Products")?;
+        let bold = Format::new().set_bold();
+        for (col, name) in COLUMNS
+            .iter()
+            .copied()
+            .chain(detail_columns.iter().map(String::as_str))
+            .enumerate()
+        {
+            worksheet.write_string_with_format(0, col as u16, name, &bold)?;
+        }
+        worksheet.set_freeze_panes(1, 0)?;
+
+        Ok(XlsxSheet {
+            worksheet,
+            detail_columns,
+            row: 0,
+            date: Format::new().set_num_format("yyyy-mm-dd hh:mm:ss"),
+            price: Format::new().set_num_format("0.00"),
+            whole_price: Format::new().set_num_format("0"),
+        })
+    }
+
+    fn write_product(&mut self, product: &Products) -> Result<(), XlsxError> {
+        self.row += 1;
+        let row = self.row;
+        let sheet = &mut self.worksheet;
+        let price_format = match product.price.currency.minor_digits() {
+            0 => &self.whole_price,
+            _ => &self.price,
+        };
+
+        sheet.write_number(row, 0, product.id as f64)?;
+        sheet.write_string(row, 1, &product.name_product)?;
+        sheet.write_number_with_format(row, 2, product.price.to_f64(), price_format)?;
+        sheet.write_string(row, 3, product.price.currency.code())?;
+        sheet.write_number(row, 4, product.stock as f64)?;
+        if let Some(type_name) = &product.products_type_name {
+            sheet.write_string(row, 5, type_name)?;
+        }
+        sheet.write_datetime_with_format(row, 6, product.create_at, &self.date)?;
+        sheet.write_string(row, 7, product.images_path.join(IMAGE_SEPARATOR))?;
+
+        let mut cells = BTreeMap::new();
+        flatten_detail(DETAIL_COLUMN, &product.detail, &mut cells);
+        for (index, column) in self.detail_columns.iter().enumerate() {
+            let col = (COLUMNS.len() + index) as u16;
+            match cells.get(column) {
+                None | Some(Value::Null) => {}
+                Some(Value::Number(number)) => {
+                    sheet.write_number(row, col, number.as_f64().unwrap_or_default())?;
+                }
+                Some(Value::Bool(value)) => {
+                    sheet.write_boolean(row, col, *value)?;
+                }
+                Some(value) => {
+                    sheet.write_string(row, col, cell_text(value))?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn finish(mut self) -> Result<Vec<u8>, ExportError> {
+        self.worksheet.autofit();
+        // autofit ไม่นับความยาวของวันที่ตามรูปแบบ
+        self.worksheet.set_column_width(6, 19)?;
+        let mut workbook = Workbook::new();
+        workbook.push_worksheet(self.worksheet);
+        Ok(workbook.save_to_buffer()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn flatten_detail_joins_nested_keys() {
+        let detail = json!({
+            "color": "red",
+            "spec": {"cpu": "i7", "ram": {"gb": 16}},
+            "tags": ["a", "b"],
+            "empty": {},
+        });
+        let mut cells = BTreeMap::new();
+        flatten_detail(DETAIL_COLUMN, &detail, &mut cells);
+        assert_eq!(
+            cells.keys().collect::<Vec<_>>(),
+            [
+                "detail.color",
+                "detail.spec.cpu",
+                "detail.spec.ram.gb",
+                "detail.tags"
+            ]
+        );
+        assert_eq!(cells["detail.spec.ram.gb"], &json!(16));
+    }
+
+    #[test]
+    fn cell_text_writes_scalars_and_json() {
+        assert_eq!(cell_text(&Value::Null), "");
+        assert_eq!(cell_text(&json!("red")), "red");
+        assert_eq!(cell_text(&json!(["a", 1, true])), "a, 1, true");
+        assert_eq!(cell_text(&json!([{"a": 1}])), r#"[{"a":1}]"#);
+    }
+
+    #[test]
+    fn csv_text_escapes_formulas() {
+        for text in ["=SUM(A1)", "+1", "-1", "@cmd", "\tx"] {
+            assert_eq!(csv_text(text.to_string()), format!("'{}", text));
+        }
+        assert_eq!(csv_text("red".to_string()), "red");
+        assert_eq!(csv_text(String::new()), "");
+        // ตัวเลขใน detail ไม่ใช่ข้อความ จึงไม่ถูกเติม '
+        assert_eq!(csv_detail_cell(Some(&&json!(-5))), "-5");
+        assert_eq!(csv_detail_cell(Some(&&json!("-5"))), "'-5");
+        assert_eq!(csv_detail_cell(None), "");
+    }
+}
@@ -0,0 +1,75 @@
+use crate::models::{AuditQuery, PaginatedResponse, PaginationInfo, page_number, page_size};
+use crate::repository::{AuditFilter, AuditRepository};
+use actix_web::dev::Payload;
+use actix_web::{FromRequest, HttpRequest, HttpResponse, Responder, get, web};
+use std::future::{Ready, ready};
+
+// ผู้ที่ทำการแก้ไข อ่านจาก header X-Actor (ยังไม่มีระบบ login) ถ้าไม่ส่งมาจะเป็น "anonymous"
+// ไคลเอนต์ตั้งค่าอะไรก็ได้และไม่มีการยืนยันตัวตน actor ใน audit log จึงเป็นข้อมูลประกอบเท่านั้น
+// ห้ามใช้ตัดสินสิทธิ์หรือเป็นหลักฐานว่าใครแก้ไข จนกว่าจะมี login มาแทน header นี้
+pub struct Actor(pub String);
+
+impl FromRequest for Actor {
+    type Error = actix_web::Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let actor = req
+            .headers()
+            .get("X-Actor")
+            .and_then(|value| value.to_str().ok())
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+            .unwrap_or("anonymous");
+        ready(Ok(Actor(actor.to_string())))
+    }
+}
+
+#[get("/api/audit")]
+pub async fn get_audit(
+    audit_repo: web::Data<dyn AuditRepository>,
+    query: web::Query<AuditQuery>,
+) -> impl Responder {
+    let page = page_number(query.page);
+    let items_per_page = page_size(query.per_page);
+    let offset = (page - 1) * items_per_page;
+
+    // from/to รวมทั้งวัน: to=2024-05-01 คือก่อนเที่ยงคืนของวันที่ 2
+    let filter = AuditFilter {
+        actor: query.actor.clone(),
+        action: query.action,
+        entity_type: query.entity_type,
+        entity_id: query.entity_id,
+        from: query.from.and_then(|date| date.and_hms_opt(0, 0, 0)),
+        to: query
+            .to
+            .and_then(|date| date.succ_opt())
+            .and_then(|date| date.and_hms_opt(0, 0, 0)),
+    };
+
+    let count_result = audit_repo.count(&filter).await;
+    let entries_result = audit_repo.list(&filter, items_per_page, offset).await;
+
+    match (count_result, entries_result) {
+        (Ok(total_count), Ok(entries)) => {
+            let total_pages = (total_count + items_per_page - 1) / items_per_page;
+
+            HttpResponse::Ok().json(PaginatedResponse {
+                data: entries,
+                pagination: PaginationInfo {
+                    total_items: Some(total_count),
+                    items_per_page,
+                    current_page: Some(page),
+                    total_pages: Some(total_pages),
+                    sort: None,
+                    next_cursor: None,
+                    prev_cursor: None,
+                },
+            })
+        }
+        (Err(e), _) | (_, Err(e)) => {
+            eprintln!("❌ Failed to read audit log: {}", e);
+            HttpResponse::InternalServerError().body("Database query failed")
+        }
+    }
+}
@@ -0,0 +1,38 @@
+use crate::backup;
+use crate::config::Config;
+use crate::db::DbPool;
+use actix_web::{HttpResponse, Responder, get, post, web};
+
+// สร้าง backup ระหว่างที่ server ทำงานอยู่ (กู้คืนได้ด้วยคำสั่ง `backend restore <ไฟล์>` เท่านั้น)
+#[post("/api/backups")]
+pub async fn post_backup(
+    db: Option<web::Data<DbPool>>,
+    config: web::Data<Config>,
+) -> impl Responder {
+    let Some(db) = db else {
+        return HttpResponse::NotFound().body("No database to back up for in-memory store");
+    };
+    if !backup::SUPPORTED {
+        return HttpResponse::NotImplemented()
+            .body("Online backup is only supported for SQLite, use pg_dump for PostgreSQL");
+    }
+
+    match backup::create_backup(db.get_ref(), &config).await {
+        Ok(info) => HttpResponse::Created().json(info),
+        Err(e) => {
+            eprintln!("❌ Failed to create backup: {}", e);
+            HttpResponse::InternalServerError().body("Failed to create backup")
+        }
+    }
+}
+
+#[get("/api/backups")]
+pub async fn get_backups(config: web::Data<Config>) -> impl Responder {
+    match backup::list_backups(&config) {
+        Ok(backups) => HttpResponse::Ok().json(backups),
+        Err(e) => {
+            eprintln!("❌ Failed to list backups: {}", e);
+            HttpResponse::InternalServerError().body("Failed to list backups")
+        }
+    }
+}
@@ -0,0 +1,85 @@
+use crate::barcode::{self, DEFAULT_SCALE, ImageFormat, MAX_SCALE, equivalent_codes};
+use crate::handlers::products::load_product;
+use crate::models::BarcodeImageQuery;
+use crate::repository::{ImageRepository, ProductRepository};
+use actix_web::{HttpResponse, Responder, get, web};
+
+// สินค้าจาก barcode ที่สแกนได้หน้าร้าน (รูปแบบเดียวกับ GET /api/products/{id})
+// UPC-A กับ EAN-13 ที่มี 0 นำหน้าถือเป็นรหัสเดียวกัน
+#[get("/api/products/by-barcode/{code}")]
+pub async fn get_product_by_barcode(
+    products_repo: web::Data<dyn ProductRepository>,
+    images_repo: web::Data<dyn ImageRepository>,
+    path: web::Path<String>,
+) -> impl Responder {
+    let code = path.into_inner();
+    let product_id = match products_repo
+        .find_id_by_barcode(&equivalent_codes(code.trim()))
+        .await
+    {
+        Ok(Some(id)) => id,
+        Ok(None) => return HttpResponse::NotFound().body("Product not found"),
+        Err(e) => {
+            eprintln!("❌ Failed to look up barcode: {}", e);
+            return HttpResponse::InternalServerError().body("Database query failed");
+        }
+    };
+
+    match load_product(products_repo.get_ref(), images_repo.get_ref(), product_id).await {
+        Ok(Some(product)) => HttpResponse::Ok().json(product),
+        Ok(None) => HttpResponse::NotFound().body("Product not found"),
+        Err(e) => {
+            eprintln!("❌ Failed to fetch product: {}", e);
+            HttpResponse::InternalServerError().body("Database query failed")
+        }
+    }
+}
+
+// รูป barcode ของสินค้าสำหรับพิมพ์ฉลาก ?format=svg (ค่าเริ่มต้น มีข้อความใต้แท่ง) | png
+#[get("/api/products/{id}/barcode")]
+pub async fn get_product_barcode(
+    products_repo: web::Data<dyn ProductRepository>,
+    path: web::Path<i64>,
+    query: web::Query<BarcodeImageQuery>,
+) -> impl Responder {
+    let format = match query.format.as_deref() {
+        None => ImageFormat::Svg,
+        Some(name) => match ImageFormat::from_name(name) {
+            Some(format) => format,
+            None => {
+                return HttpResponse::BadRequest()
+                    .body("Unknown image format, use format=svg or format=png");
+            }
+        },
+    };
+    let scale = query.scale.unwrap_or(DEFAULT_SCALE);
+    if !(1..=MAX_SCALE).contains(&scale) {
+        return HttpResponse::BadRequest()
+            .body(format!("scale must be between 1 and {}", MAX_SCALE));
+    }
+
+    let product = match products_repo.find(path.into_inner()).await {
+        Ok(Some(product)) => product,
+        Ok(None) => return HttpResponse::NotFound().body("Product not found"),
+        Err(e) => {
+            eprintln!("❌ Failed to fetch product: {}", e);
+            return HttpResponse::InternalServerError().body("Database query failed");
+        }
+    };
+    let (Some(code), Some(barcode_format)) = (product.barcode, product.barcode_format) else {
+        return HttpResponse::NotFound().body("Product has no barcode");
+    };
+
+    let mut response = HttpResponse::Ok();
+    response.content_type(format.content_type());
+    match format {
+        ImageFormat::Svg => response.body(barcode::svg(barcode_format, &code, scale)),
+        ImageFormat::Png => match barcode::png(barcode_format, &code, scale) {
+            Ok(bytes) => response.body(bytes),
+            Err(e) => {
+                eprintln!("❌ Failed to render barcode: {}", e);
+                HttpResponse::InternalServerError().body("Failed to render barcode")
+            }
+        },
+    }
+}
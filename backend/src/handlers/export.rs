@@ -0,0 +1,69 @@
+use crate::config::Config;
+use crate::export::{ExportError, ExportFormat, Exporter};
+use crate::handlers::products::{list_sort, product_filter};
+use crate::models::{ExportQuery, Querysearchandpage};
+use crate::repository::{ImageRepository, ProductRepository};
+use actix_web::http::header::ContentDisposition;
+use actix_web::{HttpRequest, HttpResponse, Responder, get, web};
+
+// export สินค้าเป็นไฟล์แนบ ใช้ query และตัวกรองชุดเดียวกับ GET /api/products (ไม่สน page/per_page/cursor)
+// ?format=csv (ค่าเริ่มต้น) | ndjson | xlsx รูปแบบคอลัมน์อยู่ที่ export.rs
+#[get("/api/products/export")]
+pub async fn get_products_export(
+    req: HttpRequest,
+    products_repo: web::Data<dyn ProductRepository>,
+    images_repo: web::Data<dyn ImageRepository>,
+    config: web::Data<Config>,
+    query: web::Query<Querysearchandpage>,
+    export_query: web::Query<ExportQuery>,
+) -> impl Responder {
+    let format = match export_query.format.as_deref() {
+        None => ExportFormat::Csv,
+        Some(name) => match ExportFormat::from_name(name) {
+            Some(format) => format,
+            None => {
+                return HttpResponse::BadRequest()
+                    .body("Unknown export format, use format=csv, format=ndjson or format=xlsx");
+            }
+        },
+    };
+
+    let search = query.search.clone().unwrap_or_default();
+    let sort = list_sort(query.sort, &search);
+    let filter = match product_filter(&query, req.query_string(), search, sort) {
+        Ok(filter) => filter,
+        Err(message) => return HttpResponse::BadRequest().body(message),
+    };
+
+    let exporter = Exporter::new(
+        products_repo.into_inner(),
+        images_repo.into_inner(),
+        filter,
+        &config.public_url,
+    );
+    let mut response = HttpResponse::Ok();
+    response
+        .content_type(format.content_type())
+        .insert_header(ContentDisposition::attachment(format.file_name()));
+
+    match format {
+        ExportFormat::Xlsx => match exporter.xlsx().await {
+            Ok(bytes) => response.body(bytes),
+            Err(e) => export_failed(e),
+        },
+        _ => match exporter.stream(format).await {
+            Ok(stream) => response.streaming(stream),
+            Err(e) => export_failed(e),
+        },
+    }
+}
+
+fn export_failed(e: ExportError) -> HttpResponse {
+    match e {
+        ExportError::Invalid(message) => HttpResponse::BadRequest().body(message),
+        e => {
+            eprintln!("❌ Failed to export products: {}", e);
+            HttpResponse::InternalServerError().body("Failed to export products")
+        }
+    }
+}
@@ -1,16 +1,15 @@
+use crate::config::Config;
 use actix_files::NamedFile;
-use actix_web::{get, web, Result};
-use std::path::PathBuf;
+use actix_web::{Result, get, web};
 
 #[get("/images/{file:.*}")]
-pub async fn get_image(file: web::Path<String>) -> Result<NamedFile> {
-    let base_path = PathBuf::from("../databases/dbimages");
-    let file_path = base_path.join(file.into_inner());
+pub async fn get_image(config: web::Data<Config>, file: web::Path<String>) -> Result<NamedFile> {
+    // อนุญาตเฉพาะ path ย่อยของ images_dir (ไม่มี .., / นำหน้า drive prefix หรือ symlink ออกไปข้างนอก)
+    let file_path = config.image_file(&file.into_inner());
 
     // ตรวจสอบว่าไฟล์มีอยู่จริงไหม
-    if file_path.exists() {
-        Ok(NamedFile::open(file_path)?)
-    } else {
-        Ok(NamedFile::open("../databases/dbimages/404.jpg")?) // ไฟล์ 404 หรือไฟล์ที่แสดงถ้าไม่พบไฟล์
+    match file_path {
+        Ok(file_path) if file_path.is_file() => Ok(NamedFile::open(file_path)?),
+        _ => Ok(NamedFile::open(config.images_dir.join("404.jpg"))?), // ไฟล์ 404 หรือไฟล์ที่แสดงถ้าไม่พบไฟล์
     }
 }
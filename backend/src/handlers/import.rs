@@ -0,0 +1,117 @@
+use crate::config::Config;
+use crate::handlers::audit::Actor;
+use crate::import::{self, ImageSources, ImportError, ImportFormat, ImportOptions};
+use crate::models::ImportQuery;
+use crate::repository::{ProductRepository, ProductTypeRepository, RepoError};
+use actix_multipart::Multipart;
+use actix_web::{HttpResponse, Responder, post, web};
+use futures_util::StreamExt;
+use std::collections::HashMap;
+
+// นำเข้าสินค้าหลายชิ้นจากไฟล์ CSV หรือ NDJSON (multipart)
+//   file               ไฟล์ import (รูปแบบคอลัมน์อยู่ที่ import.rs)
+//   images / images[]  ไฟล์รูปที่แถวอ้างถึงด้วยชื่อไฟล์
+// dry_run ตอบ 200 พร้อมผลตรวจ ถ้ามีแถวที่ไม่ผ่านตอบ 422 โดยไม่บันทึกอะไรเลย สำเร็จตอบ 201
+#[post("/api/products/import")]
+pub async fn post_products_import(
+    products_repo: web::Data<dyn ProductRepository>,
+    types_repo: web::Data<dyn ProductTypeRepository>,
+    config: web::Data<Config>,
+    actor: Actor,
+    query: web::Query<ImportQuery>,
+    mut payload: Multipart,
+) -> impl Responder {
+    let mut data = None;
+    let mut file_name = String::new();
+    let mut uploaded = HashMap::new();
+
+    while let Some(item) = payload.next().await {
+        let mut field = match item {
+            Ok(f) => f,
+            Err(_) => return HttpResponse::BadRequest().body("Invalid form data"),
+        };
+
+        let name = field.name().unwrap_or("").to_string();
+        if name != "file" && name != "images" && name != "images[]" {
+            continue;
+        }
+        let filename = field
+            .content_disposition()
+            .and_then(|cd| cd.get_filename())
+            .unwrap_or("")
+            .to_string();
+
+        let mut bytes = Vec::new();
+        while let Some(chunk) = field.next().await {
+            match chunk {
+                Ok(chunk) => bytes.extend_from_slice(&chunk),
+                Err(_) => return HttpResponse::BadRequest().body("Invalid form data"),
+            }
+        }
+
+        if name == "file" {
+            data = Some(bytes);
+            file_name = filename;
+        } else if filename.is_empty() {
+            return HttpResponse::BadRequest().body("Image file name is required");
+        } else if uploaded.insert(filename.clone(), bytes).is_some() {
+            return HttpResponse::BadRequest().body(format!("Duplicate image file: {}", filename));
+        }
+    }
+
+    let Some(data) = data else {
+        return HttpResponse::BadRequest().body("Missing import file");
+    };
+    let format = match &query.format {
+        Some(name) => match ImportFormat::from_name(name) {
+            Some(format) => format,
+            None => {
+                return HttpResponse::BadRequest()
+                    .body(format!("Unsupported import format: {}", name));
+            }
+        },
+        None => match ImportFormat::from_file_name(&file_name) {
+            Some(format) => format,
+            None => {
+                return HttpResponse::BadRequest()
+                    .body("Unknown import format, use format=csv or format=ndjson");
+            }
+        },
+    };
+    let options = ImportOptions {
+        format,
+        dry_run: query.dry_run.unwrap_or(false),
+        create_types: query.create_types.unwrap_or(false),
+    };
+
+    match import::import_products(
+        products_repo.get_ref(),
+        types_repo.get_ref(),
+        &config,
+        &actor.0,
+        &data,
+        &ImageSources::Uploaded(uploaded),
+        &options,
+    )
+    .await
+    {
+        Ok(report) if report.dry_run => HttpResponse::Ok().json(report),
+        Ok(report) if !report.errors.is_empty() => HttpResponse::UnprocessableEntity().json(report),
+        Ok(report) => {
+            println!(
+                "✅ Imported {} products, created {} product types",
+                report.product_ids.len(),
+                report.created_types.len()
+            );
+            HttpResponse::Created().json(report)
+        }
+        Err(ImportError::Invalid(reason)) => HttpResponse::BadRequest().body(reason),
+        Err(ImportError::Repo(RepoError::Conflict(reason))) => {
+            HttpResponse::Conflict().body(reason)
+        }
+        Err(e) => {
+            eprintln!("❌ Failed to import products: {}", e);
+            HttpResponse::InternalServerError().body("Import failed")
+        }
+    }
+}
@@ -1,3 +1,11 @@
+pub mod audit;
+pub mod backup;
+pub mod barcode;
+pub mod export;
+pub mod get_images;
+pub mod import;
 pub mod product_type;
 pub mod products;
-pub mod get_images;
+pub mod schema;
+pub mod stock;
+pub mod variants;
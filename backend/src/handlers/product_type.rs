@@ -1,110 +1,55 @@
+use crate::config::Config;
+use crate::handlers::audit::Actor;
+use crate::handlers::products::{field_text, product_name_error};
+use crate::handlers::stock::reorder_point_error;
 use crate::models::{
-    NewProductType, PaginatedResponse, PaginationInfo, ProductType, Querysearchandpage,
+    ListSort, PaginatedResponse, PaginationInfo, ProductTypePatch, Querysearchandpage,
 };
+use crate::repository::{ImageRepository, ProductTypeChanges, ProductTypeRepository, RepoError};
+use crate::trash;
 use actix_multipart::Multipart;
-use actix_web::dev::Path;
-use actix_web::{HttpResponse, Responder, delete, get, patch, post, put, web};
+use actix_web::{HttpResponse, Responder, delete, get, patch, post, web};
 use futures_util::StreamExt;
-use sqlx::Row;
-use sqlx::SqlitePool;
 use std::{fs, io::Write};
 
 #[get("/api/product-types")]
 pub async fn get_product_types(
-    db: web::Data<SqlitePool>,
+    types_repo: web::Data<dyn ProductTypeRepository>,
+    images_repo: web::Data<dyn ImageRepository>,
     query: web::Query<Querysearchandpage>,
 ) -> impl Responder {
-    let search_term = &query.search.clone().unwrap_or_default();
-    let page = query.page.unwrap_or(1);
-    let items_per_page = 10;
+    let search = query.search.clone().unwrap_or_default();
+    let trashed = query.trash.unwrap_or(false);
+    let page = query.page();
+    let items_per_page = query.per_page();
     let offset = (page - 1) * items_per_page;
 
+    let sort = query.sort.unwrap_or(ListSort::IdAsc);
+    if !sort.applies_to_types() {
+        return HttpResponse::BadRequest().body("Product types can only be sorted by id or name");
+    }
+
     // คำนวณจำนวนประเภทสินค้าทั้งหมดที่ตรงกับการค้นหา
-    let count_query = sqlx::query_scalar::<_, i64>(
-        r#"
-        SELECT COUNT(*)
-        FROM products_type pt
-        WHERE pt.products_type_name LIKE '%' || ? || '%'
-        "#,
-    )
-    .bind(search_term)
-    .fetch_one(db.get_ref())
-    .await;
+    let count_query = types_repo.count(&search, trashed).await;
 
     // ดึงข้อมูลประเภทสินค้าพื้นฐานก่อน - จำกัดแค่ items_per_page รายการ
-    let types_query = sqlx::query(
-        r#"
-        SELECT 
-            pt.id, 
-            pt.products_type_name
-        FROM 
-            products_type pt
-        WHERE 
-            pt.products_type_name LIKE '%' || ? || '%'
-        ORDER BY 
-            pt.id
-        LIMIT ? OFFSET ?;
-        "#,
-    )
-    .bind(search_term)
-    .bind(items_per_page)
-    .bind(offset)
-    .fetch_all(db.get_ref())
-    .await;
+    let types_query = types_repo
+        .list(&search, trashed, sort, items_per_page, offset)
+        .await;
 
     match (count_query, types_query) {
-        (Ok(total_count), Ok(type_rows)) => {
-            let mut product_types: Vec<ProductType> = Vec::new();
-
+        (Ok(total_count), Ok(mut product_types)) => {
             // สร้าง vector เก็บ ID ประเภทสินค้าที่ต้องการดึงรูปภาพ
-            let type_ids: Vec<i64> = type_rows
-                .iter()
-                .map(|row| row.get::<i64, _>("id"))
-                .collect();
-
-            // สร้าง ProductType จากข้อมูลพื้นฐาน (ยังไม่มีรูปภาพ)
-            for row in type_rows {
-                let id: i64 = row.get("id");
-                let name: String = row.get("products_type_name");
-
-                product_types.push(ProductType {
-                    id,
-                    name,
-                    images_path: Vec::new(), // เริ่มต้นด้วย vector ว่าง
-                });
-            }
+            let type_ids: Vec<i64> = product_types.iter().map(|pt| pt.id).collect();
 
-            // ถ้ามีประเภทสินค้าที่ต้องดึงรูปภาพ
-            if !type_ids.is_empty() {
-                // ดึงรูปภาพแยกต่างหาก
-                let images_query = sqlx::query(
-                    r#"
-                    SELECT product_type_id, image_path
-                    FROM images
-                    WHERE product_type_id IN (SELECT value FROM json_each(?))
-                    ORDER BY product_type_id, id
-                    "#,
-                )
-                .bind(serde_json::to_string(&type_ids).unwrap())
-                .fetch_all(db.get_ref())
-                .await;
-
-                // เพิ่มรูปภาพเข้าไปในประเภทสินค้าที่ตรงกัน
-                if let Ok(image_rows) = images_query {
-                    for row in image_rows {
-                        let type_id: i64 = row.get("product_type_id");
-                        let image_path: String = row.get("image_path");
-
-                        if image_path.starts_with("../databases/dbimages/") {
-                            let rel_path = image_path.replace("../databases/dbimages/", "");
-                            let web_path = format!("/images/{}", rel_path);
-
-                            if let Some(product_type) =
-                                product_types.iter_mut().find(|pt| pt.id == type_id)
-                            {
-                                product_type.images_path.push(web_path);
-                            }
-                        }
+            // ดึงรูปภาพแยกต่างหากแล้วเพิ่มเข้าไปในประเภทสินค้าที่ตรงกัน
+            if let Ok(image_paths) = images_repo.paths_for_types(&type_ids).await {
+                for (type_id, image_path) in image_paths {
+                    let web_path = format!("/images/{}", image_path);
+
+                    if let Some(product_type) = product_types.iter_mut().find(|pt| pt.id == type_id)
+                    {
+                        product_type.images_path.push(web_path);
                     }
                 }
             }
@@ -114,10 +59,13 @@ pub async fn get_product_types(
             let response = PaginatedResponse {
                 data: product_types,
                 pagination: PaginationInfo {
-                    total_items: total_count,
+                    total_items: Some(total_count),
                     items_per_page,
-                    current_page: page,
-                    total_pages,
+                    current_page: Some(page),
+                    total_pages: Some(total_pages),
+                    sort: Some(sort),
+                    next_cursor: None,
+                    prev_cursor: None,
                 },
             };
 
@@ -128,7 +76,12 @@ pub async fn get_product_types(
 }
 
 #[post("/api/product-types")]
-pub async fn post_product_types(db: web::Data<SqlitePool>, mut payload: Multipart) -> HttpResponse {
+pub async fn post_product_types(
+    types_repo: web::Data<dyn ProductTypeRepository>,
+    config: web::Data<Config>,
+    actor: Actor,
+    mut payload: Multipart,
+) -> HttpResponse {
     let mut product_type_name = String::new();
     let mut main_image_paths = Vec::new();
     let mut index = 0;
@@ -142,29 +95,37 @@ pub async fn post_product_types(db: web::Data<SqlitePool>, mut payload: Multipar
         let name = field.name().unwrap_or("").to_string();
 
         if name == "name" {
-            let mut data = Vec::new();
-            while let Some(chunk) = field.next().await {
-                data.extend_from_slice(&chunk.unwrap());
+            match field_text(&mut field).await {
+                Some(text) => product_type_name = text,
+                None => return HttpResponse::BadRequest().body("Invalid form data"),
             }
-            product_type_name = String::from_utf8(data).unwrap_or_default();
         }
 
         // รองรับ main_image[] สำหรับการอัปโหลดหลายไฟล์
         if name == "main_image" || name == "main_image[]" {
-            // ตรวจสอบว่ามีชื่อประเภทสินค้าแล้วหรือยัง ถ้ายังว่างให้ใช้ชื่อชั่วคราว
-            let temp_name = if product_type_name.is_empty() {
-                "temp_product".to_string()
-            } else {
-                product_type_name.clone()
-            };
+            // โฟลเดอร์รูปสร้างจากชื่อประเภท จึงต้องได้ชื่อที่ตรวจแล้วก่อนเขียนไฟล์
+            if product_name_error(&product_type_name).is_some() {
+                return HttpResponse::BadRequest()
+                    .body("Send a valid product type name before main_image");
+            }
 
-            let folder_path = format!("../databases/dbimages/{}/main", product_type_name);
-            let filename = format!("{}_{}.jpg", temp_name, index);
+            let folder_path = format!("{}/main", product_type_name);
+            let filename = format!("{}_{}.jpg", product_type_name, index);
             let file_path = format!("{}/{}", folder_path, filename);
 
-            fs::create_dir_all(&folder_path).unwrap();
+            let (folder, file) = match (
+                config.image_file(&folder_path),
+                config.image_file(&file_path),
+            ) {
+                (Ok(folder), Ok(file)) => (folder, file),
+                (Err(e), _) | (_, Err(e)) => return HttpResponse::BadRequest().body(e.to_string()),
+            };
+            if let Err(e) = fs::create_dir_all(&folder) {
+                eprintln!("❌ Failed to create folder {}: {}", folder.display(), e);
+                return HttpResponse::InternalServerError().body("Failed to create image folder");
+            }
 
-            if let Err(_) = save_file(&mut field, &file_path).await {
+            if save_file(&mut field, &file).await.is_err() {
                 return HttpResponse::InternalServerError().body("Failed to save file");
             }
 
@@ -177,352 +138,163 @@ pub async fn post_product_types(db: web::Data<SqlitePool>, mut payload: Multipar
     if product_type_name.is_empty() {
         return HttpResponse::BadRequest().body("Missing product type name");
     }
+    if product_name_error(&product_type_name).is_some() {
+        return HttpResponse::BadRequest().body("Product type name must not be a path");
+    }
 
     // ตรวจสอบว่ามีรูปภาพอย่างน้อย 1 รูป
     if main_image_paths.is_empty() {
         return HttpResponse::BadRequest().body("At least one image is required");
     }
 
-    // 🔄 เริ่ม Transaction
-    let mut tx = match db.begin().await {
-        Ok(t) => t,
-        Err(_) => {
-            return HttpResponse::InternalServerError().body("Failed to start DB transaction");
-        }
-    };
-
-    // 🧠 Insert ชื่อประเภท
-    let insert_result = sqlx::query("INSERT INTO products_type (products_type_name) VALUES (?)")
-        .bind(&product_type_name)
-        .execute(&mut *tx)
-        .await;
-
-    let result = match insert_result {
-        Ok(r) => r,
-        Err(_) => return HttpResponse::InternalServerError().body("Insert product type failed"),
-    };
-
-    let product_type_id = result.last_insert_rowid();
-
-    // 💾 Insert path รูป
-    for path in &main_image_paths {
-        if let Err(_) =
-            sqlx::query("INSERT INTO images (image_path, product_type_id) VALUES (?, ?)")
-                .bind(path)
-                .bind(product_type_id)
-                .execute(&mut *tx)
-                .await
-        {
-            return HttpResponse::InternalServerError().body("Insert image failed");
-        }
-    }
-
-    if let Err(_) = tx.commit().await {
-        return HttpResponse::InternalServerError().body("Commit failed");
+    // 🧠 Insert ชื่อประเภทและ path รูปใน transaction เดียวกัน
+    if let Err(e) = types_repo
+        .create(&actor.0, &product_type_name, &main_image_paths)
+        .await
+    {
+        eprintln!("❌ Failed to insert product type: {}", e);
+        return HttpResponse::InternalServerError().body("Insert product type failed");
     }
 
     HttpResponse::Ok().body("Product type post successfully")
 }
 
 // ฟังก์ชันช่วยบันทึกไฟล์
-async fn save_file(field: &mut actix_multipart::Field, filepath: &str) -> std::io::Result<()> {
+async fn save_file(
+    field: &mut actix_multipart::Field,
+    filepath: &std::path::Path,
+) -> std::io::Result<()> {
     let mut f = std::fs::File::create(filepath)?;
 
     while let Some(chunk) = field.next().await {
-        let data = chunk.map_err(|e| {
-            std::io::Error::new(std::io::ErrorKind::Other, format!("multipart error: {}", e))
-        })?;
+        let data = chunk.map_err(|e| std::io::Error::other(format!("multipart error: {}", e)))?;
         f.write_all(&data)?;
     }
 
     Ok(())
 }
-/*
-#[put("/api/product-types/{id}")]
-pub async fn update_product_type(
-    db: web::Data<SqlitePool>,
+
+// แก้ไขค่าเริ่มต้นของจุดสั่งซื้อ สินค้าในประเภทที่ไม่ได้ตั้งเองใช้ค่านี้
+#[patch("/api/product-types/{id}")]
+pub async fn patch_product_type(
+    types_repo: web::Data<dyn ProductTypeRepository>,
+    actor: Actor,
     path: web::Path<i64>,
-    // แก้
-    mut payload: Multipart,
+    json: web::Json<ProductTypePatch>,
 ) -> impl Responder {
     let id = path.into_inner();
-    let mut name = String::new();
-    let mut images_path = Vec::new();
+    let patch = json.into_inner();
+    if patch.reorder_threshold.is_none() && patch.reorder_quantity.is_none() {
+        return HttpResponse::BadRequest().body("No fields to update");
+    }
+    if let Some(message) = reorder_point_error(patch.reorder_threshold, patch.reorder_quantity) {
+        return HttpResponse::BadRequest().body(message);
+    }
 
-    let mut tx = match db.begin().await {
-        Ok(tx) => tx,
-        Err(_) => return HttpResponse::InternalServerError().body("Failed to begin transaction"),
+    let changes = ProductTypeChanges {
+        reorder_threshold: patch.reorder_threshold,
+        reorder_quantity: patch.reorder_quantity,
     };
 
-    if let Err(_) = sqlx::query("UPDATE products_type SET products_type_name = ? WHERE id = ?")
-        .bind(name)
-        .bind(id)
-        .execute(&mut *tx)
-        .await
-    {
-        return HttpResponse::InternalServerError().body("Update failed");
+    match types_repo.patch(&actor.0, id, &changes).await {
+        Ok(()) => {
+            println!("✅ Product type {} patched", id);
+            HttpResponse::Ok().body("Product type updated successfully")
+        }
+        Err(RepoError::NotFound) => HttpResponse::NotFound().body("Product type not found"),
+        Err(e) => {
+            eprintln!("❌ Failed to patch product type: {}", e);
+            HttpResponse::InternalServerError().body("Failed to update product type")
+        }
     }
+}
 
-    if let Err(_) = sqlx::query("DELETE FROM images WHERE product_type_id = ?")
-        .bind(id)
-        .execute(&mut *tx)
-        .await
-    {
-        return HttpResponse::InternalServerError().body("Delete old images failed");
-    }
+// ย้ายประเภทพร้อมสินค้าทั้งหมดในประเภทไปถังขยะ รูปภาพยังอยู่จนกว่าจะ purge
+#[delete("/api/product-types-all/{id}")]
+pub async fn delete_product_type_all(
+    types_repo: web::Data<dyn ProductTypeRepository>,
+    actor: Actor,
+    path: web::Path<i64>,
+) -> impl Responder {
+    let id = path.into_inner();
 
-    for path in images {
-        if let Err(_) =
-            sqlx::query("INSERT INTO images (image_path, product_type_id) VALUES (?, ?)")
-                .bind(path)
-                .bind(id)
-                .execute(&mut *tx)
-                .await
-        {
-            return HttpResponse::InternalServerError().body("Insert new image failed");
+    match types_repo.soft_delete_with_products(&actor.0, id).await {
+        Ok(()) => HttpResponse::Ok().body("Product type deleted successfully"),
+        Err(RepoError::NotFound) => HttpResponse::NotFound().body("Product type not found"),
+        Err(e) => {
+            eprintln!("❌ Failed to delete product type: {}", e);
+            HttpResponse::InternalServerError().body("Failed to delete product type")
         }
     }
-
-    if let Err(_) = tx.commit().await {
-        return HttpResponse::InternalServerError().body("Commit failed");
-    }
-
-    HttpResponse::Ok().body("Product type updated")
 }
 
-#[patch("/api/prduct-types-name/{id}")]
-pub async fn update_product_type_name(
-    db: web::Data<SqlitePool>,
-    path: web::Path<usize>,
-    mut payload: Multipart,
+// กู้คืนประเภทพร้อมสินค้าที่ถูกลบไปพร้อมกัน
+#[post("/api/product-types/{id}/restore")]
+pub async fn restore_product_type(
+    types_repo: web::Data<dyn ProductTypeRepository>,
+    actor: Actor,
+    path: web::Path<i64>,
 ) -> impl Responder {
     let id = path.into_inner();
 
-    let mut product_type_name = String::new();
-
-    while let Some(item) = payload.next().await {
-        let mut field = match item {
-            Ok(f) => f,
-            Err(_) => return HttpResponse::BadRequest().body("Invalid form data"),
-        };
-
-        let name = field.name().unwrap_or("").to_string();
-
-        if name == "name" {
-            let mut data = Vec::new();
-            while let Some(chunk) = field.next().await {
-                data.extend_from_slice(&chunk.unwrap());
-            }
-            product_type_name = String::from_utf8(data).unwrap_or_default();
+    match types_repo.restore(&actor.0, id).await {
+        Ok(()) => HttpResponse::Ok().body("Product type restored successfully"),
+        Err(RepoError::NotFound) => {
+            HttpResponse::NotFound().body("Product type not found in trash")
+        }
+        Err(e) => {
+            eprintln!("❌ Failed to restore product type: {}", e);
+            HttpResponse::InternalServerError().body("Failed to restore product type")
         }
     }
-
-    let mut tx = match db.begin().await {
-        Ok(tx) => tx,
-        Err(_) => return HttpResponse::InternalServerError().body("Failed to begin transaction"),
-    };
-
-    let current_name = sqlx::query("SELECT products_type_name FROM products_type WHERE id = (?)")
-        .bind(id.to_string())
-        .execute(&mut *tx)
-        .await;
-
-    if current_name != product_type_name {}
-
-    HttpResponse::Ok().body("edit product type pass")
 }
-*/
-#[delete("/api/product-types-all/{id}")]
-pub async fn delete_product_type_all(
-    db: web::Data<SqlitePool>,
+
+// ลบถาวรประเภทที่อยู่ในถังขยะ พร้อมสินค้าในประเภทและโฟลเดอร์รูปใน dbimages
+#[delete("/api/product-types/{id}/purge")]
+pub async fn purge_product_type(
+    types_repo: web::Data<dyn ProductTypeRepository>,
+    images_repo: web::Data<dyn ImageRepository>,
+    config: web::Data<Config>,
+    actor: Actor,
     path: web::Path<i64>,
 ) -> impl Responder {
     let id = path.into_inner();
 
-    let mut tx = match db.begin().await {
-        Ok(tx) => tx,
-        Err(_) => return HttpResponse::InternalServerError().body("Failed to begin transaction"),
-    };
-
-    // 🔍 ดึง path ของรูปภาพที่ต้องลบ
-    let image_paths = match sqlx::query_scalar::<_, String>(
-        "SELECT image_path FROM images WHERE product_type_id = ?",
+    match trash::purge_product_type(
+        types_repo.get_ref(),
+        images_repo.get_ref(),
+        &config,
+        &actor.0,
+        id,
     )
-    .bind(id)
-    .fetch_all(&mut *tx)
     .await
     {
-        Ok(paths) => paths,
-        Err(_) => return HttpResponse::InternalServerError().body("Failed to fetch image paths"),
-    };
-
-    // สมมุติว่า path มีรูปแบบเหมือนกันทั้งหมด
-    if let Some(first_path) = image_paths.get(0) {
-        let path = std::path::Path::new(first_path);
-
-        // ลบ 2 ระดับสุดท้ายออก: game/minecraft/minecraft_0.jpg -> ../databases/dbimages/
-        if let Some(grand_parent) = path.parent().and_then(|p| p.parent()) {
-            if grand_parent.exists() {
-                if let Err(e) = fs::remove_dir_all(grand_parent) {
-                    return HttpResponse::InternalServerError().body(format!(
-                        "Failed to delete directory:{} , {}",
-                        e,
-                        grand_parent.display()
-                    ));
-                }
-            }
+        Ok(()) => HttpResponse::Ok().body("Product type purged successfully"),
+        Err(RepoError::NotFound) => {
+            HttpResponse::NotFound().body("Product type not found in trash")
+        }
+        Err(e) => {
+            eprintln!("❌ Failed to purge product type: {}", e);
+            HttpResponse::InternalServerError().body("Failed to purge product type")
         }
     }
-
-    // ลบจากฐานข้อมูล
-    if let Err(_) = sqlx::query("DELETE FROM images WHERE product_type_id = ?")
-        .bind(id)
-        .execute(&mut *tx)
-        .await
-    {
-        return HttpResponse::InternalServerError().body("Failed to delete related images");
-    }
-
-    if let Err(_) = sqlx::query("DELETE FROM products WHERE products_type_id = ?")
-        .bind(id)
-        .execute(&mut *tx)
-        .await
-    {
-        return HttpResponse::InternalServerError().body("Failed to delete related products");
-    }
-
-    if let Err(_) = sqlx::query("DELETE FROM products_type WHERE id = ?")
-        .bind(id)
-        .execute(&mut *tx)
-        .await
-    {
-        return HttpResponse::InternalServerError().body("Failed to delete product type");
-    }
-
-    if let Err(_) = tx.commit().await {
-        return HttpResponse::InternalServerError().body("Failed to commit transaction");
-    }
-
-    HttpResponse::Ok().body("Product type deleted successfully")
 }
 
+// ย้ายประเภทไปถังขยะแต่เก็บสินค้าไว้เป็นสินค้าไม่มีประเภท ไม่แตะไฟล์รูป รูป main ของประเภทถูกลบตอน purge
 #[delete("/api/product-types/{id}")]
 pub async fn delete_product_type(
-    db: web::Data<SqlitePool>,
+    types_repo: web::Data<dyn ProductTypeRepository>,
+    actor: Actor,
     path: web::Path<i64>,
 ) -> impl Responder {
     let id = path.into_inner();
 
-    let mut tx: sqlx::Transaction<'static, sqlx::Sqlite> = match db.begin().await {
-        Ok(tx) => tx,
-        Err(_) => return HttpResponse::InternalServerError().body("Failed to begin transaction"),
-    };
-
-    let product_id =
-        match sqlx::query_scalar::<_, i64>("SELECT id FROM products WHERE products_type_id = ?")
-            .bind(id)
-            .fetch_one(&mut *tx)
-            .await
-        {
-            Ok(id) => id,
-            Err(e) => {
-                return HttpResponse::InternalServerError()
-                    .body(format!("Failed to get product_id: {}", e));
-            }
-        };
-    let image_paths =
-        match sqlx::query_scalar::<_, String>("SELECT image_path FROM images WHERE product_id = ?")
-            .bind(product_id)
-            .fetch_all(&mut *tx)
-            .await
-        {
-            Ok(paths) => paths,
-            Err(_) => {
-                return HttpResponse::InternalServerError().body("Failed to fetch image paths");
-            }
-        };
-
-    let product_type_name = match sqlx::query_scalar::<_, String>(
-        "SELECT products_type_name FROM products_type WHERE id = ?",
-    )
-    .bind(id)
-    .fetch_one(&mut *tx) // <--- ใช้ fetch_one เพราะต้องการค่าเดียว
-    .await
-    {
-        Ok(name) => name,
-        Err(_) => {
-            return HttpResponse::InternalServerError().body("Failed to get name product type");
-        }
-    };
-
-    for path in image_paths {
-
-        let old_dir = match std::path::Path::new(&path).parent() {
-            Some(p) => p,
-            None => {
-                return HttpResponse::InternalServerError().body("Failed to get old parent directory");
-            }
-        };
-
-        if let Err(e) = fs::remove_dir_all(old_dir) {
-            return HttpResponse::InternalServerError()
-                .body(format!("Failed to create folder: {}", e));
-        }
-
-        let new_image_path = path.replace(&product_type_name, "other"); // ต้องใช้ & เพราะเป็น reference
-
-        let new_dir = match std::path::Path::new(&new_image_path).parent() {
-            Some(p) => p,
-            None => {
-                return HttpResponse::InternalServerError().body("Failed to get parent directory");
-            }
-        };
-
-        if let Err(e) = fs::create_dir_all(new_dir) {
-            return HttpResponse::InternalServerError()
-                .body(format!("Failed to create folder: {}", e));
+    match types_repo.soft_delete_keep_products(&actor.0, id).await {
+        Ok(()) => HttpResponse::Ok().body("Product type deleted successfully"),
+        Err(RepoError::NotFound) => HttpResponse::NotFound().body("Product type not found"),
+        Err(e) => {
+            eprintln!("❌ Failed to delete product type: {}", e);
+            HttpResponse::InternalServerError().body("Failed to delete product type")
         }
-
-        if std::path::Path::new(&path).exists() {
-            if let Err(_) = fs::rename(&path, &new_image_path) {
-                return HttpResponse::InternalServerError().body(path);
-            }
-        }
-
-        if let Err(e) = sqlx::query(
-            "UPDATE images SET image_path = ? WHERE product_id = ? AND image_path = ?",
-        )
-        .bind(&new_image_path) // ค่าใหม่
-        .bind(product_id) // ใช้ id ดีกว่าใช้ชื่อ เพราะมัน unique ชัดเจน
-        .bind(&path) // อัปเดตเฉพาะภาพที่ตรง path เดิม
-        .execute(&mut *tx)
-        .await
-        {
-            return HttpResponse::InternalServerError()
-                .body(format!("Failed to update images: {}", e));
-        }
-    }
-
-    if let Err(_) =
-        sqlx::query("UPDATE products SET products_type_id = null WHERE products_type_id = ?")
-            .bind(id)
-            .execute(&mut *tx)
-            .await
-    {
-        return HttpResponse::InternalServerError().body("Failed to update products");
-    }
-
-    if let Err(_) = sqlx::query("DELETE FROM products_type WHERE id = ?")
-        .bind(id)
-        .execute(&mut *tx)
-        .await
-    {
-        return HttpResponse::InternalServerError().body("Failed to delete product type");
-    }
-
-    if let Err(_) = tx.commit().await {
-        return HttpResponse::InternalServerError().body("Failed to commit transaction");
     }
-
-    HttpResponse::Ok().body("Product type deleted successfully")
 }
@@ -1,33 +1,301 @@
 use std::fs;
 use std::io::Write;
-
-use crate::models::{NewProducts, PaginatedResponse, PaginationInfo, Products, Querysearchandpage};
+use std::path::PathBuf;
+
+use crate::barcode::{barcode_error, detect_format};
+use crate::config::Config;
+use crate::handlers::audit::Actor;
+use crate::handlers::stock::reorder_point_error;
+use crate::handlers::variants::{product_variants, sku_error};
+use crate::models::{
+    Currency, FacetQuery, ListSort, Money, NewProducts, PaginatedResponse, PaginationInfo,
+    ProductClone, ProductImage, ProductPatch, Products, Querysearchandpage,
+};
+use crate::repository::{
+    Comparison, DetailCondition, DetailFilter, ImageRecord, ImageRepository, ImageSlot, OTHER_TYPE,
+    PageSeek, ProductChanges, ProductFilter, ProductInput, ProductRepository,
+    ProductTypeRepository, RepoError, SortKey, TypeIdFilter, search_terms,
+};
+use crate::trash;
 use actix_multipart::Multipart;
-use actix_web::http::header;
-use actix_web::{HttpResponse, Responder, delete, get, post, put, web};
+use actix_web::http::header::{self, ContentType, EntityTag};
+use actix_web::{
+    HttpMessage, HttpRequest, HttpResponse, Responder, delete, get, patch, post, put, web,
+};
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
 use futures_util::StreamExt;
-use sqlx::Row;
-use sqlx::SqlitePool;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+// ตำแหน่งที่ next_cursor/prev_cursor ชี้ เข้ารหัสเป็น base64 ของ JSON ให้ไคลเอนต์ถือไว้แบบ opaque
+#[derive(Serialize, Deserialize)]
+struct Cursor {
+    sort: ListSort,
+    // true = หน้าก่อนตำแหน่งนี้, false = หน้าหลังตำแหน่งนี้
+    before: bool,
+    key: SortKey,
+}
+
+impl Cursor {
+    // relevance ไม่มี cursor (ดู SortValue::fits)
+    fn encode(sort: ListSort, before: bool, key: &SortKey) -> Option<String> {
+        if !key.value.fits(sort) {
+            return None;
+        }
+        let cursor = Cursor {
+            sort,
+            before,
+            key: key.clone(),
+        };
+        let json = serde_json::to_vec(&cursor).ok()?;
+        Some(URL_SAFE_NO_PAD.encode(json))
+    }
+
+    fn decode(token: &str) -> Option<Cursor> {
+        let json = URL_SAFE_NO_PAD.decode(token.trim()).ok()?;
+        let cursor: Cursor = serde_json::from_slice(&json).ok()?;
+        cursor.key.value.fits(cursor.sort).then_some(cursor)
+    }
 
-enum TypeIdFilter {
-    None,
-    IsNull,
-    Equal(i64),
+    fn seek(self) -> PageSeek {
+        if self.before {
+            PageSeek::Before(self.key)
+        } else {
+            PageSeek::After(self.key)
+        }
+    }
 }
 
+// แบบ page (ค่าเริ่มต้น) ใช้ LIMIT/OFFSET และนับจำนวนทั้งหมด
+// แบบ cursor (?cursor=) ใช้ keyset ต่อจากตำแหน่งใน token ไม่นับจำนวน และไม่ข้ามหรือซ้ำรายการเมื่อมีสินค้าเพิ่มระหว่างเปิดดู
+// ทั้งสองแบบส่ง next_cursor/prev_cursor กลับไป จึงเริ่มจากหน้าแรกแบบ page แล้วเลื่อนต่อด้วย cursor ได้
+// ยกเว้น sort=relevance ที่ใช้ได้แค่ page เพราะคะแนนของสินค้าเปลี่ยนได้ระหว่างเปิดดู
+// sort ตามราคาเรียงตามรหัสสกุลเงินก่อน แล้วจึงราคาภายในสกุลเงินเดียวกัน
 #[get("/api/products")]
 pub async fn get_products(
-    db: web::Data<SqlitePool>,
+    req: HttpRequest,
+    products_repo: web::Data<dyn ProductRepository>,
+    images_repo: web::Data<dyn ImageRepository>,
     query: web::Query<Querysearchandpage>,
 ) -> impl Responder {
-    let search_term = &query.search.clone().unwrap_or_default();
-    let items_per_page = 10;
-    let mut page = query.page.unwrap_or(1);
-    if page == 0 {
-        page = 1;
+    let page = query.page();
+    let items_per_page = query.per_page();
+    let search = query.search.clone().unwrap_or_default();
+
+    let cursor = match query.cursor.as_deref() {
+        None => None,
+        Some(token) => match Cursor::decode(token) {
+            Some(cursor) => Some(cursor),
+            None => return HttpResponse::BadRequest().body("Invalid cursor"),
+        },
+    };
+
+    // ถ้าไม่ระบุ sort แต่ส่ง cursor มา ใช้ sort ของ cursor
+    let sort = list_sort(query.sort.or(cursor.as_ref().map(|c| c.sort)), &search);
+    if cursor.as_ref().is_some_and(|c| c.sort != sort) {
+        return HttpResponse::BadRequest().body("Cursor does not match sort");
+    }
+    if sort == ListSort::Relevance && query.cursor.is_some() {
+        return HttpResponse::BadRequest().body("Relevance results can only be paged with page");
     }
-    let offset = (page - 1) * items_per_page;
 
+    let filter = match product_filter(&query, req.query_string(), search, sort) {
+        Ok(filter) => filter,
+        Err(message) => return HttpResponse::BadRequest().body(message),
+    };
+
+    let (products, pagination) = match cursor {
+        Some(cursor) => {
+            let before = cursor.before;
+            // ดึงเกินหนึ่งรายการเพื่อรู้ว่ายังมีหน้าต่อไปในทิศที่เลื่อนหรือไม่
+            match products_repo
+                .list(&filter, &cursor.seek(), items_per_page + 1)
+                .await
+            {
+                Ok(mut products) => {
+                    let has_more = products.len() as i64 > items_per_page;
+                    if has_more {
+                        if before {
+                            products.remove(0);
+                        } else {
+                            products.pop();
+                        }
+                    }
+                    let first = products.first().map(|(_, key)| key);
+                    let last = products.last().map(|(_, key)| key);
+                    let pagination = PaginationInfo {
+                        total_items: None,
+                        items_per_page,
+                        current_page: None,
+                        total_pages: None,
+                        sort: Some(sort),
+                        next_cursor: last
+                            .filter(|_| has_more || before)
+                            .and_then(|key| Cursor::encode(sort, false, key)),
+                        prev_cursor: first
+                            .filter(|_| has_more || !before)
+                            .and_then(|key| Cursor::encode(sort, true, key)),
+                    };
+                    (products, pagination)
+                }
+                Err(e) => return list_failed(e),
+            }
+        }
+        None => {
+            let offset = (page - 1) * items_per_page;
+            let count_result = products_repo.count(&filter).await;
+            let products_result = products_repo
+                .list(&filter, &PageSeek::Offset(offset), items_per_page)
+                .await;
+            match (count_result, products_result) {
+                (Ok(total_count), Ok(products)) => {
+                    let total_pages = (total_count + items_per_page - 1) / items_per_page;
+                    let first = products.first().map(|(_, key)| key);
+                    let last = products.last().map(|(_, key)| key);
+                    let pagination = PaginationInfo {
+                        total_items: Some(total_count),
+                        items_per_page,
+                        current_page: Some(page),
+                        total_pages: Some(total_pages),
+                        sort: Some(sort),
+                        next_cursor: last
+                            .filter(|_| page < total_pages)
+                            .and_then(|key| Cursor::encode(sort, false, key)),
+                        prev_cursor: first
+                            .filter(|_| page > 1)
+                            .and_then(|key| Cursor::encode(sort, true, key)),
+                    };
+                    (products, pagination)
+                }
+                (Err(e), _) | (_, Err(e)) => return list_failed(e),
+            }
+        }
+    };
+
+    let mut products: Vec<Products> = products.into_iter().map(|(product, _)| product).collect();
+
+    // สร้าง vector เก็บ ID สินค้าที่ต้องการดึงรูปภาพ
+    let product_ids: Vec<i64> = products.iter().map(|p| p.id).collect();
+
+    // ดึงรูปภาพแยกต่างหากแล้วเพิ่มเข้าไปในสินค้าที่ตรงกัน
+    if let Ok(image_paths) = images_repo.paths_for_products(&product_ids).await {
+        for (product_id, image_path) in image_paths {
+            // แปลงเป็น path ที่ให้ frontend เรียกผ่าน endpoint /images
+            let web_path = format!("/images/{}", image_path);
+
+            if let Some(product) = products.iter_mut().find(|p| p.id == product_id) {
+                product.images_path.push(web_path);
+            }
+        }
+    }
+
+    let response = PaginatedResponse {
+        data: products,
+        pagination,
+    };
+
+    HttpResponse::Ok().json(response)
+}
+
+// มีคำค้นแต่ไม่ระบุ sort จะเรียงตามความเกี่ยวข้อง ส่วน relevance ที่ไม่มีคำค้นจะเรียงตาม id
+pub fn list_sort(sort: Option<ListSort>, search: &str) -> ListSort {
+    let has_terms = !search_terms(search).is_empty();
+    match sort {
+        Some(ListSort::Relevance) | None if has_terms => ListSort::Relevance,
+        Some(ListSort::Relevance) | None => ListSort::IdAsc,
+        Some(sort) => sort,
+    }
+}
+
+fn query_currency(query: &Querysearchandpage) -> Result<Currency, String> {
+    match query.currency.as_deref() {
+        Some(text) => text.parse::<Currency>(),
+        None => Ok(Currency::default()),
+    }
+}
+
+const MAX_DETAIL_FILTERS: usize = 10;
+const MAX_DETAIL_KEY_CHARS: usize = 64;
+
+// ชื่อ key ใน detail มาจากฟอร์มจึงเป็นข้อความอิสระ (เช่นภาษาไทย)
+// แต่ห้ามมีอักขระที่มีความหมายใน JSON path หรือในไวยากรณ์ของตัวกรอง
+pub fn valid_detail_key(key: &str) -> bool {
+    !key.is_empty()
+        && key.chars().count() <= MAX_DETAIL_KEY_CHARS
+        && key.trim() == key
+        && !key
+            .chars()
+            .any(|c| c.is_control() || "\"\\.[]$*<>=".contains(c))
+}
+
+// ตัวกรอง detail จาก query เช่น detail.color=red, detail.ram_gb>=8, detail.ram_gb<8, detail.spec.cpu=i7
+// หลังแยก query ด้วย '=' แล้ว ">=" จะเหลือ '>' ท้ายชื่อ ส่วน ">" ที่ไม่มี '=' จะอยู่ในชื่อทั้งหมด
+fn detail_filters(query_string: &str) -> Result<Vec<DetailFilter>, String> {
+    let pairs = web::Query::<Vec<(String, String)>>::from_query(query_string)
+        .map_err(|e| format!("Invalid query: {}", e))?;
+
+    let mut filters = Vec::new();
+    for (name, value) in pairs.into_inner() {
+        let Some(rest) = name.strip_prefix("detail.") else {
+            continue;
+        };
+
+        let (key, comparison, operand) = if let Some(key) = rest.strip_suffix('>') {
+            (key, Some(Comparison::GreaterOrEqual), value.as_str())
+        } else if let Some(key) = rest.strip_suffix('<') {
+            (key, Some(Comparison::LessOrEqual), value.as_str())
+        } else if let Some(index) = rest.find(['>', '<'])
+            && value.is_empty()
+        {
+            let comparison = if rest[index..].starts_with('>') {
+                Comparison::Greater
+            } else {
+                Comparison::Less
+            };
+            (&rest[..index], Some(comparison), &rest[index + 1..])
+        } else {
+            (rest, None, value.as_str())
+        };
+
+        let path: Vec<String> = key.split('.').map(str::to_string).collect();
+        if !path.iter().all(|key| valid_detail_key(key)) {
+            return Err(format!("Invalid detail key: {}", key));
+        }
+
+        let condition = match comparison {
+            Some(comparison) => match operand.trim().parse::<f64>() {
+                Ok(number) if number.is_finite() => DetailCondition::Compare(comparison, number),
+                _ => {
+                    return Err(format!(
+                        "detail.{} {} needs a number, got: {}",
+                        key,
+                        comparison.sql(),
+                        operand
+                    ));
+                }
+            },
+            None => DetailCondition::Equals(operand.to_string()),
+        };
+        filters.push(DetailFilter { path, condition });
+    }
+
+    if filters.len() > MAX_DETAIL_FILTERS {
+        return Err(format!(
+            "Too many detail filters (max {})",
+            MAX_DETAIL_FILTERS
+        ));
+    }
+    Ok(filters)
+}
+
+// แปลงตัวกรองจาก query ทุกตัวใช้ร่วมกันได้ (AND) ขอบล่างต้องไม่มากกว่าขอบบน
+pub fn product_filter(
+    query: &Querysearchandpage,
+    query_string: &str,
+    search: String,
+    sort: ListSort,
+) -> Result<ProductFilter, String> {
     let type_filter = match &query.type_id {
         None => TypeIdFilter::None,
         Some(s) if s == "null" => TypeIdFilter::IsNull,
@@ -37,173 +305,229 @@ pub async fn get_products(
         },
     };
 
-    // สร้าง query พื้นฐานสำหรับนับจำนวนสินค้า
-    let mut count_sql = String::from(
-        r#"
-        SELECT COUNT(*)
-        FROM products p
-        WHERE p.name_products LIKE '%' || ? || '%'
-        "#,
-    );
+    let currency = query_currency(query)?;
+    let parse_price = |text: &Option<String>| {
+        text.as_deref()
+            .map(|text| Money::parse(text, currency))
+            .transpose()
+    };
+    let min_price = parse_price(&query.min_price)?;
+    let max_price = parse_price(&query.max_price)?;
+    if let (Some(min), Some(max)) = (min_price, max_price)
+        && min.amount > max.amount
+    {
+        return Err("min_price must not be greater than max_price".to_string());
+    }
 
-    // สร้าง query พื้นฐานสำหรับดึงข้อมูลสินค้า
-    let mut products_sql = String::from(
-        r#"
-        SELECT 
-            p.id,
-            p.name_products,
-            p.price,
-            p.detail,
-            p.stock,
-            p.created_at,
-            p.products_type_id,
-            pt.products_type_name
-        FROM 
-            products p
-        LEFT JOIN 
-            products_type pt ON p.products_type_id = pt.id
-        WHERE 
-            p.name_products LIKE '%' || ? || '%'
-        "#,
-    );
+    // in_stock รวมเข้ากับช่วง stock: true = อย่างน้อย 1 ชิ้น, false = ไม่เกิน 0
+    let (mut min_stock, mut max_stock) = (query.min_stock, query.max_stock);
+    match query.in_stock {
+        Some(true) => min_stock = Some(min_stock.unwrap_or(1).max(1)),
+        Some(false) => max_stock = Some(max_stock.unwrap_or(0).min(0)),
+        None => {}
+    }
+    if let (Some(min), Some(max)) = (min_stock, max_stock)
+        && min > max
+    {
+        return Err(match query.in_stock {
+            Some(_) => "in_stock conflicts with min_stock/max_stock".to_string(),
+            None => "min_stock must not be greater than max_stock".to_string(),
+        });
+    }
+
+    if let (Some(from), Some(to)) = (query.created_from, query.created_to)
+        && from > to
+    {
+        return Err("created_from must not be after created_to".to_string());
+    }
+
+    let detail = detail_filters(query_string)?;
+
+    // created_to รวมทั้งวัน เหมือน from/to ของ audit log
+    Ok(ProductFilter {
+        search,
+        type_id: type_filter,
+        trashed: query.trash.unwrap_or(false),
+        sort,
+        min_price,
+        max_price,
+        min_stock,
+        max_stock,
+        created_from: query
+            .created_from
+            .and_then(|date| date.and_hms_opt(0, 0, 0)),
+        created_to: query
+            .created_to
+            .and_then(|date| date.succ_opt())
+            .and_then(|date| date.and_hms_opt(0, 0, 0)),
+        detail,
+    })
+}
 
-    // เพิ่มเงื่อนไขกรองตาม type_id ถ้ามีการระบุ
-    match type_filter {
-        TypeIdFilter::IsNull => {
-            count_sql.push_str(" AND p.products_type_id IS NULL");
-            products_sql.push_str(" AND p.products_type_id IS NULL");
+const MAX_FACET_KEYS: usize = 10;
+const MAX_PRICE_BUCKETS: usize = 20;
+const DEFAULT_PRICE_BUCKETS: &str = "100,500,1000,5000";
+
+// จำนวนสินค้าตามประเภท ช่วงราคา สถานะ stock และค่าใน detail สำหรับทำแถบตัวกรอง
+// ใช้ query และตัวกรองชุดเดียวกับ GET /api/products (ไม่สน page/per_page/sort/cursor) จำนวนจึงตรงกับรายการ
+// ?detail_keys=color,spec.cpu เลือก key ที่ต้องการนับ ?price_buckets=100,500 กำหนดขอบของช่วงราคา
+#[get("/api/products/facets")]
+pub async fn get_product_facets(
+    req: HttpRequest,
+    products_repo: web::Data<dyn ProductRepository>,
+    query: web::Query<Querysearchandpage>,
+    facet_query: web::Query<FacetQuery>,
+) -> impl Responder {
+    let search = query.search.clone().unwrap_or_default();
+    let filter = match product_filter(&query, req.query_string(), search, ListSort::IdAsc) {
+        Ok(filter) => filter,
+        Err(message) => return HttpResponse::BadRequest().body(message),
+    };
+    let currency = match query_currency(&query) {
+        Ok(currency) => currency,
+        Err(message) => return HttpResponse::BadRequest().body(message),
+    };
+
+    let mut price_bounds = Vec::new();
+    let bucket_text = facet_query
+        .price_buckets
+        .as_deref()
+        .unwrap_or(DEFAULT_PRICE_BUCKETS);
+    for text in bucket_text
+        .split(',')
+        .filter(|text| !text.trim().is_empty())
+    {
+        match Money::parse(text, currency) {
+            Ok(bound) => price_bounds.push(bound.amount),
+            Err(message) => return HttpResponse::BadRequest().body(message),
         }
-        TypeIdFilter::Equal(_) => {
-            count_sql.push_str(" AND p.products_type_id = ?");
-            products_sql.push_str(" AND p.products_type_id = ?");
+    }
+    if price_bounds.len() > MAX_PRICE_BUCKETS {
+        return HttpResponse::BadRequest().body(format!(
+            "Too many price buckets (max {})",
+            MAX_PRICE_BUCKETS
+        ));
+    }
+    if price_bounds.windows(2).any(|pair| pair[0] >= pair[1]) {
+        return HttpResponse::BadRequest().body("price_buckets must be in increasing order");
+    }
+
+    let mut detail_paths = Vec::new();
+    let keys = facet_query.detail_keys.as_deref().unwrap_or_default();
+    for key in keys.split(',').map(str::trim).filter(|key| !key.is_empty()) {
+        let path: Vec<String> = key.split('.').map(str::to_string).collect();
+        if !path.iter().all(|key| valid_detail_key(key)) {
+            return HttpResponse::BadRequest().body(format!("Invalid detail key: {}", key));
         }
-        TypeIdFilter::None => {}
+        detail_paths.push(path);
+    }
+    if detail_paths.len() > MAX_FACET_KEYS {
+        return HttpResponse::BadRequest()
+            .body(format!("Too many detail_keys (max {})", MAX_FACET_KEYS));
     }
 
-    // เพิ่ม ORDER BY, LIMIT และ OFFSET ให้กับ query สินค้า
-    products_sql.push_str(
-        r#"
-        ORDER BY 
-            p.id
-        LIMIT ? OFFSET ?;
-        "#,
-    );
+    match products_repo
+        .facets(&filter, currency, &price_bounds, &detail_paths)
+        .await
+    {
+        Ok(facets) => HttpResponse::Ok().json(facets),
+        Err(e) => {
+            eprintln!("❌ Failed to count product facets: {}", e);
+            HttpResponse::InternalServerError().body("Database query failed")
+        }
+    }
+}
 
-    // สร้าง query สำหรับนับจำนวนสินค้า
-    let mut count_query = sqlx::query_scalar::<_, i64>(&count_sql).bind(search_term);
-
-    // สร้าง query สำหรับดึงข้อมูลสินค้า
-    let mut products_query = sqlx::query(&products_sql).bind(search_term);
-
-    // เพิ่ม parameter type_id ถ้ามีการระบุ
-    if let TypeIdFilter::Equal(id) = type_filter {
-        count_query = count_query.bind(id);
-        products_query = products_query.bind(id);
-    }
-
-    // เพิ่ม parameter limit และ offset
-    products_query = products_query.bind(items_per_page).bind(offset);
-
-    // ดำเนินการ query
-    let count_result = count_query.fetch_one(db.get_ref()).await;
-    let products_result = products_query.fetch_all(db.get_ref()).await;
-
-    match (count_result, products_result) {
-        (Ok(total_count), Ok(product_rows)) => {
-            let mut products: Vec<Products> = Vec::new();
-
-            // สร้าง vector เก็บ ID สินค้าที่ต้องการดึงรูปภาพ
-            let product_ids: Vec<i64> = product_rows
-                .iter()
-                .map(|row| row.get::<i64, _>("id"))
-                .collect();
-
-            // สร้าง Products จากข้อมูลพื้นฐาน (ยังไม่มีรูปภาพ)
-            for row in product_rows {
-                let id: i64 = row.get("id");
-                let name_product: String = row.get("name_products");
-                let price: f64 = row.get("price");
-                let detail: serde_json::Value = row.get("detail");
-                let stock: i64 = row.get("stock");
-                let create_at: chrono::NaiveDateTime = row.get("created_at");
-                let products_type_id: Option<i64> = row.get("products_type_id");
-                let products_type_name: Option<String> = row.get("products_type_name");
-
-                products.push(Products {
-                    id,
-                    name_product,
-                    price,
-                    detail,
-                    stock,
-                    create_at,
-                    products_type_id,
-                    products_type_name,
-                    images_path: Vec::new(), // เริ่มต้นด้วย vector ว่าง
-                });
-            }
+fn list_failed(e: RepoError) -> HttpResponse {
+    eprintln!("❌ Failed to list products: {}", e);
+    HttpResponse::InternalServerError().body("Database query failed")
+}
 
-            // ถ้ามีสินค้าที่ต้องดึงรูปภาพ
-            if !product_ids.is_empty() {
-                // ดึงรูปภาพแยกต่างหาก
-                let images_query = sqlx::query(
-                    r#"
-                    SELECT product_id, image_path
-                    FROM images
-                    WHERE product_id IN (SELECT value FROM json_each(?))
-                    ORDER BY product_id, id
-                    "#,
-                )
-                .bind(serde_json::to_string(&product_ids).unwrap())
-                .fetch_all(db.get_ref())
-                .await;
+// สินค้าที่ไม่อยู่ในถังขยะพร้อม path รูปและ variant สำหรับ frontend
+pub async fn load_product(
+    products_repo: &dyn ProductRepository,
+    images_repo: &dyn ImageRepository,
+    product_id: i64,
+) -> Result<Option<Products>, RepoError> {
+    let Some(mut product) = products_repo.find(product_id).await? else {
+        return Ok(None);
+    };
+    product.images_path = images_repo
+        .paths_for_product(product_id)
+        .await?
+        .iter()
+        .map(|image_path| format!("/images/{}", image_path))
+        .collect();
+    product.variants = Some(product_variants(products_repo, product_id).await?);
+    Ok(Some(product))
+}
 
-                // เพิ่มรูปภาพเข้าไปในสินค้าที่ตรงกัน
-                if let Ok(image_rows) = images_query {
-                    for row in image_rows {
-                        let product_id: i64 = row.get("product_id");
-                        let image_path: String = row.get("image_path");
-
-                        if image_path.starts_with("../databases/dbimages/") {
-                            // แปลงเป็น path ที่ให้ frontend เรียกผ่าน endpoint ใหม่
-                            let rel_path = image_path.replace("../databases/dbimages/", "");
-                            let web_path = format!("/images/{}", rel_path); // ✅ เปลี่ยนตรงนี้
-
-                            if let Some(product) = products.iter_mut().find(|p| p.id == product_id)
-                            {
-                                product.images_path.push(web_path);
-                            }
-                        }
-                    }
-                }
-            }
+// body JSON ของสินค้ากับ ETag (sha256 ของ body)
+fn product_body(product: &Products) -> Option<(Vec<u8>, EntityTag)> {
+    let body = serde_json::to_vec(product).ok()?;
+    let etag = EntityTag::new_strong(format!("{:x}", Sha256::digest(&body)));
+    Some((body, etag))
+}
 
-            let total_pages = (total_count + items_per_page - 1) / items_per_page;
+// สินค้าชิ้นเดียวพร้อมชื่อประเภท รูปตามลำดับที่เพิ่ม และ detail
+// ETag คือ sha256 ของ body ถ้า If-None-Match ตรงกันจะตอบ 304 โดยไม่ส่ง body
+#[get("/api/products/{id}")]
+pub async fn get_product(
+    req: HttpRequest,
+    products_repo: web::Data<dyn ProductRepository>,
+    images_repo: web::Data<dyn ImageRepository>,
+    path: web::Path<i64>,
+) -> impl Responder {
+    let product_id = path.into_inner();
 
-            let response = PaginatedResponse {
-                data: products,
-                pagination: PaginationInfo {
-                    total_items: total_count,
-                    items_per_page,
-                    current_page: page,
-                    total_pages,
-                },
-            };
+    let product =
+        match load_product(products_repo.get_ref(), images_repo.get_ref(), product_id).await {
+            Ok(Some(product)) => product,
+            Ok(None) => return HttpResponse::NotFound().body("Product not found"),
+            Err(e) => {
+                eprintln!("❌ Failed to fetch product: {}", e);
+                return HttpResponse::InternalServerError().body("Database query failed");
+            }
+        };
 
-            HttpResponse::Ok().json(response)
-        }
-        _ => HttpResponse::InternalServerError().body("Database query failed"),
+    let Some((body, etag)) = product_body(&product) else {
+        return HttpResponse::InternalServerError().body("Failed to encode product");
+    };
+
+    let not_modified = match req.get_header::<header::IfNoneMatch>() {
+        Some(header::IfNoneMatch::Any) => true,
+        Some(header::IfNoneMatch::Items(tags)) => tags.iter().any(|tag| tag.weak_eq(&etag)),
+        None => false,
+    };
+    if not_modified {
+        return HttpResponse::NotModified()
+            .insert_header(header::ETag(etag))
+            .finish();
     }
+
+    HttpResponse::Ok()
+        .insert_header(header::ETag(etag))
+        .content_type(ContentType::json())
+        .body(body)
 }
 
 #[post("/api/products")]
-pub async fn post_products(db: web::Data<SqlitePool>, mut payload: Multipart) -> impl Responder {
+pub async fn post_products(
+    products_repo: web::Data<dyn ProductRepository>,
+    types_repo: web::Data<dyn ProductTypeRepository>,
+    config: web::Data<Config>,
+    actor: Actor,
+    mut payload: Multipart,
+) -> impl Responder {
     let mut name_products = String::new();
-    let mut price = 0.0;
+    let mut price_text = String::new();
+    let mut currency_text = String::new();
     let mut detail = String::new();
     let mut main_image_paths = Vec::new();
-    let mut stock = 0;
+    let mut stock: i64 = 0;
     let mut product_type_name = String::new();
     let mut index = 0;
+    let mut files = ImageFileChanges::default();
 
     while let Some(item) = payload.next().await {
         let mut field = match item {
@@ -213,104 +537,102 @@ pub async fn post_products(db: web::Data<SqlitePool>, mut payload: Multipart) ->
 
         let name = field.name().unwrap_or("").to_string();
 
-        if name == "name" {
-            let mut data = Vec::new();
-            while let Some(chunk) = field.next().await {
-                data.extend_from_slice(&chunk.unwrap());
+        if name == "main_image" || name == "main_image[]" {
+            // โฟลเดอร์รูปสร้างจากชื่อสินค้าและชื่อประเภท จึงต้องได้ชื่อที่ตรวจแล้วก่อนเขียนไฟล์
+            if product_type_name.is_empty() || product_type_name == "null" {
+                product_type_name = OTHER_TYPE.to_string();
             }
-            name_products = String::from_utf8(data).unwrap_or_default();
-        }
-
-        if name == "price" {
-            let mut data = Vec::new();
-            while let Some(chunk) = field.next().await {
-                data.extend_from_slice(&chunk.unwrap());
+            if let Some(message) = product_name_error(&name_products) {
+                return HttpResponse::BadRequest()
+                    .body(format!("{} (send name before main_image)", message));
             }
-            price = String::from_utf8(data)
-                .unwrap_or_default()
-                .parse::<f64>()
-                .unwrap_or(0.0);
-        }
-
-        if name == "detail" {
-            let mut data = Vec::new();
-            while let Some(chunk) = field.next().await {
-                data.extend_from_slice(&chunk.unwrap());
-            }
-            detail = String::from_utf8(data).unwrap_or_default();
-        }
-
-        if name == "stock" {
-            let mut data = Vec::new();
-            while let Some(chunk) = field.next().await {
-                data.extend_from_slice(&chunk.unwrap());
+            if product_name_error(&product_type_name).is_some() {
+                return HttpResponse::BadRequest().body("Invalid product type name");
             }
-            stock = String::from_utf8(data)
-                .unwrap_or_default()
-                .parse::<i32>()
-                .unwrap_or(0);
-        }
 
-        if name == "product_type_name" {
-            let mut data = Vec::new();
-            while let Some(chunk) = field.next().await {
-                data.extend_from_slice(&chunk.unwrap());
-            }
-            product_type_name = String::from_utf8(data).unwrap_or_default();
-        }
+            let folder_path = format!("{}/{}", product_type_name, name_products);
+            let filename = format!("{}_{}.jpg", name_products, index);
+            let file_path = format!("{}/{}", folder_path, filename);
 
-        if name == "main_image" || name == "main_image[]" {
-            // ตรวจสอบว่ามีชื่อประเภทสินค้าแล้วหรือยัง ถ้ายังว่างให้ใช้ชื่อชั่วคราว
-            let temp_name = if name_products.is_empty() {
-                "temp_product".to_string()
-            } else {
-                name_products.clone()
+            let (folder, file) = match (
+                config.image_file(&folder_path),
+                config.image_file(&file_path),
+            ) {
+                (Ok(folder), Ok(file)) => (folder, file),
+                (Err(e), _) | (_, Err(e)) => return HttpResponse::BadRequest().body(e.to_string()),
             };
-
-            if product_type_name == "null" {
-                product_type_name = "other".to_string();
+            // จดโฟลเดอร์ที่สร้างใหม่ (ลึกก่อน) เพื่อลบทิ้งถ้าบันทึกไม่สำเร็จ
+            for dir in [Some(folder.as_path()), folder.parent()]
+                .into_iter()
+                .flatten()
+            {
+                if !dir.exists() && !files.created_dirs.iter().any(|created| created == dir) {
+                    files.created_dirs.push(dir.to_path_buf());
+                }
+            }
+            if let Err(e) = fs::create_dir_all(&folder) {
+                eprintln!("❌ Failed to create folder {}: {}", folder.display(), e);
+                return HttpResponse::InternalServerError().body("Failed to create image folder");
             }
-            let folder_path = format!(
-                "../databases/dbimages/{}/{}",
-                product_type_name, name_products
-            );
-            let filename = format!("{}_{}.jpg", temp_name, index);
-            let file_path = format!("{}/{}", folder_path, filename);
-            
-            fs::create_dir_all(&folder_path).unwrap();
 
-            if save_file(&mut field, &file_path).await.is_err() {
+            files.created.push(file.clone());
+            if save_file(&mut field, &file).await.is_err() {
                 return HttpResponse::InternalServerError().body("Failed to save file");
             }
 
             main_image_paths.push(file_path.clone());
             index += 1;
+            continue;
+        }
+
+        let Some(text) = field_text(&mut field).await else {
+            return HttpResponse::BadRequest().body("Invalid form data");
+        };
+        match name.as_str() {
+            "name" => name_products = text,
+            "price" => price_text = text,
+            "currency" => currency_text = text,
+            "detail" => detail = text,
+            "stock" => stock = text.parse::<i64>().unwrap_or(0),
+            "product_type_name" => product_type_name = text,
+            _ => {}
         }
     }
 
-    let mut tx = match db.begin().await {
-        Ok(tx) => {
-            println!("✅ Started DB transaction");
-            tx
+    if let Some(message) = product_name_error(&name_products) {
+        return HttpResponse::BadRequest().body(message);
+    }
+
+    // ตรวจราคาหลังอ่านฟอร์มครบ เพราะ currency อาจมาหลัง price
+    let currency = if currency_text.trim().is_empty() {
+        Currency::default()
+    } else {
+        match currency_text.parse::<Currency>() {
+            Ok(c) => c,
+            Err(e) => return HttpResponse::BadRequest().body(e),
         }
-        Err(e) => {
-            eprintln!("❌ Failed to begin transaction: {:?}", e);
-            return HttpResponse::InternalServerError().body("Failed to begin transaction");
+    };
+    let price = match Money::parse(&price_text, currency) {
+        Ok(p) => p,
+        Err(e) => return HttpResponse::BadRequest().body(e),
+    };
+
+    // detail ต้องเป็น JSON ที่ถูกต้อง (เก็บเป็น JSONB ใน Postgres) ค่าว่างถือเป็น {}
+    let detail: serde_json::Value = if detail.trim().is_empty() {
+        serde_json::json!({})
+    } else {
+        match serde_json::from_str(&detail) {
+            Ok(v) => v,
+            Err(_) => return HttpResponse::BadRequest().body("Invalid detail JSON"),
         }
     };
 
     // 🟡 หา id ของ products_type_name
     let products_type_id: Option<i64> =
-        if !product_type_name.is_empty() && product_type_name != "other"{
+        if !product_type_name.is_empty() && product_type_name != OTHER_TYPE {
             println!("🔍 Looking up product type: {}", product_type_name);
-            let row = sqlx::query("SELECT id FROM products_type WHERE products_type_name = ?")
-                .bind(&product_type_name)
-                .fetch_optional(&mut *tx)
-                .await;
-
-            match row {
-                Ok(Some(row)) => {
-                    let id = row.get::<i64, _>("id");
+            match types_repo.find_id_by_name(&product_type_name).await {
+                Ok(Some(id)) => {
                     println!("✅ Found product type ID: {}", id);
                     Some(id)
                 }
@@ -319,7 +641,7 @@ pub async fn post_products(db: web::Data<SqlitePool>, mut payload: Multipart) ->
                     return HttpResponse::BadRequest().body("Invalid product type name");
                 }
                 Err(e) => {
-                    eprintln!("❌ DB error when looking for product type: {:?}", e);
+                    eprintln!("❌ DB error when looking for product type: {}", e);
                     return HttpResponse::InternalServerError().body("Failed to find product type");
                 }
             }
@@ -328,101 +650,610 @@ pub async fn post_products(db: web::Data<SqlitePool>, mut payload: Multipart) ->
             None
         };
 
-    let result = sqlx::query(
-        "INSERT INTO products (name_products, price, detail, stock, products_type_id)
-         VALUES (?, ?, ?, ?, ?)",
-    )
-    .bind(&name_products)
-    .bind(price)
-    .bind(&detail)
-    .bind(stock)
-    .bind(products_type_id)
-    .execute(&mut *tx)
-    .await;
+    let product = ProductInput {
+        name: name_products,
+        price,
+        detail,
+        stock,
+        products_type_id,
+    };
+
+    // สินค้าและรูปภาพถูกบันทึกใน transaction เดียวกัน
+    let product_id = match products_repo
+        .create(&actor.0, &product, &main_image_paths)
+        .await
+    {
+        Ok(id) => id,
+        Err(e) => {
+            eprintln!("❌ Failed to insert product: {}", e);
+            return HttpResponse::InternalServerError().body("Insert failed");
+        }
+    };
+    println!("✅ Product inserted with ID: {}", product_id);
+    files.commit();
+
+    println!("✅ Product and images inserted successfully");
+    HttpResponse::Found()
+        .append_header((
+            header::LOCATION,
+            format!("{}/products", config.frontend_url),
+        ))
+        .finish()
+}
+
+// คัดลอกสินค้า (แถวสินค้า detail ประเภท และรูป) เป็นสินค้าใหม่ แล้วตอบสินค้าใหม่
+// ไม่คัดลอก sku, barcode และ variant (รวมแกนตัวเลือก) เพราะ sku/barcode ต้องไม่ซ้ำ
+// body JSON (ไม่บังคับ) แทนค่าบาง field ของสำเนา ไฟล์รูปถูกคัดลอกไปที่ {type}/{folder}/{name}_{n}.jpg
+// โดย folder คือ {name} หรือ {name}_2, {name}_3, ... ที่ยังไม่มี สำเนาจึงมีโฟลเดอร์ของตัวเองเสมอ
+// ไม่ปนกับไฟล์ของสินค้าต้นฉบับหรือสินค้าอื่นที่ชื่อเดียวกัน
+#[post("/api/products/{id}/clone")]
+pub async fn clone_product(
+    products_repo: web::Data<dyn ProductRepository>,
+    types_repo: web::Data<dyn ProductTypeRepository>,
+    images_repo: web::Data<dyn ImageRepository>,
+    config: web::Data<Config>,
+    actor: Actor,
+    path: web::Path<i64>,
+    body: web::Bytes,
+) -> impl Responder {
+    let source_id = path.into_inner();
+    println!("🟢 Cloning product with ID: {}", source_id);
+
+    let overrides: ProductClone = if body.iter().all(u8::is_ascii_whitespace) {
+        ProductClone::default()
+    } else {
+        match serde_json::from_slice(&body) {
+            Ok(overrides) => overrides,
+            Err(e) => return HttpResponse::BadRequest().body(format!("Invalid JSON body: {}", e)),
+        }
+    };
+
+    let source = match products_repo.find(source_id).await {
+        Ok(Some(product)) => product,
+        Ok(None) => return HttpResponse::NotFound().body("Product not found"),
+        Err(e) => {
+            eprintln!("❌ Failed to fetch product: {}", e);
+            return HttpResponse::InternalServerError().body("Database query failed");
+        }
+    };
+    let source_images = match images_repo.images_for_product(source_id).await {
+        Ok(images) => images,
+        Err(e) => {
+            eprintln!("❌ Failed to fetch images: {}", e);
+            return HttpResponse::InternalServerError().body("Failed to fetch image paths");
+        }
+    };
 
-    if let Err(e) = result {
-        eprintln!("❌ Failed to insert product: {:?}", e);
-        return HttpResponse::InternalServerError().body("Insert failed");
+    let name = match overrides.name_product.map(|name| name.trim().to_string()) {
+        Some(name) => match product_name_error(&name) {
+            Some(message) => return HttpResponse::BadRequest().body(message),
+            None => name,
+        },
+        None => source.name_product.clone(),
+    };
+    let price = overrides.price.unwrap_or(source.price);
+    let stock = overrides.stock.unwrap_or(source.stock);
+    if stock < 0 {
+        return HttpResponse::BadRequest().body("Stock must not be negative");
+    }
+    let detail = overrides.detail.unwrap_or(source.detail);
+    if !detail.is_object() {
+        return HttpResponse::BadRequest().body("detail must be a JSON object");
     }
 
-    let insert_result = result.unwrap();
-    let product_id = insert_result.last_insert_rowid();
-    println!("✅ Product inserted with ID: {}", product_id);
+    let (products_type_id, type_name) = match overrides.products_type_name {
+        None => (source.products_type_id, source.products_type_name),
+        Some(None) => (None, None),
+        Some(Some(type_name)) if type_name == OTHER_TYPE => (None, None),
+        Some(Some(type_name)) => match types_repo.find_id_by_name(&type_name).await {
+            Ok(Some(id)) => (Some(id), Some(type_name)),
+            Ok(None) => return HttpResponse::BadRequest().body("Invalid product type name"),
+            Err(e) => {
+                eprintln!("❌ Error finding product type: {}", e);
+                return HttpResponse::InternalServerError().body("Failed to find product type");
+            }
+        },
+    };
 
-    for path in &main_image_paths {
-        println!("📷 Inserting image path: {}", path);
-        if let Err(e) = sqlx::query("INSERT INTO images (image_path, product_id) VALUES (?, ?)")
-            .bind(path)
-            .bind(product_id)
-            .execute(&mut *tx)
-            .await
-        {
-            eprintln!("❌ Failed to insert image {}: {:?}", path, e);
-            return HttpResponse::InternalServerError().body("Insert image failed");
+    let type_folder = type_name.as_deref().unwrap_or(OTHER_TYPE);
+    // ชื่อของต้นฉบับที่บันทึกไว้ก่อนมีการตรวจชื่ออาจเป็น path ได้ ต้องตรวจก่อนเขียนไฟล์
+    if product_name_error(type_folder).is_some() || product_name_error(&name).is_some() {
+        return HttpResponse::Conflict()
+            .body("Product or type name is not a valid folder name, rename it first");
+    }
+
+    let mut files = ImageFileChanges::default();
+    let mut folder_path = String::new();
+    if !source_images.is_empty() {
+        if let Err(e) = config.image_file(type_folder).and_then(fs::create_dir_all) {
+            return HttpResponse::InternalServerError()
+                .body(format!("Failed to create folder: {}", e));
+        }
+        // create_dir ล้มเหลวถ้ามีโฟลเดอร์อยู่แล้ว การ clone พร้อมกันจึงไม่ได้โฟลเดอร์เดียวกัน
+        let mut suffix = 1;
+        folder_path = loop {
+            let candidate = match suffix {
+                1 => format!("{}/{}", type_folder, name),
+                _ => format!("{}/{}_{}", type_folder, name, suffix),
+            };
+            suffix += 1;
+            let dir = match config.image_file(&candidate) {
+                Ok(dir) => dir,
+                Err(e) => {
+                    return HttpResponse::InternalServerError()
+                        .body(format!("Failed to create folder: {}", e));
+                }
+            };
+            match fs::create_dir(&dir) {
+                Ok(()) => {
+                    files.created_dirs.push(dir);
+                    break candidate;
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => continue,
+                Err(e) => {
+                    return HttpResponse::InternalServerError()
+                        .body(format!("Failed to create folder: {}", e));
+                }
+            }
+        };
+    }
+
+    let mut image_paths = Vec::new();
+    for (index, image) in source_images.iter().enumerate() {
+        let file_path = format!("{}/{}_{}.jpg", folder_path, name, index);
+        let (source, file) = match (
+            config.image_file(&image.image_path),
+            config.image_file(&file_path),
+        ) {
+            (Ok(source), Ok(file)) => (source, file),
+            (Err(e), _) | (_, Err(e)) => {
+                eprintln!("❌ Failed to copy image {}: {}", image.image_path, e);
+                return HttpResponse::InternalServerError().body("Failed to copy image file");
+            }
+        };
+        files.created.push(file.clone());
+        if let Err(e) = fs::copy(source, &file) {
+            eprintln!("❌ Failed to copy image {}: {}", image.image_path, e);
+            return HttpResponse::InternalServerError().body("Failed to copy image file");
         }
+        image_paths.push(file_path);
     }
 
-    if let Err(e) = tx.commit().await {
-        eprintln!("❌ Failed to commit transaction: {:?}", e);
-        return HttpResponse::InternalServerError().body("Transaction commit failed");
+    let product = ProductInput {
+        name,
+        price,
+        detail,
+        stock,
+        products_type_id,
+    };
+    let product_id = match products_repo
+        .duplicate(&actor.0, source_id, &product, &image_paths)
+        .await
+    {
+        Ok(id) => id,
+        Err(RepoError::NotFound) => return HttpResponse::NotFound().body("Product not found"),
+        Err(e) => {
+            eprintln!("❌ Failed to insert product: {}", e);
+            return HttpResponse::InternalServerError().body("Insert failed");
+        }
+    };
+    files.commit();
+    println!(
+        "✅ Product {} cloned as {} with {} images",
+        source_id,
+        product_id,
+        image_paths.len()
+    );
+
+    match load_product(products_repo.get_ref(), images_repo.get_ref(), product_id).await {
+        Ok(Some(product)) => HttpResponse::Created().json(product),
+        Ok(None) => HttpResponse::NotFound().body("Product not found"),
+        Err(e) => {
+            eprintln!("❌ Failed to fetch product: {}", e);
+            HttpResponse::InternalServerError().body("Database query failed")
+        }
     }
+}
 
-    println!("✅ Product and images inserted successfully");
-    HttpResponse::Found()
-        .append_header((header::LOCATION, "http://localhost:8080/products"))
-        .finish()
+// ชื่อสินค้าเป็นชื่อโฟลเดอร์ของรูปด้วย ({type}/{name}) จึงห้ามว่างและห้ามเป็น path
+pub fn product_name_error(name: &str) -> Option<&'static str> {
+    if name.is_empty() {
+        return Some("Product name must not be empty");
+    }
+    if name.contains(['/', '\\']) || name == "." || name == ".." {
+        return Some("Product name must not be a path");
+    }
+    None
 }
 
-async fn save_file(field: &mut actix_multipart::Field, filepath: &str) -> std::io::Result<()> {
+async fn save_file(
+    field: &mut actix_multipart::Field,
+    filepath: &std::path::Path,
+) -> std::io::Result<()> {
     let mut f = std::fs::File::create(filepath)?;
 
     while let Some(chunk) = field.next().await {
-        let data = chunk.map_err(|e| {
-            std::io::Error::new(std::io::ErrorKind::Other, format!("multipart error: {}", e))
-        })?;
+        let data = chunk.map_err(|e| std::io::Error::other(format!("multipart error: {}", e)))?;
         f.write_all(&data)?;
     }
 
     Ok(())
 }
 
-#[put("/api/products/{id}")]
-pub async fn update_product(
-    db: web::Data<SqlitePool>,
+pub async fn field_text(field: &mut actix_multipart::Field) -> Option<String> {
+    let mut data = Vec::new();
+    while let Some(chunk) = field.next().await {
+        data.extend_from_slice(&chunk.ok()?);
+    }
+    String::from_utf8(data).ok()
+}
+
+// ไฟล์ที่เขียนหรือพักไว้ระหว่างแก้ไขรูป ถ้าไม่ commit (เช่น return ก่อนฐานข้อมูลสำเร็จ) จะถูกคืนสภาพเดิมตอน drop
+#[derive(Default)]
+struct ImageFileChanges {
+    created: Vec<PathBuf>,
+    // โฟลเดอร์ที่สร้างใหม่ ลบทิ้งถ้ายังว่างหลังลบไฟล์ใน created
+    created_dirs: Vec<PathBuf>,
+    // (ไฟล์เดิม, ชื่อชั่วคราว) ของรูปที่จะลบ
+    staged: Vec<(PathBuf, PathBuf)>,
+    committed: bool,
+}
+
+impl ImageFileChanges {
+    // เปลี่ยนชื่อไฟล์ของรูปที่จะลบไว้ก่อน ยังย้ายกลับได้จนกว่าจะ commit
+    fn stage_removal(&mut self, file: PathBuf) -> std::io::Result<()> {
+        if !file.exists() {
+            return Ok(());
+        }
+        let mut staged = file.clone().into_os_string();
+        staged.push(".removing");
+        let staged = PathBuf::from(staged);
+        fs::rename(&file, &staged)?;
+        self.staged.push((file, staged));
+        Ok(())
+    }
+
+    fn commit(mut self) {
+        self.committed = true;
+        for (_, staged) in &self.staged {
+            if let Err(e) = fs::remove_file(staged) {
+                eprintln!("⚠️ Failed to remove file {}: {}", staged.display(), e);
+            }
+        }
+    }
+}
+
+impl Drop for ImageFileChanges {
+    fn drop(&mut self) {
+        if self.committed {
+            return;
+        }
+        for file in self.created.iter().filter(|file| file.exists()) {
+            if let Err(e) = fs::remove_file(file) {
+                eprintln!("⚠️ Failed to remove file {}: {}", file.display(), e);
+            }
+        }
+        for (file, staged) in &self.staged {
+            if let Err(e) = fs::rename(staged, file) {
+                eprintln!("⚠️ Failed to restore file {}: {}", file.display(), e);
+            }
+        }
+        for dir in &self.created_dirs {
+            let _ = fs::remove_dir(dir);
+        }
+    }
+}
+
+// ลำดับรูปใหม่และ id ที่ถูกลบ จาก remove_image และ order
+// รูปที่ไม่อยู่ใน order ต่อท้ายตามลำดับเดิม แล้วตามด้วยไฟล์ใหม่ตามลำดับที่ส่ง
+fn image_slots(
+    current: &[ImageRecord],
+    new_paths: &[String],
+    remove_texts: &[String],
+    order_text: &str,
+) -> Result<(Vec<ImageSlot>, Vec<i64>), String> {
+    let known_id = |token: &str| -> Result<i64, String> {
+        let id = token
+            .parse::<i64>()
+            .map_err(|_| format!("Invalid image id: {}", token))?;
+        if current.iter().any(|image| image.id == id) {
+            Ok(id)
+        } else {
+            Err(format!("Image {} does not belong to this product", id))
+        }
+    };
+
+    let mut removed = Vec::new();
+    for token in remove_texts.iter().flat_map(|text| text.split(',')) {
+        let token = token.trim();
+        if token.is_empty() {
+            continue;
+        }
+        let id = known_id(token)?;
+        if !removed.contains(&id) {
+            removed.push(id);
+        }
+    }
+
+    let mut slots = Vec::new();
+    for token in order_text.split(',').map(str::trim) {
+        if token.is_empty() {
+            continue;
+        }
+        let slot = if let Some(index) = token.strip_prefix("new:") {
+            let path = index
+                .parse::<usize>()
+                .ok()
+                .and_then(|index| new_paths.get(index))
+                .ok_or_else(|| format!("No uploaded image for {}", token))?;
+            ImageSlot::New(path.clone())
+        } else {
+            let id = known_id(token)?;
+            if removed.contains(&id) {
+                return Err(format!("Image {} is both removed and ordered", id));
+            }
+            ImageSlot::Existing(id)
+        };
+        if slots.contains(&slot) {
+            return Err(format!("Duplicate entry in order: {}", token));
+        }
+        slots.push(slot);
+    }
+
+    let rest_existing = current
+        .iter()
+        .filter(|image| !removed.contains(&image.id))
+        .map(|image| ImageSlot::Existing(image.id));
+    let rest_new = new_paths.iter().map(|path| ImageSlot::New(path.clone()));
+    for slot in rest_existing.chain(rest_new).collect::<Vec<_>>() {
+        if !slots.contains(&slot) {
+            slots.push(slot);
+        }
+    }
+
+    Ok((slots, removed))
+}
+
+async fn product_images(
+    images_repo: &dyn ImageRepository,
+    product_id: i64,
+) -> Result<Vec<ProductImage>, RepoError> {
+    Ok(images_repo
+        .images_for_product(product_id)
+        .await?
+        .into_iter()
+        .map(|image| ProductImage {
+            id: image.id,
+            image_path: format!("/images/{}", image.image_path),
+        })
+        .collect())
+}
+
+// รูปของสินค้าพร้อม id ตามลำดับที่แสดง
+#[get("/api/products/{id}/images")]
+pub async fn get_product_images(
+    products_repo: web::Data<dyn ProductRepository>,
+    images_repo: web::Data<dyn ImageRepository>,
     path: web::Path<i64>,
-    json: web::Json<NewProducts>,
 ) -> impl Responder {
     let product_id = path.into_inner();
-    println!("🟢 Updating product with ID: {}", product_id);
 
-    let name_product = &json.name_product;
-    let price = &json.price;
-    let images = &json.images_path;
-    let detail = &json.detail;
-    let stock = &json.stock;
-    let products_type_name = &json.products_type_name;
+    match products_repo.find(product_id).await {
+        Ok(Some(_)) => {}
+        Ok(None) => return HttpResponse::NotFound().body("Product not found"),
+        Err(e) => {
+            eprintln!("❌ Failed to fetch product: {}", e);
+            return HttpResponse::InternalServerError().body("Database query failed");
+        }
+    }
+
+    match product_images(images_repo.get_ref(), product_id).await {
+        Ok(images) => HttpResponse::Ok().json(images),
+        Err(e) => {
+            eprintln!("❌ Failed to fetch images: {}", e);
+            HttpResponse::InternalServerError().body("Failed to fetch image paths")
+        }
+    }
+}
+
+// เพิ่ม ลบ และเรียงรูปของสินค้าในครั้งเดียว (multipart)
+//   main_image / main_image[]      ไฟล์รูปใหม่ บันทึกแบบเดียวกับ post_products
+//   remove_image / remove_image[]  id ของรูปที่จะลบ (คั่นด้วย , ได้) ไฟล์ใน dbimages ถูกลบด้วย
+//   order                          ลำดับใหม่ เช่น "12,new:0,7" (new:n คือไฟล์ใหม่ลำดับที่ n นับจาก 0)
+// ไฟล์และฐานข้อมูลสำเร็จหรือไม่สำเร็จพร้อมกัน แล้วตอบรายการรูปหลังแก้ไข
+#[patch("/api/products/{id}/images")]
+pub async fn update_product_images(
+    products_repo: web::Data<dyn ProductRepository>,
+    images_repo: web::Data<dyn ImageRepository>,
+    config: web::Data<Config>,
+    actor: Actor,
+    path: web::Path<i64>,
+    mut payload: Multipart,
+) -> impl Responder {
+    let product_id = path.into_inner();
+    println!("🟢 Updating images of product with ID: {}", product_id);
 
-    let mut tx = match db.begin().await {
-        Ok(tx) => tx,
+    let product = match products_repo.find(product_id).await {
+        Ok(Some(product)) => product,
+        Ok(None) => return HttpResponse::NotFound().body("Product not found"),
         Err(e) => {
-            eprintln!("❌ Failed to begin transaction: {:?}", e);
-            return HttpResponse::InternalServerError().body("Failed to begin transaction");
+            eprintln!("❌ Failed to fetch product: {}", e);
+            return HttpResponse::InternalServerError().body("Database query failed");
+        }
+    };
+    let current = match images_repo.images_for_product(product_id).await {
+        Ok(images) => images,
+        Err(e) => {
+            eprintln!("❌ Failed to fetch images: {}", e);
+            return HttpResponse::InternalServerError().body("Failed to fetch image paths");
+        }
+    };
+
+    let type_name = product.products_type_name.as_deref().unwrap_or(OTHER_TYPE);
+    // ชื่อที่บันทึกไว้ก่อนมีการตรวจชื่ออาจเป็น path ที่ออกนอก images_dir ได้ ต้องตรวจก่อนเขียนไฟล์
+    if product_name_error(type_name).is_some()
+        || product_name_error(&product.name_product).is_some()
+    {
+        return HttpResponse::Conflict()
+            .body("Product or type name is not a valid folder name, rename it first");
+    }
+    let folder_path = format!("{}/{}", type_name, product.name_product);
+
+    let mut files = ImageFileChanges::default();
+    let mut new_paths = Vec::new();
+    let mut remove_texts = Vec::new();
+    let mut order_text = String::new();
+    let mut index = 0;
+
+    while let Some(item) = payload.next().await {
+        let mut field = match item {
+            Ok(f) => f,
+            Err(_) => return HttpResponse::BadRequest().body("Invalid form data"),
+        };
+
+        let name = field.name().unwrap_or("").to_string();
+
+        if name == "remove_image" || name == "remove_image[]" {
+            match field_text(&mut field).await {
+                Some(text) => remove_texts.push(text),
+                None => return HttpResponse::BadRequest().body("Invalid form data"),
+            }
+        }
+
+        if name == "order" {
+            match field_text(&mut field).await {
+                Some(text) => order_text = text,
+                None => return HttpResponse::BadRequest().body("Invalid form data"),
+            }
+        }
+
+        if name == "main_image" || name == "main_image[]" {
+            // ชื่อไฟล์แบบเดียวกับ post_products ข้ามเลขที่มีไฟล์หรือรูปในฐานข้อมูลอยู่แล้ว
+            let file_path = loop {
+                let candidate = format!("{}/{}_{}.jpg", folder_path, product.name_product, index);
+                index += 1;
+                if !current.iter().any(|image| image.image_path == candidate)
+                    && !config
+                        .image_file(&candidate)
+                        .is_ok_and(|file| file.exists())
+                {
+                    break candidate;
+                }
+            };
+
+            if let Err(e) = config.image_file(&folder_path).and_then(fs::create_dir_all) {
+                return HttpResponse::InternalServerError()
+                    .body(format!("Failed to create folder: {}", e));
+            }
+
+            let file = match config.image_file(&file_path) {
+                Ok(file) => file,
+                Err(e) => return HttpResponse::BadRequest().body(e.to_string()),
+            };
+            files.created.push(file.clone());
+            if save_file(&mut field, &file).await.is_err() {
+                return HttpResponse::InternalServerError().body("Failed to save file");
+            }
+
+            new_paths.push(file_path);
         }
+    }
+
+    if new_paths.is_empty()
+        && remove_texts.iter().all(|text| text.trim().is_empty())
+        && order_text.trim().is_empty()
+    {
+        return HttpResponse::BadRequest().body("No image changes");
+    }
+
+    let (slots, removed) = match image_slots(&current, &new_paths, &remove_texts, &order_text) {
+        Ok(plan) => plan,
+        Err(e) => return HttpResponse::BadRequest().body(e),
     };
 
-    let products_type_id: Option<i64> = if let Some(type_name) = products_type_name {
-        let row = sqlx::query("SELECT id FROM products_type WHERE products_type_name = ?")
-            .bind(type_name)
-            .fetch_optional(&mut *tx)
-            .await;
+    for image in current.iter().filter(|image| removed.contains(&image.id)) {
+        if let Err(e) = config
+            .image_file(&image.image_path)
+            .and_then(|file| files.stage_removal(file))
+        {
+            eprintln!("❌ Failed to remove image {}: {}", image.image_path, e);
+            return HttpResponse::InternalServerError().body("Failed to remove image file");
+        }
+    }
+
+    match products_repo
+        .update_images(&actor.0, product_id, &slots)
+        .await
+    {
+        Ok(()) => {}
+        Err(RepoError::NotFound) => return HttpResponse::NotFound().body("Product not found"),
+        Err(RepoError::Conflict(reason)) => return HttpResponse::Conflict().body(reason),
+        Err(e) => {
+            eprintln!("❌ Failed to update images: {}", e);
+            return HttpResponse::InternalServerError().body("Update failed");
+        }
+    }
+    files.commit();
+
+    println!(
+        "✅ Product images updated: {} added, {} removed",
+        new_paths.len(),
+        removed.len()
+    );
+    match product_images(images_repo.get_ref(), product_id).await {
+        Ok(images) => HttpResponse::Ok().json(images),
+        Err(e) => {
+            eprintln!("❌ Failed to fetch images: {}", e);
+            HttpResponse::InternalServerError().body("Failed to fetch image paths")
+        }
+    }
+}
+
+// images_path ใน JSON อ้างถึงไฟล์ที่มีอยู่แล้วใน images_dir เท่านั้น
+// ไฟล์ใหม่ต้องอัปโหลดผ่าน PATCH /api/products/{id}/images
+fn images_path_error(config: &Config, paths: &[String]) -> Option<String> {
+    paths.iter().find_map(|path| match config.image_file(path) {
+        Ok(file) if file.is_file() => None,
+        _ => Some(format!("Image file not found: {}", path)),
+    })
+}
+
+// แทนที่สินค้าทั้งตัว ตรวจค่าแบบเดียวกับ patch_product
+#[put("/api/products/{id}")]
+pub async fn update_product(
+    products_repo: web::Data<dyn ProductRepository>,
+    types_repo: web::Data<dyn ProductTypeRepository>,
+    config: web::Data<Config>,
+    actor: Actor,
+    path: web::Path<i64>,
+    json: web::Json<NewProducts>,
+) -> impl Responder {
+    let product_id = path.into_inner();
+    println!("🟢 Updating product with ID: {}", product_id);
+
+    let json = json.into_inner();
+    let name = json.name_product.trim().to_string();
+    if let Some(message) = product_name_error(&name) {
+        return HttpResponse::BadRequest().body(message);
+    }
+    if json.stock < 0 {
+        return HttpResponse::BadRequest().body("Stock must not be negative");
+    }
+    if !json.detail.is_object() {
+        return HttpResponse::BadRequest().body("detail must be a JSON object");
+    }
+    if let Some(message) = images_path_error(&config, &json.images_path) {
+        return HttpResponse::BadRequest().body(message);
+    }
 
-        match row {
-            Ok(Some(row)) => Some(row.get("id")),
+    let products_type_id: Option<i64> = if let Some(type_name) = &json.products_type_name {
+        match types_repo.find_id_by_name(type_name).await {
+            Ok(Some(id)) => Some(id),
             Ok(None) => {
                 return HttpResponse::BadRequest().body("Invalid product type name");
             }
             Err(e) => {
-                eprintln!("❌ Error finding product type: {:?}", e);
+                eprintln!("❌ Error finding product type: {}", e);
                 return HttpResponse::InternalServerError().body("Failed to find product type");
             }
         }
@@ -430,126 +1261,417 @@ pub async fn update_product(
         None
     };
 
-    let result = sqlx::query(
-        "UPDATE products
-         SET name_products = ?, price = ?, detail = ?, stock = ?, products_type_id = ?
-         WHERE id = ?",
-    )
-    .bind(name_product)
-    .bind(price)
-    .bind(detail)
-    .bind(stock)
-    .bind(products_type_id)
-    .bind(product_id)
-    .execute(&mut *tx)
-    .await;
-
-    if let Err(e) = result {
-        eprintln!("❌ Failed to update product: {:?}", e);
-        return HttpResponse::InternalServerError().body("Update failed");
-    }
-
-    // 🔴 ลบรูปเก่าก่อน
-    if let Err(e) = sqlx::query("DELETE FROM images WHERE product_id = ?")
-        .bind(product_id)
-        .execute(&mut *tx)
+    let product = ProductInput {
+        name,
+        price: json.price,
+        detail: json.detail,
+        stock: json.stock,
+        products_type_id,
+    };
+
+    // 🔴 รูปเก่าถูกแทนที่ด้วย images_path ทั้งหมด
+    match products_repo
+        .update(&actor.0, product_id, &product, &json.images_path)
         .await
     {
-        eprintln!("❌ Failed to delete old images: {:?}", e);
-        return HttpResponse::InternalServerError().body("Delete old images failed");
+        Ok(()) => {}
+        Err(RepoError::NotFound) => {
+            return HttpResponse::NotFound().body("Product not found");
+        }
+        Err(e) => {
+            eprintln!("❌ Failed to update product: {}", e);
+            return HttpResponse::InternalServerError().body("Update failed");
+        }
     }
 
-    // 🟢 เพิ่มรูปใหม่
-    for path in images {
-        if let Err(e) = sqlx::query("INSERT INTO images (image_path, product_id) VALUES (?, ?)")
-            .bind(path)
-            .bind(product_id)
-            .execute(&mut *tx)
-            .await
-        {
-            eprintln!("❌ Failed to insert image {}: {:?}", path, e);
-            return HttpResponse::InternalServerError().body("Insert image failed");
+    println!("✅ Product updated successfully");
+    HttpResponse::Ok().body("Product updated successfully")
+}
+
+// แก้ไขเฉพาะ field ที่ส่งมา รูปภาพไม่เปลี่ยนถ้าไม่ส่ง images_path แล้วตอบสินค้าหลังแก้ไขพร้อม ETag
+#[patch("/api/products/{id}")]
+pub async fn patch_product(
+    products_repo: web::Data<dyn ProductRepository>,
+    types_repo: web::Data<dyn ProductTypeRepository>,
+    images_repo: web::Data<dyn ImageRepository>,
+    config: web::Data<Config>,
+    actor: Actor,
+    path: web::Path<i64>,
+    json: web::Json<ProductPatch>,
+) -> impl Responder {
+    let product_id = path.into_inner();
+    let patch = json.into_inner();
+    if patch.name_product.is_none()
+        && patch.price.is_none()
+        && patch.detail.is_none()
+        && patch.stock.is_none()
+        && patch.products_type_name.is_none()
+        && patch.images_path.is_none()
+        && patch.allow_backorder.is_none()
+        && patch.reorder_threshold.is_none()
+        && patch.reorder_quantity.is_none()
+        && patch.sku.is_none()
+        && patch.barcode.is_none()
+        && patch.barcode_format.is_none()
+    {
+        return HttpResponse::BadRequest().body("No fields to update");
+    }
+
+    let name = match patch.name_product.map(|name| name.trim().to_string()) {
+        Some(name) => match product_name_error(&name) {
+            Some(message) => return HttpResponse::BadRequest().body(message),
+            None => Some(name),
+        },
+        None => None,
+    };
+    if patch.stock.is_some_and(|stock| stock < 0) {
+        return HttpResponse::BadRequest().body("Stock must not be negative");
+    }
+    if let Some(message) = reorder_point_error(patch.reorder_threshold, patch.reorder_quantity) {
+        return HttpResponse::BadRequest().body(message);
+    }
+    let sku = match patch.sku {
+        Some(Some(sku)) => {
+            let sku = sku.trim().to_string();
+            if let Some(message) = sku_error(&sku) {
+                return HttpResponse::BadRequest().body(message);
+            }
+            Some(Some(sku))
+        }
+        sku => sku,
+    };
+    let barcode = match (patch.barcode, patch.barcode_format) {
+        (Some(Some(code)), format) => {
+            let code = code.trim().to_string();
+            let format = format.unwrap_or_else(|| detect_format(&code));
+            if let Some(message) = barcode_error(format, &code) {
+                return HttpResponse::BadRequest().body(message);
+            }
+            Some(Some((format, code)))
+        }
+        (_, Some(_)) => {
+            return HttpResponse::BadRequest().body("barcode_format requires barcode");
         }
+        (barcode, None) => barcode.map(|_| None),
+    };
+    // ตัวกรอง detail.* และ facet อ่าน detail เป็น object
+    if patch
+        .detail
+        .as_ref()
+        .is_some_and(|detail| !detail.is_object())
+    {
+        return HttpResponse::BadRequest().body("detail must be a JSON object");
     }
+    if let Some(message) = patch
+        .images_path
+        .as_ref()
+        .and_then(|paths| images_path_error(&config, paths))
+    {
+        return HttpResponse::BadRequest().body(message);
+    }
+
+    let products_type_id = match patch.products_type_name {
+        None => None,
+        Some(None) => Some(None),
+        Some(Some(type_name)) if type_name == OTHER_TYPE => Some(None),
+        Some(Some(type_name)) => match types_repo.find_id_by_name(&type_name).await {
+            Ok(Some(id)) => Some(Some(id)),
+            Ok(None) => return HttpResponse::BadRequest().body("Invalid product type name"),
+            Err(e) => {
+                eprintln!("❌ Error finding product type: {}", e);
+                return HttpResponse::InternalServerError().body("Failed to find product type");
+            }
+        },
+    };
+
+    let changes = ProductChanges {
+        name,
+        price: patch.price,
+        detail: patch.detail,
+        stock: patch.stock,
+        allow_backorder: patch.allow_backorder,
+        reorder_threshold: patch.reorder_threshold,
+        reorder_quantity: patch.reorder_quantity,
+        products_type_id,
+        image_paths: patch.images_path,
+        sku,
+        barcode,
+    };
 
-    if let Err(e) = tx.commit().await {
-        eprintln!("❌ Failed to commit update transaction: {:?}", e);
-        return HttpResponse::InternalServerError().body("Transaction commit failed");
+    match products_repo.patch(&actor.0, product_id, &changes).await {
+        Ok(()) => {}
+        Err(RepoError::NotFound) => return HttpResponse::NotFound().body("Product not found"),
+        Err(RepoError::Conflict(message)) => return HttpResponse::Conflict().body(message),
+        Err(e) => {
+            eprintln!("❌ Failed to patch product: {}", e);
+            return HttpResponse::InternalServerError().body("Update failed");
+        }
     }
 
-    println!("✅ Product updated successfully");
-    HttpResponse::Ok().body("Product updated successfully")
+    let product =
+        match load_product(products_repo.get_ref(), images_repo.get_ref(), product_id).await {
+            Ok(Some(product)) => product,
+            Ok(None) => return HttpResponse::NotFound().body("Product not found"),
+            Err(e) => {
+                eprintln!("❌ Failed to fetch product: {}", e);
+                return HttpResponse::InternalServerError().body("Database query failed");
+            }
+        };
+    let Some((body, etag)) = product_body(&product) else {
+        return HttpResponse::InternalServerError().body("Failed to encode product");
+    };
+
+    println!("✅ Product {} patched", product_id);
+    HttpResponse::Ok()
+        .insert_header(header::ETag(etag))
+        .content_type(ContentType::json())
+        .body(body)
 }
 
+// ย้ายสินค้าไปถังขยะ รูปภาพยังอยู่จนกว่าจะ purge
 #[delete("/api/products/{id}")]
-pub async fn delete_product(db: web::Data<SqlitePool>, path: web::Path<i64>) -> impl Responder {
+pub async fn delete_product(
+    products_repo: web::Data<dyn ProductRepository>,
+    actor: Actor,
+    path: web::Path<i64>,
+) -> impl Responder {
     let product_id = path.into_inner();
-    println!("🔴 Deleting product with ID: {}", product_id);
+    println!("🔴 Moving product with ID {} to trash", product_id);
 
-    let mut tx = match db.begin().await {
-        Ok(tx) => tx,
+    match products_repo.soft_delete(&actor.0, product_id).await {
+        Ok(()) => {}
+        Err(RepoError::NotFound) => return HttpResponse::NotFound().body("Product not found"),
         Err(e) => {
-            eprintln!("❌ Failed to begin transaction: {:?}", e);
-            return HttpResponse::InternalServerError().body("Failed to begin transaction");
+            eprintln!("❌ Failed to delete product: {}", e);
+            return HttpResponse::InternalServerError().body("Failed to delete product");
         }
-    };
+    }
 
-    let image_paths =
-        match sqlx::query_scalar::<_, String>("SELECT image_path FROM images WHERE product_id = ?")
-            .bind(product_id)
-            .fetch_all(&mut *tx)
-            .await
-        {
-            Ok(paths) => paths,
-            Err(_) => {
-                return HttpResponse::InternalServerError()
-                    .body("Failed to fetch image path of products");
-            }
-        };
+    println!("✅ Product moved to trash");
+    HttpResponse::Ok().body("Product deleted successfully")
+}
 
-    if let Some(first_path) = image_paths.get(0) {
-        let path = std::path::Path::new(first_path);
-
-        // ลบโฟลเดอร์ระดับ parent (1 ระดับ) เช่น game/minecraft/minecraft_0.jpg -> ลบ minecraft
-        if let Some(parent_folder) = path.parent() {
-            if parent_folder.exists() {
-                if let Err(e) = fs::remove_dir_all(parent_folder) {
-                    return HttpResponse::InternalServerError().body(format!(
-                        "Failed to delete folder: {}, {}",
-                        e,
-                        parent_folder.display()
-                    ));
-                }
-            }
+#[post("/api/products/{id}/restore")]
+pub async fn restore_product(
+    products_repo: web::Data<dyn ProductRepository>,
+    actor: Actor,
+    path: web::Path<i64>,
+) -> impl Responder {
+    let product_id = path.into_inner();
+
+    match products_repo.restore(&actor.0, product_id).await {
+        Ok(()) => HttpResponse::Ok().body("Product restored successfully"),
+        Err(RepoError::NotFound) => HttpResponse::NotFound().body("Product not found in trash"),
+        Err(RepoError::Conflict(reason)) => HttpResponse::Conflict().body(reason),
+        Err(e) => {
+            eprintln!("❌ Failed to restore product: {}", e);
+            HttpResponse::InternalServerError().body("Failed to restore product")
         }
     }
+}
 
-    // 🔴 ลบรูปภาพที่เกี่ยวข้อง
-    if let Err(e) = sqlx::query("DELETE FROM images WHERE product_id = ?")
-        .bind(product_id)
-        .execute(&mut *tx)
-        .await
+// ลบถาวรสินค้าที่อยู่ในถังขยะ พร้อมโฟลเดอร์รูปใน dbimages
+#[delete("/api/products/{id}/purge")]
+pub async fn purge_product(
+    products_repo: web::Data<dyn ProductRepository>,
+    images_repo: web::Data<dyn ImageRepository>,
+    config: web::Data<Config>,
+    actor: Actor,
+    path: web::Path<i64>,
+) -> impl Responder {
+    let product_id = path.into_inner();
+    println!("🔴 Purging product with ID: {}", product_id);
+
+    match trash::purge_product(
+        products_repo.get_ref(),
+        images_repo.get_ref(),
+        &config,
+        &actor.0,
+        product_id,
+    )
+    .await
     {
-        eprintln!("❌ Failed to delete images: {:?}", e);
-        return HttpResponse::InternalServerError().body("Failed to delete images");
+        Ok(()) => {
+            println!("✅ Product and associated images deleted successfully");
+            HttpResponse::Ok().body("Product purged successfully")
+        }
+        Err(RepoError::NotFound) => HttpResponse::NotFound().body("Product not found in trash"),
+        Err(e) => {
+            eprintln!("❌ Failed to purge product: {}", e);
+            HttpResponse::InternalServerError().body("Failed to purge product")
+        }
     }
+}
 
-    // 🔴 ลบสินค้า
-    let result = sqlx::query("DELETE FROM products WHERE id = ?")
-        .bind(product_id)
-        .execute(&mut *tx)
-        .await;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repository::SortValue;
 
-    if let Err(e) = result {
-        eprintln!("❌ Failed to delete product: {:?}", e);
-        return HttpResponse::InternalServerError().body("Failed to delete product");
+    fn key(value: SortValue) -> SortKey {
+        SortKey { value, id: 7 }
     }
 
-    if let Err(e) = tx.commit().await {
-        eprintln!("❌ Failed to commit transaction: {:?}", e);
-        return HttpResponse::InternalServerError().body("Transaction commit failed");
+    #[test]
+    fn cursor_round_trips() {
+        let token = Cursor::encode(
+            ListSort::PriceDesc,
+            true,
+            &key(SortValue::Price("USD".to_string(), 1250)),
+        )
+        .unwrap();
+        let cursor = Cursor::decode(&token).unwrap();
+        assert_eq!(cursor.sort, ListSort::PriceDesc);
+        assert!(cursor.before);
+        assert_eq!(cursor.key.id, 7);
+        assert_eq!(cursor.key.value, SortValue::Price("USD".to_string(), 1250));
     }
 
-    println!("✅ Product and associated images deleted successfully");
-    HttpResponse::Ok().body("Product deleted successfully")
+    #[test]
+    fn cursor_rejects_relevance_and_mismatched_values() {
+        assert!(Cursor::encode(ListSort::Relevance, false, &key(SortValue::Float(0.5))).is_none());
+        assert!(Cursor::encode(ListSort::PriceAsc, false, &key(SortValue::Int(100))).is_none());
+
+        // token ที่สร้างเองด้วยค่าที่ไม่ตรงกับ sort ใช้ไม่ได้
+        let forged = |json: &str| URL_SAFE_NO_PAD.encode(json);
+        assert!(
+            Cursor::decode(&forged(
+                r#"{"sort":"relevance","before":false,"key":{"value":{"float":0.5},"id":1}}"#
+            ))
+            .is_none()
+        );
+        assert!(
+            Cursor::decode(&forged(
+                r#"{"sort":"stock_asc","before":false,"key":{"value":{"text":"a"},"id":1}}"#
+            ))
+            .is_none()
+        );
+        assert!(Cursor::decode("not a cursor").is_none());
+    }
+
+    #[test]
+    fn detail_keys_reject_path_and_filter_syntax() {
+        assert!(valid_detail_key("color"));
+        assert!(valid_detail_key("สี"));
+        assert!(valid_detail_key("ram gb"));
+        for key in ["", " color", "a.b", "a[0]", "$", "a\"b", "x>", "x=", "a\nb"] {
+            assert!(!valid_detail_key(key), "{:?}", key);
+        }
+        assert!(!valid_detail_key(&"k".repeat(MAX_DETAIL_KEY_CHARS + 1)));
+    }
+
+    #[test]
+    fn detail_filters_parse_comparisons() {
+        let filters = detail_filters(
+            "detail.color=red&detail.ram_gb>=8&detail.ram_gb%3C16&page=2&detail.spec.cpu=i7",
+        )
+        .unwrap();
+        assert_eq!(filters.len(), 4);
+        assert!(
+            filters[0].path == ["color"]
+                && matches!(&filters[0].condition, DetailCondition::Equals(v) if v == "red")
+        );
+        assert!(matches!(
+            filters[1].condition,
+            DetailCondition::Compare(Comparison::GreaterOrEqual, n) if n == 8.0
+        ));
+        assert!(matches!(
+            filters[2].condition,
+            DetailCondition::Compare(Comparison::Less, n) if n == 16.0
+        ));
+        assert_eq!(filters[3].path, ["spec", "cpu"]);
+
+        assert!(detail_filters("detail.ram_gb>=eight").is_err());
+        assert!(detail_filters("detail.ram_gb>=inf").is_err());
+        assert!(detail_filters("detail.a[0]=x").is_err());
+        assert!(detail_filters("detail.=x").is_err());
+        let many = (0..=MAX_DETAIL_FILTERS)
+            .map(|i| format!("detail.k{}=v", i))
+            .collect::<Vec<_>>()
+            .join("&");
+        assert!(detail_filters(&many).is_err());
+    }
+
+    #[test]
+    fn image_slots_apply_removals_and_order() {
+        let current: Vec<ImageRecord> = [10, 11, 12]
+            .into_iter()
+            .map(|id| ImageRecord {
+                id,
+                image_path: format!("other/tee/tee_{}.jpg", id),
+            })
+            .collect();
+        let new_paths = vec!["other/tee/tee_13.jpg".to_string()];
+
+        let (slots, removed) =
+            image_slots(&current, &new_paths, &["11".to_string()], "new:0, 12").unwrap();
+        assert_eq!(removed, [11]);
+        assert!(
+            slots
+                == [
+                    ImageSlot::New(new_paths[0].clone()),
+                    ImageSlot::Existing(12),
+                    ImageSlot::Existing(10),
+                ]
+        );
+
+        // ไม่ระบุ order = รูปเดิมตามลำดับเดิม แล้วตามด้วยรูปใหม่
+        let (slots, _) = image_slots(&current, &new_paths, &[], "").unwrap();
+        assert!(
+            slots
+                == [
+                    ImageSlot::Existing(10),
+                    ImageSlot::Existing(11),
+                    ImageSlot::Existing(12),
+                    ImageSlot::New(new_paths[0].clone()),
+                ]
+        );
+
+        for (remove, order) in [
+            ("99", ""),
+            ("x", ""),
+            ("11", "11"),
+            ("", "10,10"),
+            ("", "new:1"),
+        ] {
+            assert!(
+                image_slots(&current, &new_paths, &[remove.to_string()], order).is_err(),
+                "{} / {}",
+                remove,
+                order
+            );
+        }
+    }
+
+    #[test]
+    fn images_path_must_name_existing_files() {
+        let root = std::env::temp_dir().join(format!("shop-images-path-{}", std::process::id()));
+        fs::create_dir_all(root.join("game/tee")).unwrap();
+        fs::write(root.join("game/tee/tee_0.jpg"), b"jpg").unwrap();
+        let config = Config {
+            images_dir: root.clone(),
+            ..Config::default()
+        };
+        let paths = |paths: &[&str]| paths.iter().map(|p| p.to_string()).collect::<Vec<_>>();
+
+        assert_eq!(
+            images_path_error(&config, &paths(&["game/tee/tee_0.jpg"])),
+            None
+        );
+        assert_eq!(images_path_error(&config, &[]), None);
+        for bad in [
+            "game/tee/tee_1.jpg",
+            "game/tee",
+            "../escape_0.jpg",
+            "/etc/passwd",
+        ] {
+            assert!(
+                images_path_error(&config, &paths(&["game/tee/tee_0.jpg", bad])).is_some(),
+                "{}",
+                bad
+            );
+        }
+
+        fs::remove_dir_all(&root).unwrap();
+    }
 }
@@ -0,0 +1,27 @@
+use crate::db::DbPool;
+use crate::db::{latest_version, schema_version};
+use crate::models::SchemaVersion;
+use actix_web::{HttpResponse, Responder, get, web};
+
+#[get("/api/schema-version")]
+pub async fn get_schema_version(db: Option<web::Data<DbPool>>) -> impl Responder {
+    // โหมด database_url = "memory:" ไม่มีฐานข้อมูลจึงไม่มี schema
+    let Some(db) = db else {
+        return HttpResponse::NotFound().body("No database schema for in-memory store");
+    };
+
+    match schema_version(db.get_ref()).await {
+        Ok(current) => {
+            let latest = latest_version();
+            HttpResponse::Ok().json(SchemaVersion {
+                current,
+                latest,
+                up_to_date: current == latest,
+            })
+        }
+        Err(e) => {
+            eprintln!("❌ Failed to read schema version: {:?}", e);
+            HttpResponse::InternalServerError().body("Failed to read schema version")
+        }
+    }
+}
@@ -0,0 +1,248 @@
+use crate::handlers::audit::Actor;
+use crate::models::{
+    LowStockQuery, PaginatedResponse, PaginationInfo, StockAdjustment, StockAlertQuery,
+    StockMovementQuery, StockReason, page_number, page_size,
+};
+use crate::repository::{ProductRepository, RepoError};
+use actix_web::{HttpResponse, Responder, get, post, web};
+
+// จำนวนที่ปรับได้ต่อครั้ง กันไม่ให้ stock ล้นช่วงของ i64
+const MAX_ADJUSTMENT: i64 = 1_000_000_000;
+const MAX_NOTE_LENGTH: usize = 500;
+
+// ตรวจค่าจุดสั่งซื้อจาก PATCH ของสินค้าหรือประเภท (null = ล้างค่า ผ่านเสมอ)
+pub fn reorder_point_error(
+    threshold: Option<Option<i64>>,
+    quantity: Option<Option<i64>>,
+) -> Option<&'static str> {
+    if threshold.flatten().is_some_and(|threshold| threshold < 0) {
+        return Some("reorder_threshold must not be negative");
+    }
+    if quantity.flatten().is_some_and(|quantity| quantity <= 0) {
+        return Some("reorder_quantity must be positive");
+    }
+    None
+}
+
+// เพิ่มหรือลด stock จากค่าปัจจุบัน (ไม่เขียนทับ) การปรับพร้อมกันหลายคนจึงไม่ทับกัน
+// ตอบ 201 พร้อมรายการที่บันทึก (stock_after คือ stock ใหม่)
+// ถ้าลดจน stock ติดลบโดยที่สินค้าไม่ได้เปิด allow_backorder ตอบ 409 และไม่เปลี่ยนอะไร
+#[post("/api/products/{id}/stock-adjustments")]
+pub async fn post_stock_adjustment(
+    products_repo: web::Data<dyn ProductRepository>,
+    actor: Actor,
+    path: web::Path<i64>,
+    json: web::Json<StockAdjustment>,
+) -> impl Responder {
+    let product_id = path.into_inner();
+    let mut adjustment = json.into_inner();
+
+    if adjustment.quantity == 0 {
+        return HttpResponse::BadRequest().body("quantity must not be zero");
+    }
+    if adjustment.quantity.abs() > MAX_ADJUSTMENT {
+        return HttpResponse::BadRequest().body(format!(
+            "quantity must be between -{} and {}",
+            MAX_ADJUSTMENT, MAX_ADJUSTMENT
+        ));
+    }
+    match adjustment.reason {
+        StockReason::Sale | StockReason::Damage if adjustment.quantity > 0 => {
+            return HttpResponse::BadRequest().body(format!(
+                "quantity must be negative for {}",
+                adjustment.reason.as_str()
+            ));
+        }
+        StockReason::Restock if adjustment.quantity < 0 => {
+            return HttpResponse::BadRequest().body("quantity must be positive for restock");
+        }
+        _ => {}
+    }
+
+    adjustment.note = adjustment
+        .note
+        .map(|note| note.trim().to_string())
+        .filter(|note| !note.is_empty());
+    if adjustment
+        .note
+        .as_ref()
+        .is_some_and(|note| note.chars().count() > MAX_NOTE_LENGTH)
+    {
+        return HttpResponse::BadRequest().body(format!(
+            "note is too long (max {} characters)",
+            MAX_NOTE_LENGTH
+        ));
+    }
+
+    match products_repo
+        .adjust_stock(&actor.0, product_id, &adjustment)
+        .await
+    {
+        Ok(movement) => {
+            println!(
+                "✅ Stock of product {} adjusted by {} ({}), now {}",
+                product_id,
+                movement.quantity,
+                movement.reason.as_str(),
+                movement.stock_after
+            );
+            HttpResponse::Created().json(movement)
+        }
+        Err(RepoError::NotFound) => HttpResponse::NotFound().body("Product not found"),
+        Err(RepoError::Conflict(message)) => HttpResponse::Conflict().body(message),
+        Err(e) => {
+            eprintln!("❌ Failed to adjust stock: {}", e);
+            HttpResponse::InternalServerError().body("Failed to adjust stock")
+        }
+    }
+}
+
+// ประวัติ stock ของสินค้า รายการล่าสุดก่อน ?reason=sale กรองตามเหตุผล
+#[get("/api/products/{id}/stock-movements")]
+pub async fn get_stock_movements(
+    products_repo: web::Data<dyn ProductRepository>,
+    path: web::Path<i64>,
+    query: web::Query<StockMovementQuery>,
+) -> impl Responder {
+    let product_id = path.into_inner();
+    let page = page_number(query.page);
+    let items_per_page = page_size(query.per_page);
+    let offset = (page - 1) * items_per_page;
+
+    match products_repo.find(product_id).await {
+        Ok(Some(_)) => {}
+        Ok(None) => return HttpResponse::NotFound().body("Product not found"),
+        Err(e) => {
+            eprintln!("❌ Failed to fetch product: {}", e);
+            return HttpResponse::InternalServerError().body("Database query failed");
+        }
+    }
+
+    let count_result = products_repo
+        .count_stock_movements(product_id, query.reason)
+        .await;
+    let movements_result = products_repo
+        .stock_movements(product_id, query.reason, items_per_page, offset)
+        .await;
+
+    match (count_result, movements_result) {
+        (Ok(total_count), Ok(movements)) => {
+            let total_pages = (total_count + items_per_page - 1) / items_per_page;
+
+            HttpResponse::Ok().json(PaginatedResponse {
+                data: movements,
+                pagination: PaginationInfo {
+                    total_items: Some(total_count),
+                    items_per_page,
+                    current_page: Some(page),
+                    total_pages: Some(total_pages),
+                    sort: None,
+                    next_cursor: None,
+                    prev_cursor: None,
+                },
+            })
+        }
+        (Err(e), _) | (_, Err(e)) => {
+            eprintln!("❌ Failed to read stock movements: {}", e);
+            HttpResponse::InternalServerError().body("Database query failed")
+        }
+    }
+}
+
+// สินค้าที่ stock ถึงหรือต่ำกว่าจุดสั่งซื้อ (ของสินค้า หรือของประเภทถ้าสินค้าไม่ได้ตั้ง) ส่วนที่ขาดมากก่อน
+#[get("/api/products/low-stock")]
+pub async fn get_low_stock(
+    products_repo: web::Data<dyn ProductRepository>,
+    query: web::Query<LowStockQuery>,
+) -> impl Responder {
+    let page = page_number(query.page);
+    let items_per_page = page_size(query.per_page);
+    let offset = (page - 1) * items_per_page;
+
+    let count_result = products_repo.count_low_stock().await;
+    let products_result = products_repo.low_stock(items_per_page, offset).await;
+
+    match (count_result, products_result) {
+        (Ok(total_count), Ok(products)) => {
+            let total_pages = (total_count + items_per_page - 1) / items_per_page;
+
+            HttpResponse::Ok().json(PaginatedResponse {
+                data: products,
+                pagination: PaginationInfo {
+                    total_items: Some(total_count),
+                    items_per_page,
+                    current_page: Some(page),
+                    total_pages: Some(total_pages),
+                    sort: None,
+                    next_cursor: None,
+                    prev_cursor: None,
+                },
+            })
+        }
+        (Err(e), _) | (_, Err(e)) => {
+            eprintln!("❌ Failed to read low stock products: {}", e);
+            HttpResponse::InternalServerError().body("Database query failed")
+        }
+    }
+}
+
+// outbox ของการแจ้งเตือนสินค้าใกล้หมด เก่าสุดก่อน ?pending=true เฉพาะที่ยังไม่ acknowledge
+// ระบบสั่งซื้อ/แจ้งเตือนภายนอก poll endpoint นี้แล้ว acknowledge รายการที่จัดการแล้ว
+#[get("/api/stock-alerts")]
+pub async fn get_stock_alerts(
+    products_repo: web::Data<dyn ProductRepository>,
+    query: web::Query<StockAlertQuery>,
+) -> impl Responder {
+    let page = page_number(query.page);
+    let items_per_page = page_size(query.per_page);
+    let offset = (page - 1) * items_per_page;
+
+    let count_result = products_repo.count_stock_alerts(query.pending).await;
+    let alerts_result = products_repo
+        .stock_alerts(query.pending, items_per_page, offset)
+        .await;
+
+    match (count_result, alerts_result) {
+        (Ok(total_count), Ok(alerts)) => {
+            let total_pages = (total_count + items_per_page - 1) / items_per_page;
+
+            HttpResponse::Ok().json(PaginatedResponse {
+                data: alerts,
+                pagination: PaginationInfo {
+                    total_items: Some(total_count),
+                    items_per_page,
+                    current_page: Some(page),
+                    total_pages: Some(total_pages),
+                    sort: None,
+                    next_cursor: None,
+                    prev_cursor: None,
+                },
+            })
+        }
+        (Err(e), _) | (_, Err(e)) => {
+            eprintln!("❌ Failed to read stock alerts: {}", e);
+            HttpResponse::InternalServerError().body("Database query failed")
+        }
+    }
+}
+
+// ทำเครื่องหมายว่าจัดการการแจ้งเตือนแล้ว เรียกซ้ำได้ (acknowledged_at ไม่เปลี่ยน)
+#[post("/api/stock-alerts/{id}/ack")]
+pub async fn post_stock_alert_ack(
+    products_repo: web::Data<dyn ProductRepository>,
+    path: web::Path<i64>,
+) -> impl Responder {
+    let alert_id = path.into_inner();
+
+    match products_repo.acknowledge_stock_alert(alert_id).await {
+        Ok(alert) => {
+            println!("✅ Stock alert {} acknowledged", alert_id);
+            HttpResponse::Ok().json(alert)
+        }
+        Err(RepoError::NotFound) => HttpResponse::NotFound().body("Stock alert not found"),
+        Err(e) => {
+            eprintln!("❌ Failed to acknowledge stock alert: {}", e);
+            HttpResponse::InternalServerError().body("Failed to acknowledge stock alert")
+        }
+    }
+}
@@ -0,0 +1,386 @@
+use crate::handlers::audit::Actor;
+use crate::models::{NewVariant, ProductVariant, Products, VariantOption, VariantPatch};
+use crate::repository::{
+    ImageRepository, ProductRepository, RepoError, VariantChanges, VariantInput,
+    variant_options_error,
+};
+use actix_web::{HttpResponse, Responder, delete, get, patch, post, put, web};
+use std::collections::BTreeMap;
+
+const MAX_OPTION_AXES: usize = 5;
+const MAX_OPTION_VALUES: usize = 100;
+const MAX_OPTION_CHARS: usize = 64;
+const MAX_SKU_CHARS: usize = 64;
+
+// SKU ใช้พิมพ์บนป้ายและค้นหา จึงรับเฉพาะตัวอักษรอังกฤษ ตัวเลข และ - _ .
+pub fn sku_error(sku: &str) -> Option<String> {
+    if sku.is_empty() {
+        return Some("SKU must not be empty".to_string());
+    }
+    if sku.chars().count() > MAX_SKU_CHARS {
+        return Some(format!(
+            "SKU is too long (max {} characters)",
+            MAX_SKU_CHARS
+        ));
+    }
+    if !sku
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+    {
+        return Some("SKU may only contain letters, digits, '-', '_' and '.'".to_string());
+    }
+    None
+}
+
+// ชื่อแกนและค่าต้องไม่ว่าง ไม่ซ้ำกัน (ตัดช่องว่างหัวท้ายแล้ว)
+fn normalize_options(options: Vec<VariantOption>) -> Result<Vec<VariantOption>, String> {
+    if options.len() > MAX_OPTION_AXES {
+        return Err(format!("Too many options (max {})", MAX_OPTION_AXES));
+    }
+    let mut normalized: Vec<VariantOption> = Vec::with_capacity(options.len());
+    for option in options {
+        let name = option.name.trim().to_string();
+        if name.is_empty() || name.chars().count() > MAX_OPTION_CHARS {
+            return Err(format!(
+                "Option name must be 1-{} characters",
+                MAX_OPTION_CHARS
+            ));
+        }
+        if normalized.iter().any(|axis| axis.name == name) {
+            return Err(format!("Duplicate option: {}", name));
+        }
+        if option.values.is_empty() || option.values.len() > MAX_OPTION_VALUES {
+            return Err(format!(
+                "Option {} must have 1-{} values",
+                name, MAX_OPTION_VALUES
+            ));
+        }
+        let mut values: Vec<String> = Vec::with_capacity(option.values.len());
+        for value in option.values {
+            let value = value.trim().to_string();
+            if value.is_empty() || value.chars().count() > MAX_OPTION_CHARS {
+                return Err(format!(
+                    "Values of option {} must be 1-{} characters",
+                    name, MAX_OPTION_CHARS
+                ));
+            }
+            if values.contains(&value) {
+                return Err(format!("Duplicate value for option {}: {}", name, value));
+            }
+            values.push(value);
+        }
+        normalized.push(VariantOption { name, values });
+    }
+    Ok(normalized)
+}
+
+fn trim_options(options: BTreeMap<String, String>) -> BTreeMap<String, String> {
+    options
+        .into_iter()
+        .map(|(name, value)| (name.trim().to_string(), value.trim().to_string()))
+        .collect()
+}
+
+// สินค้าที่ไม่อยู่ในถังขยะ พร้อม id รูปของสินค้าที่ variant เลือกได้
+async fn load_product(
+    products_repo: &dyn ProductRepository,
+    images_repo: &dyn ImageRepository,
+    product_id: i64,
+) -> Result<(Products, Vec<i64>), HttpResponse> {
+    let product = match products_repo.find(product_id).await {
+        Ok(Some(product)) => product,
+        Ok(None) => return Err(HttpResponse::NotFound().body("Product not found")),
+        Err(e) => {
+            eprintln!("❌ Failed to fetch product: {}", e);
+            return Err(HttpResponse::InternalServerError().body("Database query failed"));
+        }
+    };
+    let image_ids = match images_repo.images_for_product(product_id).await {
+        Ok(images) => images.iter().map(|image| image.id).collect(),
+        Err(e) => {
+            eprintln!("❌ Failed to fetch images: {}", e);
+            return Err(HttpResponse::InternalServerError().body("Failed to fetch image paths"));
+        }
+    };
+    Ok((product, image_ids))
+}
+
+fn image_ids_error(image_ids: &[i64], product_image_ids: &[i64]) -> Option<String> {
+    for (index, image_id) in image_ids.iter().enumerate() {
+        if !product_image_ids.contains(image_id) {
+            return Some(format!(
+                "Image {} does not belong to this product",
+                image_id
+            ));
+        }
+        if image_ids[..index].contains(image_id) {
+            return Some(format!("Duplicate image: {}", image_id));
+        }
+    }
+    None
+}
+
+// variant ของสินค้าพร้อม path รูปสำหรับ frontend
+pub async fn product_variants(
+    products_repo: &dyn ProductRepository,
+    product_id: i64,
+) -> Result<Vec<ProductVariant>, RepoError> {
+    let mut variants = products_repo.variants(product_id).await?;
+    for variant in &mut variants {
+        for image in &mut variant.images {
+            image.image_path = format!("/images/{}", image.image_path);
+        }
+    }
+    Ok(variants)
+}
+
+async fn variant_response(
+    products_repo: &dyn ProductRepository,
+    product_id: i64,
+    variant_id: i64,
+    created: bool,
+) -> HttpResponse {
+    match product_variants(products_repo, product_id).await {
+        Ok(variants) => match variants.into_iter().find(|v| v.id == variant_id) {
+            Some(variant) if created => HttpResponse::Created().json(variant),
+            Some(variant) => HttpResponse::Ok().json(variant),
+            None => HttpResponse::NotFound().body("Variant not found"),
+        },
+        Err(e) => {
+            eprintln!("❌ Failed to fetch variants: {}", e);
+            HttpResponse::InternalServerError().body("Database query failed")
+        }
+    }
+}
+
+fn variant_failed(e: RepoError) -> HttpResponse {
+    match e {
+        RepoError::NotFound => HttpResponse::NotFound().body("Variant not found"),
+        RepoError::Conflict(reason) => HttpResponse::Conflict().body(reason),
+        e => {
+            eprintln!("❌ Failed to save variant: {}", e);
+            HttpResponse::InternalServerError().body("Failed to save variant")
+        }
+    }
+}
+
+// ตั้งแกนตัวเลือกของสินค้า เช่น [{"name":"size","values":["S","M"]}] ส่ง [] = ไม่มี variant
+// ถ้า variant ที่มีอยู่ใช้แกนหรือค่าที่ไม่อยู่ในรายการใหม่ ตอบ 409 (ลบหรือแก้ variant นั้นก่อน)
+#[put("/api/products/{id}/variant-options")]
+pub async fn put_variant_options(
+    products_repo: web::Data<dyn ProductRepository>,
+    actor: Actor,
+    path: web::Path<i64>,
+    json: web::Json<Vec<VariantOption>>,
+) -> impl Responder {
+    let product_id = path.into_inner();
+    let options = match normalize_options(json.into_inner()) {
+        Ok(options) => options,
+        Err(e) => return HttpResponse::BadRequest().body(e),
+    };
+
+    match products_repo
+        .set_variant_options(&actor.0, product_id, &options)
+        .await
+    {
+        Ok(()) => {
+            println!("✅ Variant options of product {} updated", product_id);
+            HttpResponse::Ok().json(options)
+        }
+        Err(RepoError::NotFound) => HttpResponse::NotFound().body("Product not found"),
+        Err(RepoError::Conflict(reason)) => HttpResponse::Conflict().body(reason),
+        Err(e) => {
+            eprintln!("❌ Failed to update variant options: {}", e);
+            HttpResponse::InternalServerError().body("Update failed")
+        }
+    }
+}
+
+#[get("/api/products/{id}/variants")]
+pub async fn get_variants(
+    products_repo: web::Data<dyn ProductRepository>,
+    path: web::Path<i64>,
+) -> impl Responder {
+    let product_id = path.into_inner();
+
+    match products_repo.find(product_id).await {
+        Ok(Some(_)) => {}
+        Ok(None) => return HttpResponse::NotFound().body("Product not found"),
+        Err(e) => {
+            eprintln!("❌ Failed to fetch product: {}", e);
+            return HttpResponse::InternalServerError().body("Database query failed");
+        }
+    }
+
+    match product_variants(products_repo.get_ref(), product_id).await {
+        Ok(variants) => HttpResponse::Ok().json(variants),
+        Err(e) => {
+            eprintln!("❌ Failed to fetch variants: {}", e);
+            HttpResponse::InternalServerError().body("Database query failed")
+        }
+    }
+}
+
+// เพิ่ม variant ค่าตัวเลือกต้องครบทุกแกนของสินค้า ราคา (ถ้าส่ง) ต้องเป็นสกุลเงินเดียวกับสินค้า
+// รูปเลือกจากรูปของสินค้าด้วย image_ids ตอบ 201 พร้อม variant ที่เพิ่ม
+#[post("/api/products/{id}/variants")]
+pub async fn post_variant(
+    products_repo: web::Data<dyn ProductRepository>,
+    images_repo: web::Data<dyn ImageRepository>,
+    actor: Actor,
+    path: web::Path<i64>,
+    json: web::Json<NewVariant>,
+) -> impl Responder {
+    let product_id = path.into_inner();
+    let variant = json.into_inner();
+    let (product, product_image_ids) =
+        match load_product(products_repo.get_ref(), images_repo.get_ref(), product_id).await {
+            Ok(loaded) => loaded,
+            Err(response) => return response,
+        };
+
+    let sku = variant.sku.trim().to_string();
+    if let Some(e) = sku_error(&sku) {
+        return HttpResponse::BadRequest().body(e);
+    }
+    let options = trim_options(variant.options);
+    if let Some(e) = variant_options_error(&product.variant_options, &options) {
+        return HttpResponse::BadRequest().body(e);
+    }
+    if let Some(price) = variant.price
+        && price.currency != product.price.currency
+    {
+        return HttpResponse::BadRequest().body(format!(
+            "Variant price must be in {}",
+            product.price.currency.code()
+        ));
+    }
+    if variant.stock < 0 {
+        return HttpResponse::BadRequest().body("Stock must not be negative");
+    }
+    if let Some(e) = image_ids_error(&variant.image_ids, &product_image_ids) {
+        return HttpResponse::BadRequest().body(e);
+    }
+
+    let input = VariantInput {
+        sku,
+        options,
+        price_minor: variant.price.map(|price| price.amount),
+        stock: variant.stock,
+        image_ids: variant.image_ids,
+    };
+    let variant_id = match products_repo
+        .create_variant(&actor.0, product_id, &input)
+        .await
+    {
+        Ok(id) => id,
+        Err(RepoError::NotFound) => return HttpResponse::NotFound().body("Product not found"),
+        Err(e) => return variant_failed(e),
+    };
+
+    println!(
+        "✅ Variant {} ({}) added to product {}",
+        variant_id, input.sku, product_id
+    );
+    variant_response(products_repo.get_ref(), product_id, variant_id, true).await
+}
+
+// แก้ไขเฉพาะ field ที่ส่งมา image_ids แทนที่รูปทั้งหมดของ variant
+#[patch("/api/products/{id}/variants/{variant_id}")]
+pub async fn patch_variant(
+    products_repo: web::Data<dyn ProductRepository>,
+    images_repo: web::Data<dyn ImageRepository>,
+    actor: Actor,
+    path: web::Path<(i64, i64)>,
+    json: web::Json<VariantPatch>,
+) -> impl Responder {
+    let (product_id, variant_id) = path.into_inner();
+    let patch = json.into_inner();
+    if patch.sku.is_none()
+        && patch.options.is_none()
+        && patch.price.is_none()
+        && patch.stock.is_none()
+        && patch.image_ids.is_none()
+    {
+        return HttpResponse::BadRequest().body("No fields to update");
+    }
+    let (product, product_image_ids) =
+        match load_product(products_repo.get_ref(), images_repo.get_ref(), product_id).await {
+            Ok(loaded) => loaded,
+            Err(response) => return response,
+        };
+
+    let sku = patch.sku.map(|sku| sku.trim().to_string());
+    if let Some(e) = sku.as_deref().and_then(sku_error) {
+        return HttpResponse::BadRequest().body(e);
+    }
+    let options = patch.options.map(trim_options);
+    if let Some(e) = options
+        .as_ref()
+        .and_then(|options| variant_options_error(&product.variant_options, options))
+    {
+        return HttpResponse::BadRequest().body(e);
+    }
+    if let Some(Some(price)) = patch.price
+        && price.currency != product.price.currency
+    {
+        return HttpResponse::BadRequest().body(format!(
+            "Variant price must be in {}",
+            product.price.currency.code()
+        ));
+    }
+    if patch.stock.is_some_and(|stock| stock < 0) {
+        return HttpResponse::BadRequest().body("Stock must not be negative");
+    }
+    if let Some(e) = patch
+        .image_ids
+        .as_ref()
+        .and_then(|image_ids| image_ids_error(image_ids, &product_image_ids))
+    {
+        return HttpResponse::BadRequest().body(e);
+    }
+
+    let changes = VariantChanges {
+        sku,
+        options,
+        price_minor: patch.price.map(|price| price.map(|price| price.amount)),
+        stock: patch.stock,
+        image_ids: patch.image_ids,
+    };
+    if let Err(e) = products_repo
+        .patch_variant(&actor.0, product_id, variant_id, &changes)
+        .await
+    {
+        return variant_failed(e);
+    }
+
+    println!(
+        "✅ Variant {} of product {} patched",
+        variant_id, product_id
+    );
+    variant_response(products_repo.get_ref(), product_id, variant_id, false).await
+}
+
+// ลบ variant ถาวร (รูปยังเป็นรูปของสินค้าตามเดิม)
+#[delete("/api/products/{id}/variants/{variant_id}")]
+pub async fn delete_variant(
+    products_repo: web::Data<dyn ProductRepository>,
+    actor: Actor,
+    path: web::Path<(i64, i64)>,
+) -> impl Responder {
+    let (product_id, variant_id) = path.into_inner();
+
+    match products_repo
+        .delete_variant(&actor.0, product_id, variant_id)
+        .await
+    {
+        Ok(()) => {
+            println!(
+                "✅ Variant {} of product {} deleted",
+                variant_id, product_id
+            );
+            HttpResponse::Ok().body("Variant deleted successfully")
+        }
+        Err(e) => variant_failed(e),
+    }
+}
@@ -0,0 +1,923 @@
+use crate::config::Config;
+use crate::db::init_db;
+use crate::handlers::products::{product_name_error, valid_detail_key};
+use crate::models::{Currency, ImportReport, ImportRowError, Money};
+use crate::repository::{
+    ImportedProduct, OTHER_TYPE, ProductRepository, ProductTypeRepository, RepoError, Repositories,
+    SqlRepository,
+};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+// ไฟล์ import มีสินค้าแถวละหนึ่งชิ้น ชื่อคอลัมน์ (CSV) และ key (NDJSON) เหมือนกัน
+//   name               ชื่อสินค้า (ต้องมี)
+//   price, currency    ราคาเช่น "99.50" ในสกุล currency (ค่าเริ่มต้น THB) ใน NDJSON ส่ง {"amount", "currency"} ได้
+//   stock              จำนวนเต็มไม่ติดลบ (ค่าเริ่มต้น 0)
+//   product_type_name  ชื่อประเภท ว่างหรือ other = ไม่มีประเภท
+//   detail             JSON object (ใน CSV เขียนเป็นข้อความ JSON)
+//   images             ไฟล์รูปตามลำดับ ใน CSV คั่นด้วย | ส่วน NDJSON เป็น array
+//                      URL ของรูปจากไฟล์ export ({public_url}/images/...) ใช้ไฟล์ที่มีอยู่แล้วใน images_dir
+// CSV จากหน้า export นำเข้าได้ทันที:
+//   detail.<key>       ค่าหนึ่งค่าใน detail (key ซ้อนต่อด้วย . เช่น detail.spec.cpu) รวมกับคอลัมน์ detail
+//                      ตัวเลข true/false และข้อความ JSON ของ array/object อ่านเป็นค่านั้น ค่าอื่นเป็นข้อความ
+//   id, created_at     ไม่ได้ใช้ (สินค้าที่นำเข้าได้ id และเวลาใหม่)
+// คอลัมน์อื่นเป็น error ของไฟล์ในรายงาน (บรรทัด 1)
+const REQUIRED_COLUMNS: &[&str] = &["name", "price"];
+const DETAIL_PREFIX: &str = "detail.";
+const IMAGE_SEPARATOR: char = '|';
+
+pub const MAX_IMPORT_ROWS: usize = 10_000;
+
+// actor ใน audit_log ของการ import จากบรรทัดคำสั่ง
+const CLI_ACTOR: &str = "cli";
+
+const USAGE: &str =
+    "Usage: backend import <file> [--dry-run] [--create-types] [--format csv|ndjson]";
+
+#[derive(Clone, Copy)]
+pub enum ImportFormat {
+    Csv,
+    Ndjson,
+}
+
+impl ImportFormat {
+    pub fn from_name(name: &str) -> Option<ImportFormat> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "csv" => Some(ImportFormat::Csv),
+            "ndjson" | "jsonl" => Some(ImportFormat::Ndjson),
+            _ => None,
+        }
+    }
+
+    // ดูจากนามสกุลของไฟล์ เช่น products.csv, products.ndjson
+    pub fn from_file_name(file_name: &str) -> Option<ImportFormat> {
+        Path::new(file_name)
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .and_then(Self::from_name)
+    }
+}
+
+pub struct ImportOptions {
+    pub format: ImportFormat,
+    pub dry_run: bool,
+    pub create_types: bool,
+}
+
+// ที่มาของไฟล์รูปที่แถวอ้างถึง: ไฟล์ที่อัปโหลดมาพร้อมกัน (อ้างด้วยชื่อไฟล์) หรือโฟลเดอร์ของไฟล์ import (CLI)
+pub enum ImageSources {
+    Uploaded(HashMap<String, Vec<u8>>),
+    Directory(PathBuf),
+}
+
+impl ImageSources {
+    fn contains(&self, reference: &str) -> bool {
+        match self {
+            ImageSources::Uploaded(files) => files.contains_key(reference),
+            ImageSources::Directory(dir) => dir.join(reference).is_file(),
+        }
+    }
+
+    fn copy_to(&self, reference: &str, target: &Path) -> io::Result<()> {
+        match self {
+            ImageSources::Uploaded(files) => {
+                let data = files
+                    .get(reference)
+                    .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, reference))?;
+                fs::write(target, data)
+            }
+            ImageSources::Directory(dir) => fs::copy(dir.join(reference), target).map(|_| ()),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ImportError {
+    // ใช้ทั้งไฟล์ไม่ได้ เช่น ไม่มีคอลัมน์ที่ต้องมี (error ของแต่ละแถวอยู่ใน ImportReport)
+    Invalid(String),
+    Repo(RepoError),
+    Io(io::Error),
+}
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ImportError::Invalid(reason) => write!(f, "{}", reason),
+            ImportError::Repo(e) => write!(f, "{}", e),
+            ImportError::Io(e) => write!(f, "failed to save image: {}", e),
+        }
+    }
+}
+
+impl From<RepoError> for ImportError {
+    fn from(e: RepoError) -> Self {
+        ImportError::Repo(e)
+    }
+}
+
+// หนึ่งแถวจากไฟล์ก่อนตรวจ (ช่องของ CSV เป็นข้อความทั้งหมด)
+#[derive(Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+struct RawRow {
+    name: Option<String>,
+    price: Option<Value>,
+    currency: Option<String>,
+    stock: Option<Value>,
+    product_type_name: Option<String>,
+    detail: Option<Value>,
+    #[serde(default)]
+    images: Vec<String>,
+    // error ที่พบตอนแปลงช่องของ CSV (เช่น detail ไม่ใช่ JSON) รวมกับ error ตอนตรวจ
+    #[serde(skip)]
+    errors: Vec<ImportRowError>,
+}
+
+type ParsedRows = Vec<(u64, Result<RawRow, ImportRowError>)>;
+
+// คอลัมน์ของ CSV หลังตรวจหัวตาราง
+enum CsvColumn {
+    Name,
+    Price,
+    Currency,
+    Stock,
+    ProductTypeName,
+    Detail,
+    Images,
+    // detail.<key> แยกเป็น path ของ key
+    DetailKey(Vec<String>),
+    // คอลัมน์ที่ไม่ได้ใช้ (รวมคอลัมน์ที่ไม่รู้จักซึ่งรายงานเป็น error แล้ว)
+    Skip,
+}
+
+impl CsvColumn {
+    fn from_name(name: &str) -> Option<CsvColumn> {
+        match name {
+            "name" => Some(CsvColumn::Name),
+            "price" => Some(CsvColumn::Price),
+            "currency" => Some(CsvColumn::Currency),
+            "stock" => Some(CsvColumn::Stock),
+            "product_type_name" => Some(CsvColumn::ProductTypeName),
+            "detail" => Some(CsvColumn::Detail),
+            "images" => Some(CsvColumn::Images),
+            "id" | "created_at" => Some(CsvColumn::Skip),
+            _ => None,
+        }
+    }
+}
+
+fn row_error(line: u64, field: Option<&str>, message: impl Into<String>) -> ImportRowError {
+    ImportRowError {
+        line,
+        field: field.map(String::from),
+        message: message.into(),
+    }
+}
+
+// error ของหัวตาราง (เช่นคอลัมน์ที่ไม่รู้จัก) อยู่บรรทัด 1 แยกจาก error ของแต่ละแถว
+fn parse_csv(data: &[u8]) -> Result<(ParsedRows, Vec<ImportRowError>), ImportError> {
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .from_reader(data);
+
+    let headers = reader
+        .headers()
+        .map_err(|e| ImportError::Invalid(format!("Invalid CSV header: {}", e)))?
+        .clone();
+    let mut header_errors = Vec::new();
+    let mut names: Vec<String> = Vec::new();
+    let mut columns = Vec::new();
+    for header in headers.iter() {
+        // ชื่อคอลัมน์ไม่สนตัวพิมพ์ แต่ key ใน detail สนตัวพิมพ์
+        let lower = header.to_ascii_lowercase();
+        let (name, column) = if let Some(column) = CsvColumn::from_name(&lower) {
+            (lower, column)
+        } else if lower.starts_with(DETAIL_PREFIX) {
+            let key = &header[DETAIL_PREFIX.len()..];
+            let path: Vec<String> = key.split('.').map(String::from).collect();
+            if !path.iter().all(|key| valid_detail_key(key)) {
+                header_errors.push(row_error(1, Some(header), "Invalid detail key"));
+            }
+            (
+                format!("{}{}", DETAIL_PREFIX, key),
+                CsvColumn::DetailKey(path),
+            )
+        } else {
+            header_errors.push(row_error(1, Some(header), "Unknown column"));
+            (header.to_string(), CsvColumn::Skip)
+        };
+        if names.contains(&name) {
+            return Err(ImportError::Invalid(format!(
+                "Duplicate column: {}",
+                header
+            )));
+        }
+        names.push(name);
+        columns.push(column);
+    }
+    if let Some(missing) = REQUIRED_COLUMNS
+        .iter()
+        .find(|required| !names.iter().any(|name| name == *required))
+    {
+        return Err(ImportError::Invalid(format!("Missing column: {}", missing)));
+    }
+
+    let mut rows = Vec::new();
+    for record in reader.records() {
+        match record {
+            Ok(record) => {
+                let line = record.position().map_or(0, |p| p.line());
+                rows.push((line, Ok(csv_row(line, &columns, &record))));
+            }
+            Err(e) => {
+                let line = e.position().map_or(0, |p| p.line());
+                rows.push((
+                    line,
+                    Err(row_error(line, None, format!("Invalid CSV row: {}", e))),
+                ));
+            }
+        }
+    }
+    Ok((rows, header_errors))
+}
+
+fn csv_row(line: u64, columns: &[CsvColumn], record: &csv::StringRecord) -> RawRow {
+    let mut row = RawRow::default();
+    let mut detail_cells = Vec::new();
+    for (column, value) in columns.iter().zip(record.iter()) {
+        if value.is_empty() {
+            continue;
+        }
+        match column {
+            CsvColumn::Name => row.name = Some(value.to_string()),
+            CsvColumn::Price => row.price = Some(Value::String(value.to_string())),
+            CsvColumn::Currency => row.currency = Some(value.to_string()),
+            CsvColumn::Stock => row.stock = Some(Value::String(value.to_string())),
+            CsvColumn::ProductTypeName => row.product_type_name = Some(value.to_string()),
+            CsvColumn::Detail => match serde_json::from_str(value) {
+                Ok(detail) => row.detail = Some(detail),
+                Err(_) => {
+                    row.errors
+                        .push(row_error(line, Some("detail"), "Invalid detail JSON"));
+                }
+            },
+            CsvColumn::Images => {
+                row.images = value
+                    .split(IMAGE_SEPARATOR)
+                    .map(str::trim)
+                    .filter(|reference| !reference.is_empty())
+                    .map(String::from)
+                    .collect();
+            }
+            CsvColumn::DetailKey(path) => detail_cells.push((path, detail_cell(value))),
+            CsvColumn::Skip => {}
+        }
+    }
+
+    // detail ที่ไม่ใช่ object มี error ตอนตรวจอยู่แล้ว
+    if detail_cells.is_empty() {
+        return row;
+    }
+    let mut detail = match row.detail.take() {
+        None => Map::new(),
+        Some(Value::Object(detail)) => detail,
+        Some(detail) => {
+            row.detail = Some(detail);
+            return row;
+        }
+    };
+    for (path, value) in detail_cells {
+        if !insert_detail(&mut detail, path, value) {
+            let column = format!("{}{}", DETAIL_PREFIX, path.join("."));
+            row.errors.push(row_error(
+                line,
+                Some(&column),
+                "Conflicts with another detail value",
+            ));
+        }
+    }
+    row.detail = Some(Value::Object(detail));
+    row
+}
+
+// ค่าของคอลัมน์ detail.<key> กลับเป็นค่าแบบที่ export เขียน ข้อความที่ export เติม ' นำหน้ากันสูตรจะถูกตัด '
+// array ของค่าเดี่ยวที่ export คั่นด้วย ", " และข้อความที่อ่านเป็นตัวเลขได้ จะไม่ได้ค่าเดิม
+fn detail_cell(text: &str) -> Value {
+    if let Some(rest) = text.strip_prefix('\'')
+        && rest.starts_with(['=', '+', '-', '@', '\t', '\r'])
+    {
+        return Value::String(rest.to_string());
+    }
+    match serde_json::from_str::<Value>(text) {
+        Ok(value @ (Value::Number(_) | Value::Bool(_) | Value::Array(_) | Value::Object(_))) => {
+            value
+        }
+        _ => Value::String(text.to_string()),
+    }
+}
+
+// ใส่ค่าตาม path สร้าง object ซ้อนที่ยังไม่มี ถ้า path ชนกับค่าที่มีอยู่แล้ว (เช่น detail.a กับ detail.a.b) ได้ false
+fn insert_detail(detail: &mut Map<String, Value>, path: &[String], value: Value) -> bool {
+    let Some((key, parents)) = path.split_last() else {
+        return false;
+    };
+    let mut map = detail;
+    for parent in parents {
+        match map
+            .entry(parent.clone())
+            .or_insert_with(|| Value::Object(Map::new()))
+        {
+            Value::Object(inner) => map = inner,
+            _ => return false,
+        }
+    }
+    if map.contains_key(key) {
+        return false;
+    }
+    map.insert(key.clone(), value);
+    true
+}
+
+fn parse_ndjson(data: &[u8]) -> Result<ParsedRows, ImportError> {
+    let text = std::str::from_utf8(data)
+        .map_err(|_| ImportError::Invalid("Import file must be UTF-8".to_string()))?;
+
+    let mut rows = Vec::new();
+    for (index, text) in text.trim_start_matches('\u{feff}').lines().enumerate() {
+        let line = index as u64 + 1;
+        if text.trim().is_empty() {
+            continue;
+        }
+        let row = serde_json::from_str::<RawRow>(text)
+            .map_err(|e| row_error(line, None, format!("Invalid JSON row: {}", e)));
+        rows.push((line, row));
+    }
+    Ok(rows)
+}
+
+// ชื่อประเภทของแถว ว่างหรือ other = ไม่มีประเภท
+fn type_name(raw: &RawRow) -> Option<String> {
+    raw.product_type_name
+        .as_deref()
+        .map(str::trim)
+        .filter(|name| !name.is_empty() && *name != OTHER_TYPE)
+        .map(String::from)
+}
+
+// ตรวจทุกช่องของแถวแล้วรวม error ไว้ด้วยกัน image_paths ยังเป็นชื่อไฟล์ที่อ้างถึง
+fn validate_row(
+    line: u64,
+    mut raw: RawRow,
+    config: &Config,
+    images: &ImageSources,
+) -> Result<ImportedProduct, Vec<ImportRowError>> {
+    let mut errors = std::mem::take(&mut raw.errors);
+    let type_name = type_name(&raw);
+
+    // ชื่อสินค้าและชื่อประเภทเป็นโฟลเดอร์ของรูป ({type}/{name}) ใช้กฎเดียวกับ clone และ PATCH
+    let name = raw.name.as_deref().map(str::trim).unwrap_or("").to_string();
+    if name.is_empty() {
+        errors.push(row_error(line, Some("name"), "name is required"));
+    } else if let Some(message) = product_name_error(&name) {
+        errors.push(row_error(line, Some("name"), message));
+    }
+    if type_name
+        .as_deref()
+        .is_some_and(|type_name| product_name_error(type_name).is_some())
+    {
+        errors.push(row_error(
+            line,
+            Some("product_type_name"),
+            "Product type name must not be a path",
+        ));
+    }
+
+    let currency = match raw.currency.as_deref().map(str::trim) {
+        None | Some("") => Some(Currency::default()),
+        Some(code) => match code.parse::<Currency>() {
+            Ok(currency) => Some(currency),
+            Err(e) => {
+                errors.push(row_error(line, Some("currency"), e));
+                None
+            }
+        },
+    };
+    let price = match (raw.price, currency) {
+        (None | Some(Value::Null), _) => Err("price is required".to_string()),
+        (Some(Value::String(text)), Some(currency)) => Money::parse(&text, currency),
+        (Some(Value::Number(number)), Some(currency)) => {
+            Money::from_f64(number.as_f64().unwrap_or(f64::NAN), currency)
+        }
+        (Some(value @ Value::Object(_)), Some(currency)) => {
+            match serde_json::from_value::<Money>(value) {
+                Ok(price) if raw.currency.is_some() && price.currency != currency => {
+                    Err("price currency does not match currency".to_string())
+                }
+                Ok(price) => Ok(price),
+                Err(e) => Err(e.to_string()),
+            }
+        }
+        // currency ไม่ถูกต้อง error อยู่ที่ currency แล้ว
+        (Some(_), None) => Ok(Money::new(0, Currency::default())),
+        (Some(_), Some(_)) => Err("Invalid price".to_string()),
+    };
+    let price = match price {
+        Ok(price) => price,
+        Err(e) => {
+            errors.push(row_error(line, Some("price"), e));
+            Money::new(0, Currency::default())
+        }
+    };
+
+    let stock = match &raw.stock {
+        None | Some(Value::Null) => Some(0),
+        Some(Value::Number(number)) => number.as_i64(),
+        Some(Value::String(text)) => text.trim().parse::<i64>().ok(),
+        Some(_) => None,
+    };
+    let stock = match stock {
+        Some(stock) if stock < 0 => {
+            errors.push(row_error(line, Some("stock"), "Stock must not be negative"));
+            stock
+        }
+        Some(stock) => stock,
+        None => {
+            errors.push(row_error(line, Some("stock"), "Invalid stock"));
+            0
+        }
+    };
+
+    let detail = match raw.detail {
+        None | Some(Value::Null) => serde_json::json!({}),
+        Some(detail) if detail.is_object() => detail,
+        Some(_) => {
+            errors.push(row_error(
+                line,
+                Some("detail"),
+                "detail must be a JSON object",
+            ));
+            Value::Null
+        }
+    };
+
+    let mut image_paths = Vec::new();
+    for reference in raw.images.iter().map(|reference| reference.trim()) {
+        if reference.is_empty() {
+            continue;
+        }
+        if !images.contains(reference) && exported_image(config, reference).is_none() {
+            errors.push(row_error(
+                line,
+                Some("images"),
+                format!("Image not found: {}", reference),
+            ));
+        }
+        image_paths.push(reference.to_string());
+    }
+
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+    Ok(ImportedProduct {
+        name,
+        price,
+        detail,
+        stock,
+        type_name,
+        image_paths,
+    })
+}
+
+// ตรวจทุกแถวก่อน ถ้าไม่มี error และไม่ใช่ dry_run จึงบันทึกรูปแล้วเพิ่มทั้งหมดใน transaction เดียว
+// ถ้าฐานข้อมูลไม่สำเร็จไฟล์รูปที่เพิ่งบันทึกจะถูกลบทิ้ง
+pub async fn import_products(
+    products_repo: &dyn ProductRepository,
+    types_repo: &dyn ProductTypeRepository,
+    config: &Config,
+    actor: &str,
+    data: &[u8],
+    images: &ImageSources,
+    options: &ImportOptions,
+) -> Result<ImportReport, ImportError> {
+    let (rows, header_errors) = match options.format {
+        ImportFormat::Csv => parse_csv(data)?,
+        ImportFormat::Ndjson => (parse_ndjson(data)?, Vec::new()),
+    };
+    if rows.is_empty() {
+        return Err(ImportError::Invalid("No rows to import".to_string()));
+    }
+    if rows.len() > MAX_IMPORT_ROWS {
+        return Err(ImportError::Invalid(format!(
+            "Too many rows, at most {} per import",
+            MAX_IMPORT_ROWS
+        )));
+    }
+    let total_rows = rows.len();
+
+    let mut errors = Vec::new();
+    let mut products = Vec::new();
+    let mut type_refs = Vec::new();
+    for (line, row) in rows {
+        if let Ok(raw) = &row
+            && let Some(name) = type_name(raw)
+        {
+            type_refs.push((line, name));
+        }
+        match row
+            .map_err(|e| vec![e])
+            .and_then(|raw| validate_row(line, raw, config, images))
+        {
+            Ok(product) => products.push((line, product)),
+            Err(row_errors) => errors.extend(row_errors),
+        }
+    }
+
+    // ประเภทที่ยังไม่มี: สร้างใหม่ถ้า create_types ไม่อย่างนั้นแถวที่อ้างถึงไม่ผ่าน
+    let mut created_types: Vec<String> = Vec::new();
+    let mut type_exists: HashMap<String, bool> = HashMap::new();
+    for (line, name) in &type_refs {
+        let exists = match type_exists.get(name) {
+            Some(exists) => *exists,
+            None => {
+                let exists = types_repo.find_id_by_name(name).await?.is_some();
+                type_exists.insert(name.clone(), exists);
+                exists
+            }
+        };
+        if exists {
+            continue;
+        }
+        if options.create_types {
+            if !created_types.contains(name) {
+                created_types.push(name.clone());
+            }
+        } else {
+            errors.push(row_error(
+                *line,
+                Some("product_type_name"),
+                format!("Unknown product type: {}", name),
+            ));
+        }
+    }
+    errors.sort_by_key(|e| e.line);
+
+    let error_lines: BTreeSet<u64> = errors.iter().map(|e| e.line).collect();
+    let valid_rows = total_rows - error_lines.len();
+    errors.splice(0..0, header_errors);
+    let mut report = ImportReport {
+        dry_run: options.dry_run,
+        total_rows,
+        valid_rows,
+        errors,
+        created_types,
+        product_ids: Vec::new(),
+    };
+    if !report.errors.is_empty() && !options.dry_run {
+        report.created_types.clear();
+    }
+    if options.dry_run || !report.errors.is_empty() {
+        return Ok(report);
+    }
+
+    let mut products: Vec<ImportedProduct> =
+        products.into_iter().map(|(_, product)| product).collect();
+    let mut saved = SavedImages::default();
+    if let Err(e) = save_images(config, images, &mut products, &mut saved) {
+        saved.remove();
+        return Err(ImportError::Io(e));
+    }
+
+    match products_repo
+        .import(actor, &report.created_types, &products)
+        .await
+    {
+        Ok(ids) => report.product_ids = ids,
+        Err(e) => {
+            saved.remove();
+            return Err(e.into());
+        }
+    }
+    Ok(report)
+}
+
+// URL ของรูปในไฟล์ export ชี้ไปที่ไฟล์ใน images_dir ของร้านนี้ ใช้ได้เมื่อไฟล์ยังอยู่
+fn exported_image(config: &Config, reference: &str) -> Option<PathBuf> {
+    let prefix = format!("{}/images/", config.public_url.trim_end_matches('/'));
+    let path = reference.strip_prefix(&prefix)?;
+    config.image_file(path).ok().filter(|file| file.is_file())
+}
+
+// รูปเก็บแบบเดียวกับ post_products: {ประเภท}/{ชื่อสินค้า}/{ชื่อสินค้า}_{n}.jpg โดยข้ามเลขที่มีไฟล์อยู่แล้ว
+fn save_images(
+    config: &Config,
+    images: &ImageSources,
+    products: &mut [ImportedProduct],
+    saved: &mut SavedImages,
+) -> io::Result<()> {
+    for product in products.iter_mut() {
+        let type_name = product.type_name.as_deref().unwrap_or(OTHER_TYPE);
+        let folder_path = format!("{}/{}", type_name, product.name);
+        let mut index = 0;
+        let mut paths = Vec::new();
+
+        for reference in &product.image_paths {
+            let file_path = loop {
+                let candidate = format!("{}/{}_{}.jpg", folder_path, product.name, index);
+                index += 1;
+                if !config
+                    .image_file(&candidate)
+                    .is_ok_and(|file| file.exists())
+                {
+                    break candidate;
+                }
+            };
+
+            saved.create_folder(config.image_file(&folder_path)?)?;
+            let file = config.image_file(&file_path)?;
+            saved.files.push(file.clone());
+            match exported_image(config, reference) {
+                Some(source) if !images.contains(reference) => {
+                    fs::copy(source, &file)?;
+                }
+                _ => images.copy_to(reference, &file)?,
+            }
+            paths.push(file_path);
+        }
+        product.image_paths = paths;
+    }
+    Ok(())
+}
+
+// ไฟล์และโฟลเดอร์ที่ import สร้างขึ้น ลบทิ้งเมื่อบันทึกลงฐานข้อมูลไม่สำเร็จ
+#[derive(Default)]
+struct SavedImages {
+    files: Vec<PathBuf>,
+    folders: Vec<PathBuf>,
+}
+
+impl SavedImages {
+    fn create_folder(&mut self, folder: PathBuf) -> io::Result<()> {
+        // จดเฉพาะโฟลเดอร์ที่ยังไม่มี เรียงจากบนลงล่าง
+        let missing: Vec<PathBuf> = folder
+            .ancestors()
+            .take_while(|dir| !dir.exists())
+            .map(Path::to_path_buf)
+            .collect();
+        fs::create_dir_all(&folder)?;
+        self.folders.extend(missing.into_iter().rev());
+        Ok(())
+    }
+
+    fn remove(&self) {
+        for file in self.files.iter().filter(|file| file.exists()) {
+            if let Err(e) = fs::remove_file(file) {
+                eprintln!("⚠️ Failed to remove file {}: {}", file.display(), e);
+            }
+        }
+        for folder in self.folders.iter().rev() {
+            if let Err(e) = fs::remove_dir(folder) {
+                eprintln!("⚠️ Failed to remove folder {}: {}", folder.display(), e);
+            }
+        }
+    }
+}
+
+// backend import <ไฟล์> รูปที่อ้างถึงอยู่ในโฟลเดอร์เดียวกับไฟล์ import
+pub async fn run_command(config: &Config, args: &[String]) -> Result<(), String> {
+    let mut file = None;
+    let mut format = None;
+    let mut dry_run = false;
+    let mut create_types = false;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--dry-run" => dry_run = true,
+            "--create-types" => create_types = true,
+            "--format" => {
+                let name = args.next().ok_or(USAGE)?;
+                format = Some(ImportFormat::from_name(name).ok_or(USAGE)?);
+            }
+            _ if file.is_none() && !arg.starts_with("--") => file = Some(Path::new(arg)),
+            _ => return Err(USAGE.to_string()),
+        }
+    }
+    let file = file.ok_or(USAGE)?;
+    let format = format
+        .or_else(|| ImportFormat::from_file_name(&file.to_string_lossy()))
+        .ok_or("Unknown import format, use --format csv or --format ndjson")?;
+    let options = ImportOptions {
+        format,
+        dry_run,
+        create_types,
+    };
+
+    if config.database_url.starts_with("memory:") {
+        return Err("Import needs a database, database_url is memory:".to_string());
+    }
+    let data = fs::read(file).map_err(|e| format!("Failed to read {}: {}", file.display(), e))?;
+    let images = ImageSources::Directory(file.parent().map(Path::to_path_buf).unwrap_or_default());
+
+    let repos = Repositories::new(SqlRepository::new(init_db(config).await));
+    let report = import_products(
+        repos.products.as_ref(),
+        repos.product_types.as_ref(),
+        config,
+        CLI_ACTOR,
+        &data,
+        &images,
+        &options,
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+
+    for error in &report.errors {
+        match &error.field {
+            Some(field) => eprintln!("line {}: {}: {}", error.line, field, error.message),
+            None => eprintln!("line {}: {}", error.line, error.message),
+        }
+    }
+    if !report.errors.is_empty() {
+        return Err(format!(
+            "{} of {} rows have errors, nothing imported",
+            report.total_rows - report.valid_rows,
+            report.total_rows
+        ));
+    }
+
+    if report.dry_run {
+        println!(
+            "✅ {} rows are valid, {} product types to create",
+            report.valid_rows,
+            report.created_types.len()
+        );
+    } else {
+        println!(
+            "✅ Imported {} products, created {} product types",
+            report.product_ids.len(),
+            report.created_types.len()
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repository::MemoryRepository;
+    use serde_json::json;
+
+    fn csv_rows(data: &str) -> (Vec<RawRow>, Vec<ImportRowError>) {
+        let (rows, header_errors) = parse_csv(data.as_bytes()).unwrap();
+        let rows = rows.into_iter().map(|(_, row)| row.unwrap()).collect();
+        (rows, header_errors)
+    }
+
+    #[test]
+    fn csv_detail_columns_are_unflattened() {
+        let (rows, header_errors) = csv_rows(
+            "\u{feff}id,name,price,currency,stock,product_type_name,created_at,images,detail.color,detail.spec.cpu,detail.spec.ram_gb,detail.Note,detail.sizes\n\
+             1,tee,99.50,THB,3,,2026-01-01 00:00:00,,red,i7,16,'-5,\"[1,2]\"\n",
+        );
+        assert!(header_errors.is_empty());
+        let row = &rows[0];
+        assert!(row.errors.is_empty());
+        assert_eq!(row.name.as_deref(), Some("tee"));
+        assert_eq!(
+            row.detail,
+            Some(json!({
+                "color": "red",
+                "spec": {"cpu": "i7", "ram_gb": 16},
+                "Note": "-5",
+                "sizes": [1, 2],
+            }))
+        );
+    }
+
+    #[test]
+    fn csv_detail_columns_merge_with_detail_json() {
+        let (rows, _) = csv_rows(
+            "name,price,detail,detail.spec.cpu,detail.color\n\
+             tee,1,\"{\"\"spec\"\": {\"\"ram_gb\"\": 8}}\",i7,\n\
+             polo,1,\"{\"\"color\"\": \"\"red\"\"}\",,blue\n",
+        );
+        assert_eq!(
+            rows[0].detail,
+            Some(json!({"spec": {"ram_gb": 8, "cpu": "i7"}}))
+        );
+        assert!(rows[0].errors.is_empty());
+        assert_eq!(rows[1].errors.len(), 1);
+        assert_eq!(rows[1].errors[0].field.as_deref(), Some("detail.color"));
+    }
+
+    #[test]
+    fn insert_detail_rejects_conflicting_paths() {
+        let path = |path: &str| path.split('.').map(String::from).collect::<Vec<_>>();
+        let mut detail = Map::new();
+        assert!(insert_detail(&mut detail, &path("a"), json!(1)));
+        assert!(!insert_detail(&mut detail, &path("a.b"), json!(2)));
+        assert!(insert_detail(&mut detail, &path("c.d"), json!(3)));
+        assert!(!insert_detail(&mut detail, &path("c"), json!(4)));
+        assert_eq!(Value::Object(detail), json!({"a": 1, "c": {"d": 3}}));
+    }
+
+    #[test]
+    fn detail_cell_reads_export_values() {
+        assert_eq!(detail_cell("12.5"), json!(12.5));
+        assert_eq!(detail_cell("true"), json!(true));
+        assert_eq!(detail_cell("'=SUM(A1)"), json!("=SUM(A1)"));
+        assert_eq!(detail_cell("'quoted"), json!("'quoted"));
+        assert_eq!(detail_cell("red, blue"), json!("red, blue"));
+        assert_eq!(detail_cell("null"), json!("null"));
+    }
+
+    fn row_fields(raw: RawRow) -> Vec<String> {
+        let images = ImageSources::Uploaded(HashMap::from([("a.jpg".to_string(), Vec::new())]));
+        match validate_row(2, raw, &Config::default(), &images) {
+            Ok(_) => Vec::new(),
+            Err(errors) => errors.into_iter().filter_map(|e| e.field).collect(),
+        }
+    }
+
+    #[test]
+    fn validate_row_checks_every_field() {
+        let (rows, _) = csv_rows(
+            "name,price,currency,stock,product_type_name,detail,images
+             tee,99.50,usd,3,other,,a.jpg
+             a/b,-1,THB,-2,x/y,[1],b.jpg
+             ,1.234,XYZ,many,,{,
+",
+        );
+        let mut rows = rows.into_iter();
+
+        let product = validate_row(
+            2,
+            rows.next().unwrap(),
+            &Config::default(),
+            &ImageSources::Uploaded(HashMap::from([("a.jpg".to_string(), Vec::new())])),
+        )
+        .unwrap();
+        assert_eq!(product.price, Money::new(9950, Currency::Usd));
+        assert_eq!(product.stock, 3);
+        assert_eq!(product.type_name, None);
+        assert_eq!(product.detail, serde_json::json!({}));
+        assert_eq!(product.image_paths, ["a.jpg"]);
+
+        assert_eq!(
+            row_fields(rows.next().unwrap()),
+            [
+                "name",
+                "product_type_name",
+                "price",
+                "stock",
+                "detail",
+                "images"
+            ]
+        );
+        assert_eq!(
+            row_fields(rows.next().unwrap()),
+            ["detail", "name", "currency", "stock"]
+        );
+    }
+
+    #[tokio::test]
+    async fn unknown_columns_are_reported_in_dry_run() {
+        let repo = MemoryRepository::new();
+        let options = ImportOptions {
+            format: ImportFormat::Csv,
+            dry_run: true,
+            create_types: false,
+        };
+        let report = import_products(
+            &repo,
+            &repo,
+            &Config::default(),
+            "test",
+            b"name,price,colour,detail.bad[0]\ntee,1,red,x\npolo,-1,blue,y\n",
+            &ImageSources::Uploaded(HashMap::new()),
+            &options,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(report.total_rows, 2);
+        assert_eq!(report.valid_rows, 1);
+        let errors: Vec<(u64, Option<&str>)> = report
+            .errors
+            .iter()
+            .map(|e| (e.line, e.field.as_deref()))
+            .collect();
+        assert_eq!(
+            errors,
+            [
+                (1, Some("colour")),
+                (1, Some("detail.bad[0]")),
+                (3, Some("price"))
+            ]
+        );
+    }
+}
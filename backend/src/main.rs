@@ -1,35 +1,138 @@
+mod backup;
+mod barcode;
+mod config;
 mod db;
+mod export;
 mod models;
 mod handlers;
+mod import;
+mod repository;
+mod trash;
 
 use actix_web::{App, HttpServer};
+use config::Config;
 use db::init_db;
+use dotenvy::dotenv;
+use repository::{MemoryRepository, Repositories, SqlRepository};
 
-use handlers::product_type::{delete_product_type_all, get_product_types, post_product_types, /*update_product_type ,*/delete_product_type};
-use handlers::products::{get_products , post_products ,update_product ,delete_product};
+use handlers::product_type::{delete_product_type_all, get_product_types, post_product_types, /*update_product_type ,*/delete_product_type, restore_product_type, purge_product_type, patch_product_type};
+use handlers::products::{get_products , get_product, get_product_facets, post_products ,clone_product ,update_product ,patch_product ,get_product_images, update_product_images ,delete_product, restore_product, purge_product};
 use handlers::get_images::get_image;
+use handlers::export::get_products_export;
+use handlers::import::post_products_import;
+use handlers::schema::get_schema_version;
+use handlers::barcode::{get_product_by_barcode, get_product_barcode};
+use handlers::variants::{put_variant_options, get_variants, post_variant, patch_variant, delete_variant};
+use handlers::stock::{get_stock_movements, post_stock_adjustment, get_low_stock, get_stock_alerts, post_stock_alert_ack};
+use handlers::audit::get_audit;
+use handlers::backup::{get_backups, post_backup};
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
-    let pool = init_db().await;
+    dotenv().ok();
+
+    let config = match Config::load() {
+        Ok(config) => config,
+        Err(e) => panic!("❌ {}", e),
+    };
+    // backend backup | backend backups | backend restore <ไฟล์> ทำงานแล้วจบโดยไม่เปิด server
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if !args.is_empty() {
+        let result = match args[0].as_str() {
+            "import" => import::run_command(&config, &args[1..]).await,
+            _ => backup::run_command(&config, &args).await,
+        };
+        if let Err(e) = result {
+            eprintln!("❌ {}", e);
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+    // database_url = "memory:" รัน backend โดยเก็บข้อมูลไว้ในหน่วยความจำ (ข้อมูลหายเมื่อปิด)
+    let pool = if config.database_url.starts_with("memory:") {
+        println!("⚠️ Using in-memory store, data is lost on shutdown");
+        None
+    } else {
+        Some(init_db(&config).await)
+    };
+    // กัน `backend restore` ไม่ให้สลับไฟล์ฐานข้อมูลระหว่างที่ server นี้ทำงาน
+    let _database_lock = match &pool {
+        Some(_) if backup::SUPPORTED => match backup::lock_database(&config, false) {
+            Ok(lock) => Some(lock),
+            Err(e) => panic!("❌ {}", e),
+        },
+        _ => None,
+    };
+    let repos = match &pool {
+        Some(pool) => Repositories::new(SqlRepository::new(pool.clone())),
+        None => Repositories::new(MemoryRepository::new()),
+    };
+    trash::spawn_auto_purge(repos.clone(), config.clone());
+    let bind_addr = (config.host.clone(), config.port);
+    println!(
+        "🚀 Backend listening on {}:{} ({})",
+        config.host, config.port, config.public_url
+    );
 
     HttpServer::new(move || {
-        App::new()
-            .app_data(actix_web::web::Data::new(pool.clone()))
+        let mut app = App::new()
+            .app_data(actix_web::web::Data::new(config.clone()))
+            .app_data(actix_web::web::Data::from(repos.products.clone()))
+            .app_data(actix_web::web::Data::from(repos.product_types.clone()))
+            .app_data(actix_web::web::Data::from(repos.images.clone()))
+            .app_data(actix_web::web::Data::from(repos.audit.clone()));
+        if let Some(pool) = &pool {
+            app = app.app_data(actix_web::web::Data::new(pool.clone()));
+        }
+
+        app
+            //schema
+            .service(get_schema_version)
+            //audit
+            .service(get_audit)
+            //backups
+            .service(post_backup)
+            .service(get_backups)
             //images
             .service(get_image)
             //product_types
             .service(get_product_types)
             .service(post_product_types)
             //.service(update_product_type)
+            .service(patch_product_type)
             .service(delete_product_type)
             .service(delete_product_type_all)
+            .service(restore_product_type)
+            .service(purge_product_type)
             //products
             .service(get_products)
+            // ต้องมาก่อน /api/products/{id}
+            .service(get_product_facets)
+            .service(get_products_export)
+            .service(get_low_stock)
+            .service(get_product_by_barcode)
+            .service(get_product)
             .service(post_products)
+            .service(post_products_import)
+            .service(clone_product)
             .service(update_product)
+            .service(patch_product)
+            .service(get_product_images)
+            .service(update_product_images)
+            .service(post_stock_adjustment)
+            .service(get_stock_movements)
+            .service(put_variant_options)
+            .service(get_variants)
+            .service(post_variant)
+            .service(patch_variant)
+            .service(delete_variant)
+            .service(get_product_barcode)
+            .service(get_stock_alerts)
+            .service(post_stock_alert_ack)
             .service(delete_product)
+            .service(restore_product)
+            .service(purge_product)
     })
-    .bind(("127.0.0.1", 2001))?
+    .bind(bind_addr)?
     .run()
     .await
 }
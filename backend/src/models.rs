@@ -1,18 +1,19 @@
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use chrono::NaiveDateTime;
+use chrono::{NaiveDate, NaiveDateTime};
+use std::collections::BTreeMap;
+use std::str::FromStr;
 //ส่วนรับโครงสร้างข้อมูลของ products_type_colunm
 #[derive(Serialize)]
 pub struct ProductType {
     pub id: i64,
     pub name: String,
     pub images_path: Vec<String>,
-}
-
-#[derive(Deserialize)]
-pub struct NewProductType {
-    pub name: String,
-    pub images_path: Vec<String>,
+    // ค่าเริ่มต้นของจุดสั่งซื้อสำหรับสินค้าในประเภทที่ไม่ได้ตั้งเอง
+    pub reorder_threshold: Option<i64>,
+    pub reorder_quantity: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deleted_at: Option<NaiveDateTime>,
 }
 
 //ส่วนรับโครงสร้างข้อมูลของ products_colunm
@@ -20,31 +21,351 @@ pub struct NewProductType {
 pub struct Products{
     pub id:i64,
     pub name_product: String,
-    pub price: f64,
+    pub price: Money,
     pub detail: Value,
     pub images_path: Vec<String>,
     pub stock:i64,
+    // true = ขายเกิน stock ได้ (stock ติดลบจาก stock-adjustments ได้)
+    pub allow_backorder: bool,
+    // stock <= reorder_threshold ถือว่าใกล้หมด ควรสั่งเพิ่ม reorder_quantity ชิ้น (null = ใช้ค่าของประเภท)
+    pub reorder_threshold: Option<i64>,
+    pub reorder_quantity: Option<i64>,
+    // รหัสสินค้าของร้าน และ barcode สำหรับพิมพ์ฉลาก/สแกนหน้าร้าน (null = ยังไม่ได้ตั้ง)
+    pub sku: Option<String>,
+    pub barcode: Option<String>,
+    pub barcode_format: Option<BarcodeFormat>,
     pub create_at:NaiveDateTime,
     pub products_type_id: Option<i64>,
     pub products_type_name: Option<String>,
+    // แกนตัวเลือกของ variant ([] = สินค้าไม่มี variant)
+    pub variant_options: Vec<VariantOption>,
+    // ช่วงราคาและ stock รวมของ variant (null = ไม่มี variant)
+    pub variant_summary: Option<VariantSummary>,
+    // variant ทั้งหมด มีเฉพาะใน GET /api/products/{id}
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub variants: Option<Vec<ProductVariant>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deleted_at: Option<NaiveDateTime>,
+    // ข้อความส่วนที่ตรงกับคำค้นเป็น HTML: ข้อความถูก escape แล้ว คำที่ตรงถูกครอบด้วย <mark></mark> (มีเฉพาะตอนค้นหา)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub snippet: Option<String>,
+}
+
+// รูปของสินค้าพร้อม id ที่ใช้ลบหรือเรียงลำดับผ่าน PATCH /api/products/{id}/images
+#[derive(Serialize, Debug, Clone)]
+pub struct ProductImage {
+    pub id: i64,
+    pub image_path: String,
 }
 
 #[derive(Deserialize,Debug)]
 pub struct NewProducts{
     pub name_product: String,
-    pub price: f64,
+    pub price: Money,
     pub detail: Value,
     pub images_path: Vec<String>,
     pub stock:i64,
     pub products_type_name: Option<String>,
 }
 
+// body ของ PATCH /api/products/{id} ไม่ส่ง field ไหน = ไม่แก้ field นั้น
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct ProductPatch {
+    pub name_product: Option<String>,
+    pub price: Option<Money>,
+    pub detail: Option<Value>,
+    pub stock: Option<i64>,
+    // null = สินค้าไม่มีประเภท ต่างจากการไม่ส่ง field นี้
+    #[serde(default, deserialize_with = "present")]
+    pub products_type_name: Option<Option<String>>,
+    // ส่งมา = แทนที่รูปภาพทั้งหมด
+    pub images_path: Option<Vec<String>>,
+    pub allow_backorder: Option<bool>,
+    // null = กลับไปใช้ค่าของประเภท
+    #[serde(default, deserialize_with = "present")]
+    pub reorder_threshold: Option<Option<i64>>,
+    #[serde(default, deserialize_with = "present")]
+    pub reorder_quantity: Option<Option<i64>>,
+    // null = ลบ sku/barcode ของสินค้า
+    #[serde(default, deserialize_with = "present")]
+    pub sku: Option<Option<String>>,
+    #[serde(default, deserialize_with = "present")]
+    pub barcode: Option<Option<String>>,
+    // ไม่ส่ง = เดาจาก barcode (13 หลัก = ean13, 12 หลัก = upca, อื่น ๆ = code128)
+    pub barcode_format: Option<BarcodeFormat>,
+}
+
+// body ของ POST /api/products/{id}/clone (ไม่บังคับ) field ที่ไม่ส่ง = ใช้ค่าของสินค้าต้นฉบับ
+#[derive(Deserialize, Debug, Default)]
+#[serde(deny_unknown_fields)]
+pub struct ProductClone {
+    pub name_product: Option<String>,
+    pub price: Option<Money>,
+    pub detail: Option<Value>,
+    pub stock: Option<i64>,
+    // null = สำเนาไม่มีประเภท
+    #[serde(default, deserialize_with = "present")]
+    pub products_type_name: Option<Option<String>>,
+}
+
+// body ของ PATCH /api/product-types/{id} ค่าเริ่มต้นของจุดสั่งซื้อ null = ไม่มีค่าเริ่มต้น
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct ProductTypePatch {
+    #[serde(default, deserialize_with = "present")]
+    pub reorder_threshold: Option<Option<i64>>,
+    #[serde(default, deserialize_with = "present")]
+    pub reorder_quantity: Option<Option<i64>>,
+}
+
+// field ที่ส่งมา (รวม null) เป็น Some ส่วน field ที่ไม่ส่งได้ None จาก #[serde(default)]
+fn present<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+//ส่วนของราคา เก็บเป็นหน่วยย่อย (สตางค์/เซนต์) เป็นจำนวนเต็ม ไม่ใช้ f64
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(try_from = "MoneyInput")]
+pub struct Money {
+    pub amount: i64,
+    pub currency: Currency,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum Currency {
+    #[default]
+    Thb,
+    Usd,
+    Eur,
+    Jpy,
+}
+
+impl Currency {
+    pub fn code(&self) -> &'static str {
+        match self {
+            Currency::Thb => "THB",
+            Currency::Usd => "USD",
+            Currency::Eur => "EUR",
+            Currency::Jpy => "JPY",
+        }
+    }
+
+    // จำนวนหลักทศนิยมของหน่วยย่อย เช่น THB = 2 (สตางค์), JPY = 0
+    pub fn minor_digits(&self) -> u32 {
+        match self {
+            Currency::Jpy => 0,
+            _ => 2,
+        }
+    }
+}
+
+impl FromStr for Currency {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_uppercase().as_str() {
+            "THB" => Ok(Currency::Thb),
+            "USD" => Ok(Currency::Usd),
+            "EUR" => Ok(Currency::Eur),
+            "JPY" => Ok(Currency::Jpy),
+            _ => Err(format!("Unsupported currency: {}", s)),
+        }
+    }
+}
+
+impl Money {
+    pub fn new(amount: i64, currency: Currency) -> Self {
+        Money { amount, currency }
+    }
+
+    // แปลงข้อความเช่น "1250.50" แบบเข้มงวด: ตัวเลขล้วน ทศนิยมไม่เกินหน่วยย่อยของสกุลเงิน
+    // ไม่รับ "", ".5", "5.", "1,000", "1e3", "+5" และราคาติดลบ "-5"
+    pub fn parse(input: &str, currency: Currency) -> Result<Money, String> {
+        let text = input.trim();
+        if text.starts_with('-') {
+            return Err("Price must not be negative".to_string());
+        }
+        let (whole, fraction) = match text.split_once('.') {
+            Some((w, f)) => (w, Some(f)),
+            None => (text, None),
+        };
+
+        let is_digits = |s: &str| !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit());
+        if !is_digits(whole) || fraction.is_some_and(|f| !is_digits(f)) {
+            return Err(format!("Invalid price: {}", input));
+        }
+
+        let fraction = fraction.unwrap_or("");
+        let scale = currency.minor_digits() as usize;
+        if fraction.len() > scale {
+            return Err(format!(
+                "Price {} has more than {} decimal places for {}",
+                input,
+                scale,
+                currency.code()
+            ));
+        }
+
+        let padded = format!("{}{:0<width$}", whole, fraction, width = scale);
+        let amount = padded
+            .parse::<i64>()
+            .map_err(|_| format!("Price out of range: {}", input))?;
+
+        Ok(Money::new(amount, currency))
+    }
+
+    // แปลงจากตัวเลขทศนิยม (ไคลเอนต์เก่าที่ส่ง price เป็น number) ด้วยกฎเดียวกับ parse
+    // ใช้ข้อความที่สั้นที่สุดของตัวเลข (12.345 -> "12.345") จึงไม่ปัดเศษเงียบ ๆ และ 0.1 + 0.2 ไม่ผ่าน
+    pub fn from_f64(value: f64, currency: Currency) -> Result<Money, String> {
+        Money::parse(&value.to_string(), currency)
+    }
+
+    // หน่วยย่อยที่ไคลเอนต์ส่งมาตรง ๆ ต้องผ่านเงื่อนไขเดียวกับ parse
+    pub fn from_minor(amount: i64, currency: Currency) -> Result<Money, String> {
+        if amount < 0 {
+            return Err("Price must not be negative".to_string());
+        }
+        Ok(Money::new(amount, currency))
+    }
+
+    // ข้อความทศนิยมตามหน่วยย่อยของสกุลเงิน เช่น 1250 THB -> "12.50" (รูปแบบเดียวกับที่ parse รับ)
+    pub fn to_decimal_string(self) -> String {
+        let scale = self.currency.minor_digits();
+        let sign = if self.amount < 0 { "-" } else { "" };
+        let amount = self.amount.unsigned_abs();
+        if scale == 0 {
+            return format!("{}{}", sign, amount);
+        }
+        let factor = 10u64.pow(scale);
+        format!(
+            "{}{}.{:0width$}",
+            sign,
+            amount / factor,
+            amount % factor,
+            width = scale as usize
+        )
+    }
+
+    pub fn to_f64(self) -> f64 {
+        self.amount as f64 / 10f64.powi(self.currency.minor_digits() as i32)
+    }
+}
+
+// รูปแบบที่รับได้จาก JSON: "12.50", 12.5 หรือ {"amount": 1250, "currency": "THB"}
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum MoneyInput {
+    Minor { amount: i64, currency: Currency },
+    Text(String),
+    Number(f64),
+}
+
+impl TryFrom<MoneyInput> for Money {
+    type Error = String;
+
+    fn try_from(input: MoneyInput) -> Result<Self, Self::Error> {
+        match input {
+            MoneyInput::Minor { amount, currency } => Money::from_minor(amount, currency),
+            MoneyInput::Text(text) => Money::parse(&text, Currency::default()),
+            MoneyInput::Number(value) => Money::from_f64(value, Currency::default()),
+        }
+    }
+}
+
 //ส่วนของsearch data
 #[derive(Deserialize, Debug)]
 pub struct Querysearchandpage {
     pub search: Option<String>,
     pub page: Option<i64>,
     pub type_id: Option<String>,
+    // true = แสดงเฉพาะรายการในถังขยะ
+    pub trash: Option<bool>,
+    pub sort: Option<ListSort>,
+    // จำนวนรายการต่อหน้า (ค่าเริ่มต้น DEFAULT_PER_PAGE สูงสุด MAX_PER_PAGE)
+    pub per_page: Option<i64>,
+    // next_cursor/prev_cursor จากหน้าก่อน ถ้าส่งมาจะไม่ใช้ page (เฉพาะรายการสินค้า)
+    pub cursor: Option<String>,
+    // ตัวกรองสินค้า (รวมขอบทุกตัว) ราคาเป็นข้อความเช่น "99.50" ในสกุลเงิน currency (ค่าเริ่มต้น THB)
+    // และได้เฉพาะสินค้าในสกุลเงินนั้น
+    pub min_price: Option<String>,
+    pub max_price: Option<String>,
+    pub currency: Option<String>,
+    pub min_stock: Option<i64>,
+    pub max_stock: Option<i64>,
+    // true = stock > 0, false = หมด (stock <= 0)
+    pub in_stock: Option<bool>,
+    pub created_from: Option<NaiveDate>,
+    pub created_to: Option<NaiveDate>,
+}
+
+pub const DEFAULT_PER_PAGE: i64 = 10;
+pub const MAX_PER_PAGE: i64 = 100;
+
+// หน้าต่ำสุดคือ 1 และสูงสุดเท่าที่ (page - 1) * per_page ยังไม่ล้น i64 (OFFSET ไม่ติดลบ)
+pub fn page_number(page: Option<i64>) -> i64 {
+    page.unwrap_or(1).clamp(1, i64::MAX / MAX_PER_PAGE)
+}
+
+// per_page ที่เกินช่วงถูกปรับให้อยู่ใน 1..=MAX_PER_PAGE
+pub fn page_size(per_page: Option<i64>) -> i64 {
+    per_page.unwrap_or(DEFAULT_PER_PAGE).clamp(1, MAX_PER_PAGE)
+}
+
+impl Querysearchandpage {
+    pub fn page(&self) -> i64 {
+        page_number(self.page)
+    }
+
+    pub fn per_page(&self) -> i64 {
+        page_size(self.per_page)
+    }
+}
+
+// ลำดับของรายการ ?sort=price_desc ทุกแบบใช้ id (ทิศทางเดียวกัน) ตัดสินเมื่อค่าเท่ากัน
+// price_* เรียงตามรหัสสกุลเงินก่อน แล้วจึงราคา (ไม่แปลงสกุลเงิน)
+// relevance ใช้ได้เฉพาะตอนค้นหาสินค้า และเป็นค่าเริ่มต้นเมื่อมีคำค้น แบ่งหน้าได้แค่แบบ page
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ListSort {
+    #[default]
+    IdAsc,
+    IdDesc,
+    NameAsc,
+    NameDesc,
+    PriceAsc,
+    PriceDesc,
+    CreatedAtAsc,
+    CreatedAtDesc,
+    StockAsc,
+    StockDesc,
+    Relevance,
+}
+
+impl ListSort {
+    pub fn is_desc(&self) -> bool {
+        matches!(
+            self,
+            ListSort::IdDesc
+                | ListSort::NameDesc
+                | ListSort::PriceDesc
+                | ListSort::CreatedAtDesc
+                | ListSort::StockDesc
+        )
+    }
+
+    // ประเภทสินค้ามีแค่ id กับชื่อ
+    pub fn applies_to_types(&self) -> bool {
+        matches!(
+            self,
+            ListSort::IdAsc | ListSort::IdDesc | ListSort::NameAsc | ListSort::NameDesc
+        )
+    }
 }
 
 #[derive(Serialize)]
@@ -55,8 +376,518 @@ pub struct PaginatedResponse<T> {
 //ส่วนของpage 
 #[derive(Serialize)]
 pub struct PaginationInfo {
-    pub total_items: i64,
+    // แบบ cursor ไม่นับจำนวนทั้งหมด จึงไม่มี total_items, current_page และ total_pages
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total_items: Option<i64>,
     pub items_per_page: i64,
-    pub current_page: i64,
-    pub total_pages: i64,
-}
\ No newline at end of file
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub current_page: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total_pages: Option<i64>,
+    // ลำดับที่ใช้จริง (ไม่มีในรายการที่เรียงแบบเดียว เช่น audit log)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sort: Option<ListSort>,
+    // token สำหรับหน้าถัดไป/ก่อนหน้า ส่งกลับมาเป็น ?cursor= (ไม่มีเมื่อสุดรายการ และไม่มีเลยเมื่อ sort=relevance)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prev_cursor: Option<String>,
+}
+//ส่วนของ facet (จำนวนสินค้าตามตัวกรองเดียวกับรายการสินค้า)
+#[derive(Deserialize, Debug)]
+pub struct FacetQuery {
+    // key ใน detail คั่นด้วย , เช่น color,spec.cpu
+    pub detail_keys: Option<String>,
+    // ขอบของช่วงราคาคั่นด้วย , เช่น 100,500,1000 ในสกุลเงิน currency
+    pub price_buckets: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct ProductFacets {
+    pub total_items: i64,
+    pub types: Vec<TypeFacet>,
+    pub price: Vec<PriceBucket>,
+    pub stock: StockFacet,
+    // ตามลำดับของ detail_keys
+    pub detail: Vec<DetailFacet>,
+}
+
+// id และ name เป็น null สำหรับสินค้าที่ไม่มีประเภท
+#[derive(Serialize)]
+pub struct TypeFacet {
+    pub id: Option<i64>,
+    pub name: Option<String>,
+    pub count: i64,
+}
+
+// ช่วง min <= ราคา < max (ช่วงแรกไม่มี min ช่วงสุดท้ายไม่มี max)
+#[derive(Serialize)]
+pub struct PriceBucket {
+    pub min: Option<Money>,
+    pub max: Option<Money>,
+    pub count: i64,
+}
+
+#[derive(Serialize)]
+pub struct StockFacet {
+    pub in_stock: i64,
+    pub out_of_stock: i64,
+}
+
+#[derive(Serialize)]
+pub struct DetailFacet {
+    pub key: String,
+    pub values: Vec<ValueFacet>,
+}
+
+#[derive(Serialize)]
+pub struct ValueFacet {
+    pub value: String,
+    pub count: i64,
+}
+//ส่วนของไฟล์ backup
+#[derive(Serialize)]
+pub struct BackupInfo {
+    pub file: String,
+    pub size: u64,
+    pub created_at: NaiveDateTime,
+}
+//ส่วนของเวอร์ชัน schema
+#[derive(Serialize)]
+pub struct SchemaVersion {
+    pub current: i64,
+    pub latest: i64,
+    pub up_to_date: bool,
+}
+//ส่วนของ audit log
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditAction {
+    Create,
+    Update,
+    Delete,
+    Restore,
+    Purge,
+}
+
+impl AuditAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuditAction::Create => "create",
+            AuditAction::Update => "update",
+            AuditAction::Delete => "delete",
+            AuditAction::Restore => "restore",
+            AuditAction::Purge => "purge",
+        }
+    }
+}
+
+impl FromStr for AuditAction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "create" => Ok(AuditAction::Create),
+            "update" => Ok(AuditAction::Update),
+            "delete" => Ok(AuditAction::Delete),
+            "restore" => Ok(AuditAction::Restore),
+            "purge" => Ok(AuditAction::Purge),
+            _ => Err(format!("Unknown audit action: {}", s)),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditEntity {
+    Product,
+    ProductType,
+}
+
+impl AuditEntity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuditEntity::Product => "product",
+            AuditEntity::ProductType => "product_type",
+        }
+    }
+}
+
+impl FromStr for AuditEntity {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "product" => Ok(AuditEntity::Product),
+            "product_type" => Ok(AuditEntity::ProductType),
+            _ => Err(format!("Unknown audit entity: {}", s)),
+        }
+    }
+}
+
+// before/after เป็น snapshot ของข้อมูลก่อนและหลังแก้ไข (null ถ้ายังไม่มีหรือถูกลบถาวรแล้ว)
+#[derive(Serialize, Clone)]
+pub struct AuditEntry {
+    pub id: i64,
+    pub actor: String,
+    pub action: AuditAction,
+    pub entity_type: AuditEntity,
+    pub entity_id: i64,
+    pub before: Option<Value>,
+    pub after: Option<Value>,
+    pub created_at: NaiveDateTime,
+}
+
+// ตัวกรองของ GET /api/audit (from/to เป็นวันที่ รวมทั้งวัน)
+#[derive(Deserialize, Debug)]
+pub struct AuditQuery {
+    pub actor: Option<String>,
+    pub action: Option<AuditAction>,
+    pub entity_type: Option<AuditEntity>,
+    pub entity_id: Option<i64>,
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+    pub page: Option<i64>,
+    pub per_page: Option<i64>,
+}
+
+//ส่วนของการ import สินค้า
+// POST /api/products/import?dry_run=true ตรวจอย่างเดียว create_types=true สร้างประเภทที่ยังไม่มี
+// format = csv | ndjson (ไม่ระบุจะดูจากนามสกุลของไฟล์)
+#[derive(Deserialize, Debug)]
+pub struct ImportQuery {
+    pub dry_run: Option<bool>,
+    pub create_types: Option<bool>,
+    pub format: Option<String>,
+}
+
+// ผลการ import ถ้ามีแถวที่ไม่ผ่านจะไม่บันทึกอะไรเลย (product_ids ว่าง)
+#[derive(Serialize, Debug)]
+pub struct ImportReport {
+    pub dry_run: bool,
+    pub total_rows: usize,
+    pub valid_rows: usize,
+    pub errors: Vec<ImportRowError>,
+    // ประเภทที่ถูกสร้าง (หรือจะถูกสร้างถ้าเป็น dry_run)
+    pub created_types: Vec<String>,
+    // id ของสินค้าที่เพิ่มตามลำดับแถว
+    pub product_ids: Vec<i64>,
+}
+
+// line คือบรรทัดในไฟล์ (CSV นับบรรทัดหัวตารางเป็นบรรทัดที่ 1)
+#[derive(Serialize, Debug)]
+pub struct ImportRowError {
+    pub line: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub field: Option<String>,
+    pub message: String,
+}
+
+//ส่วนของการ export สินค้า
+// GET /api/products/export?format=csv | ndjson | xlsx (ค่าเริ่มต้น csv) ร่วมกับตัวกรองของ GET /api/products
+#[derive(Deserialize, Debug)]
+pub struct ExportQuery {
+    pub format: Option<String>,
+}
+
+//ส่วนของ stock movement (ประวัติการเปลี่ยน stock)
+// sale และ damage ต้องเป็นจำนวนติดลบ restock เป็นบวก correction เป็นได้ทั้งสองทาง
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum StockReason {
+    Sale,
+    Restock,
+    Damage,
+    Correction,
+}
+
+impl StockReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StockReason::Sale => "sale",
+            StockReason::Restock => "restock",
+            StockReason::Damage => "damage",
+            StockReason::Correction => "correction",
+        }
+    }
+}
+
+impl FromStr for StockReason {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "sale" => Ok(StockReason::Sale),
+            "restock" => Ok(StockReason::Restock),
+            "damage" => Ok(StockReason::Damage),
+            "correction" => Ok(StockReason::Correction),
+            _ => Err(format!("Unknown stock reason: {}", s)),
+        }
+    }
+}
+
+// body ของ POST /api/products/{id}/stock-adjustments quantity เป็นจำนวนที่เพิ่ม (บวก) หรือลด (ลบ)
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct StockAdjustment {
+    pub quantity: i64,
+    pub reason: StockReason,
+    pub note: Option<String>,
+}
+
+// หนึ่งรายการใน stock_movements stock_after คือ stock ของสินค้าหลังรายการนี้
+#[derive(Serialize, Debug, Clone)]
+pub struct StockMovement {
+    pub id: i64,
+    pub product_id: i64,
+    pub quantity: i64,
+    pub reason: StockReason,
+    pub note: Option<String>,
+    pub stock_after: i64,
+    pub actor: String,
+    pub created_at: NaiveDateTime,
+}
+
+// ตัวกรองของ GET /api/products/{id}/stock-movements
+#[derive(Deserialize, Debug)]
+pub struct StockMovementQuery {
+    pub reason: Option<StockReason>,
+    pub page: Option<i64>,
+    pub per_page: Option<i64>,
+}
+
+//ส่วนของการแจ้งเตือนสินค้าใกล้หมด
+// จุดสั่งซื้อที่ใช้จริงคือของสินค้า ถ้าสินค้าไม่ได้ตั้งจะใช้ของประเภท
+#[derive(Serialize, Debug)]
+pub struct LowStockProduct {
+    pub id: i64,
+    pub name_product: String,
+    pub stock: i64,
+    pub reorder_threshold: i64,
+    pub reorder_quantity: Option<i64>,
+    pub products_type_id: Option<i64>,
+    pub products_type_name: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct LowStockQuery {
+    pub page: Option<i64>,
+    pub per_page: Option<i64>,
+}
+
+// รายการใน outbox stock_alerts เกิดครั้งเดียวเมื่อ stock ลดจากเหนือ threshold ลงมาถึงหรือต่ำกว่า
+// ระบบที่รับการแจ้งเตือนอ่านรายการที่ยังไม่ acknowledge แล้ว acknowledge เมื่อจัดการแล้ว
+#[derive(Serialize, Debug, Clone)]
+pub struct StockAlert {
+    pub id: i64,
+    pub product_id: i64,
+    pub stock_movement_id: i64,
+    pub stock: i64,
+    pub reorder_threshold: i64,
+    pub reorder_quantity: Option<i64>,
+    pub created_at: NaiveDateTime,
+    pub acknowledged_at: Option<NaiveDateTime>,
+}
+
+// ?pending=true เฉพาะที่ยังไม่ acknowledge, false เฉพาะที่ acknowledge แล้ว
+#[derive(Deserialize, Debug)]
+pub struct StockAlertQuery {
+    pub pending: Option<bool>,
+    pub page: Option<i64>,
+    pub per_page: Option<i64>,
+}
+
+//ส่วนของ variant ของสินค้า (เช่นไซส์และสีของเสื้อ)
+// แกนตัวเลือกหนึ่งแกน เช่น {"name":"size","values":["S","M","L"]}
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct VariantOption {
+    pub name: String,
+    pub values: Vec<String>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct ProductVariant {
+    pub id: i64,
+    pub sku: String,
+    // ค่าของทุกแกนของสินค้า เช่น {"colour":"red","size":"M"}
+    pub options: BTreeMap<String, String>,
+    // null = ใช้ราคาของสินค้า
+    pub price: Option<Money>,
+    pub stock: i64,
+    // เลือกจากรูปของสินค้า ตามลำดับที่แสดง
+    pub images: Vec<ProductImage>,
+}
+
+// ราคาที่ใช้จริงต่ำสุด/สูงสุด (ราคาของ variant หรือของสินค้าถ้าไม่ได้ตั้ง) และ stock รวมของทุก variant
+#[derive(Serialize, Debug, Clone)]
+pub struct VariantSummary {
+    pub count: i64,
+    pub min_price: Money,
+    pub max_price: Money,
+    pub total_stock: i64,
+}
+
+// body ของ POST /api/products/{id}/variants
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct NewVariant {
+    pub sku: String,
+    pub options: BTreeMap<String, String>,
+    #[serde(default)]
+    pub price: Option<Money>,
+    #[serde(default)]
+    pub stock: i64,
+    // id ของรูปของสินค้า (จาก GET /api/products/{id}/images)
+    #[serde(default)]
+    pub image_ids: Vec<i64>,
+}
+
+// body ของ PATCH /api/products/{id}/variants/{variant_id} ไม่ส่ง field ไหน = ไม่แก้ field นั้น
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct VariantPatch {
+    pub sku: Option<String>,
+    pub options: Option<BTreeMap<String, String>>,
+    // null = กลับไปใช้ราคาของสินค้า
+    #[serde(default, deserialize_with = "present")]
+    pub price: Option<Option<Money>>,
+    pub stock: Option<i64>,
+    pub image_ids: Option<Vec<i64>>,
+}
+
+//ส่วนของ barcode
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum BarcodeFormat {
+    Ean13,
+    Upca,
+    Code128,
+}
+
+impl BarcodeFormat {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BarcodeFormat::Ean13 => "ean13",
+            BarcodeFormat::Upca => "upca",
+            BarcodeFormat::Code128 => "code128",
+        }
+    }
+}
+
+impl FromStr for BarcodeFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "ean13" => Ok(BarcodeFormat::Ean13),
+            "upca" => Ok(BarcodeFormat::Upca),
+            "code128" => Ok(BarcodeFormat::Code128),
+            _ => Err(format!("Unsupported barcode format: {}", s)),
+        }
+    }
+}
+
+// GET /api/products/{id}/barcode?format=svg | png (ค่าเริ่มต้น svg)
+// scale = ความกว้างของแท่งที่บางที่สุดเป็น pixel (1-10 ค่าเริ่มต้น 2)
+#[derive(Deserialize, Debug)]
+pub struct BarcodeImageQuery {
+    pub format: Option<String>,
+    pub scale: Option<u32>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn money_parse_is_strict() {
+        assert_eq!(
+            Money::parse("12.5", Currency::Thb),
+            Ok(Money::new(1250, Currency::Thb))
+        );
+        assert_eq!(
+            Money::parse(" 12 ", Currency::Thb),
+            Ok(Money::new(1200, Currency::Thb))
+        );
+        assert_eq!(
+            Money::parse("1200", Currency::Jpy),
+            Ok(Money::new(1200, Currency::Jpy))
+        );
+        for bad in [
+            "",
+            ".5",
+            "5.",
+            "1,000",
+            "1e3",
+            "+5",
+            "-5",
+            "12.345",
+            "99999999999999999999",
+        ] {
+            assert!(Money::parse(bad, Currency::Thb).is_err(), "{}", bad);
+        }
+        assert!(Money::parse("12.5", Currency::Jpy).is_err());
+    }
+
+    #[test]
+    fn money_from_f64_does_not_round() {
+        assert_eq!(
+            Money::from_f64(12.34, Currency::Thb),
+            Ok(Money::new(1234, Currency::Thb))
+        );
+        assert_eq!(
+            Money::from_f64(12.0, Currency::Thb),
+            Ok(Money::new(1200, Currency::Thb))
+        );
+        assert!(Money::from_f64(12.345, Currency::Thb).is_err());
+        assert!(Money::from_f64(0.1 + 0.2, Currency::Thb).is_err());
+        assert!(Money::from_f64(-1.0, Currency::Thb).is_err());
+        assert!(Money::from_f64(f64::NAN, Currency::Thb).is_err());
+        assert!(Money::from_f64(1e30, Currency::Thb).is_err());
+    }
+
+    #[test]
+    fn money_json_inputs_share_validation() {
+        let parse = |json: &str| serde_json::from_str::<Money>(json);
+        assert_eq!(parse("\"12.50\"").unwrap(), Money::new(1250, Currency::Thb));
+        assert_eq!(parse("12.5").unwrap(), Money::new(1250, Currency::Thb));
+        assert_eq!(
+            parse(r#"{"amount": 1250, "currency": "USD"}"#).unwrap(),
+            Money::new(1250, Currency::Usd)
+        );
+        assert!(parse("12.345").is_err());
+        assert!(parse("\"-1\"").is_err());
+        assert!(parse(r#"{"amount": -1, "currency": "THB"}"#).is_err());
+        assert!(parse(r#"{"amount": 1, "currency": "XYZ"}"#).is_err());
+    }
+
+    #[test]
+    fn page_and_per_page_are_clamped() {
+        assert_eq!(page_number(None), 1);
+        assert_eq!(page_number(Some(-3)), 1);
+        assert_eq!(page_number(Some(4)), 4);
+        let last = page_number(Some(i64::MAX));
+        assert!((last - 1).checked_mul(page_size(Some(i64::MAX))).is_some());
+
+        assert_eq!(page_size(None), DEFAULT_PER_PAGE);
+        assert_eq!(page_size(Some(0)), 1);
+        assert_eq!(page_size(Some(-5)), 1);
+        assert_eq!(page_size(Some(1000)), MAX_PER_PAGE);
+    }
+
+    #[test]
+    fn money_decimal_string_round_trips() {
+        for (amount, currency) in [
+            (1250, Currency::Thb),
+            (5, Currency::Usd),
+            (1200, Currency::Jpy),
+        ] {
+            let money = Money::new(amount, currency);
+            assert_eq!(
+                Money::parse(&money.to_decimal_string(), currency),
+                Ok(money)
+            );
+        }
+    }
+}
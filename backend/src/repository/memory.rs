@@ -0,0 +1,2184 @@
+use super::{
+    AuditFilter, AuditRepository, DetailCondition, DetailFilter, FACET_VALUE_LIMIT, ImageRecord,
+    ImageRepository, ImageSlot, ImportedProduct, MARK_END, MARK_START, PageSeek, ProductChanges,
+    ProductFilter, ProductInput, ProductRepository, ProductSnapshot, ProductTypeChanges,
+    ProductTypeRepository, ProductTypeSnapshot, RepoError, RepoResult, SortKey, SortValue,
+    TypeIdFilter, VariantChanges, VariantInput, VariantSnapshot, crosses_reorder_threshold,
+    json_number_text, now, price_buckets, search_terms, snapshot_json, snippet_html,
+    variant_options_error,
+};
+use crate::barcode::equivalent_codes;
+use crate::models::{
+    AuditAction, AuditEntity, AuditEntry, BarcodeFormat, Currency, DetailFacet, ListSort,
+    LowStockProduct, Money, ProductFacets, ProductImage, ProductType, ProductVariant, Products,
+    StockAdjustment, StockAlert, StockFacet, StockMovement, StockReason, TypeFacet, ValueFacet,
+    VariantOption, VariantSummary,
+};
+use async_trait::async_trait;
+use chrono::NaiveDateTime;
+use serde_json::Value;
+use std::cmp::{Ordering, Reverse};
+use std::collections::BTreeMap;
+use std::sync::{Mutex, MutexGuard};
+
+// repository ที่เก็บข้อมูลไว้ในหน่วยความจำ ใช้ทดสอบกฎต่าง ๆ โดยไม่ต้องมีไฟล์ฐานข้อมูล
+// และใช้รัน backend แบบไม่มีฐานข้อมูลได้ด้วย database_url = "memory:"
+// พฤติกรรมต้องตรงกับ SqlRepository รวมถึง ON DELETE CASCADE / SET NULL ของ schema
+#[derive(Default)]
+pub struct MemoryRepository {
+    state: Mutex<State>,
+}
+
+#[derive(Default)]
+struct State {
+    last_type_id: i64,
+    last_product_id: i64,
+    last_image_id: i64,
+    last_audit_id: i64,
+    last_stock_movement_id: i64,
+    last_stock_alert_id: i64,
+    last_variant_id: i64,
+    types: BTreeMap<i64, StoredType>,
+    products: BTreeMap<i64, StoredProduct>,
+    images: BTreeMap<i64, StoredImage>,
+    audit: Vec<AuditEntry>,
+    stock_movements: Vec<StockMovement>,
+    stock_alerts: Vec<StockAlert>,
+    variants: BTreeMap<i64, StoredVariant>,
+}
+
+struct StoredType {
+    name: String,
+    reorder_threshold: Option<i64>,
+    reorder_quantity: Option<i64>,
+    deleted_at: Option<NaiveDateTime>,
+}
+
+struct StoredProduct {
+    name: String,
+    price: Money,
+    detail: Value,
+    stock: i64,
+    allow_backorder: bool,
+    reorder_threshold: Option<i64>,
+    reorder_quantity: Option<i64>,
+    sku: Option<String>,
+    barcode: Option<(BarcodeFormat, String)>,
+    created_at: NaiveDateTime,
+    products_type_id: Option<i64>,
+    variant_options: Vec<VariantOption>,
+    deleted_at: Option<NaiveDateTime>,
+}
+
+struct StoredVariant {
+    product_id: i64,
+    sku: String,
+    options: BTreeMap<String, String>,
+    price_minor: Option<i64>,
+    stock: i64,
+    image_ids: Vec<i64>,
+}
+
+struct StoredImage {
+    image_path: String,
+    product_id: Option<i64>,
+    product_type_id: Option<i64>,
+    position: i64,
+}
+
+impl MemoryRepository {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State> {
+        // ถ้า thread อื่น panic ระหว่างถือ lock ข้อมูลยังใช้ต่อได้ เพราะทุกการแก้ไขทำเสร็จในครั้งเดียว
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+// เทียบเท่า name LIKE '%search%' (ไม่สนตัวพิมพ์เล็กใหญ่)
+fn contains_ignore_case(text: &str, search: &str) -> bool {
+    text.to_lowercase().contains(&search.to_lowercase())
+}
+
+// ค่าที่ใช้เรียงตาม sort แบบเดียวกับ sort_key ฝั่ง SQL (score = คะแนน relevance เมื่อมีคำค้น)
+fn product_sort_value(sort: ListSort, product: &StoredProduct, score: Option<u32>) -> SortValue {
+    match sort {
+        ListSort::NameAsc | ListSort::NameDesc => SortValue::Text(product.name.to_lowercase()),
+        ListSort::PriceAsc | ListSort::PriceDesc => SortValue::Price(
+            product.price.currency.code().to_string(),
+            product.price.amount,
+        ),
+        ListSort::CreatedAtAsc | ListSort::CreatedAtDesc => SortValue::Time(product.created_at),
+        ListSort::StockAsc | ListSort::StockDesc => SortValue::Int(product.stock),
+        ListSort::Relevance => {
+            score.map_or(SortValue::None, |score| SortValue::Float(score as f64))
+        }
+        ListSort::IdAsc | ListSort::IdDesc => SortValue::None,
+    }
+}
+
+// เทียบตาม sort แบบเดียวกับ ORDER BY ฝั่ง SQL (id ทิศทางเดียวกันตัดสินเมื่อค่าเท่ากัน)
+// relevance เรียงคะแนนมากก่อนแล้วจึง id จากน้อยไปมาก
+fn compare_keys(sort: ListSort, a: &SortKey, b: &SortKey) -> Ordering {
+    let value = a.value.partial_cmp(&b.value).unwrap_or(Ordering::Equal);
+    let id = a.id.cmp(&b.id);
+    match sort {
+        ListSort::Relevance => value.reverse().then(id),
+        _ if sort.is_desc() => value.then(id).reverse(),
+        _ => value.then(id),
+    }
+}
+
+// ค่าเดี่ยวใน detail ตาม path (สมาชิกของ array หรือค่าเดี่ยว) ในรูปข้อความคู่กับค่าตัวเลข
+// แบบเดียวกับ DETAIL_TEXT / DETAIL_IS_NUMBER ฝั่ง SQL
+fn detail_scalars(detail: &Value, path: &[String]) -> Vec<(String, Option<f64>)> {
+    let Some(value) = path
+        .iter()
+        .try_fold(detail, |value, key| value.as_object()?.get(key))
+    else {
+        return Vec::new();
+    };
+    let values = match value {
+        Value::Array(items) => items.iter().collect(),
+        other => vec![other],
+    };
+    values
+        .into_iter()
+        .filter_map(|value| match value {
+            Value::String(text) => Some((text.clone(), json_number_text(text))),
+            Value::Number(number) => Some((number.to_string(), number.as_f64())),
+            Value::Bool(flag) => Some((flag.to_string(), None)),
+            _ => None,
+        })
+        .collect()
+}
+
+// เงื่อนไขบน detail แบบเดียวกับ push_detail_filter ฝั่ง SQL
+fn detail_matches(detail: &Value, filter: &DetailFilter) -> bool {
+    detail_scalars(detail, &filter.path)
+        .into_iter()
+        .any(|(text, number)| match &filter.condition {
+            DetailCondition::Equals(expected) => &text == expected,
+            DetailCondition::Compare(comparison, bound) => {
+                number.is_some_and(|number| comparison.holds(number, *bound))
+            }
+        })
+}
+
+// ค่าทุกตัวใน JSON ต่อกันเป็นข้อความ (ไม่รวมชื่อ key) เหมือน products_search_source ของ SQLite
+fn json_values_text(value: &Value, out: &mut Vec<String>) {
+    match value {
+        Value::Null => {}
+        Value::String(text) => out.push(text.clone()),
+        Value::Array(items) => items.iter().for_each(|item| json_values_text(item, out)),
+        Value::Object(map) => map.values().for_each(|item| json_values_text(item, out)),
+        other => out.push(other.to_string()),
+    }
+}
+
+// คำในข้อความตรงกับคำค้นแบบขึ้นต้นคำ โดยแยกคำแบบเดียวกับ search_terms
+fn word_matches_term(word: &str, term: &str) -> bool {
+    search_terms(word)
+        .iter()
+        .any(|token| token.starts_with(term))
+}
+
+fn word_matches(word: &str, terms: &[String]) -> bool {
+    terms.iter().any(|term| word_matches_term(word, term))
+}
+
+fn field_matches(field: &str, term: &str) -> bool {
+    field
+        .split_whitespace()
+        .any(|word| word_matches_term(word, term))
+}
+
+// ข้อความราว 10 คำรอบคำแรกที่ตรง คำที่ตรงถูกครอบด้วย MARK_START/MARK_END เหมือน snippet() ของ FTS5
+fn snippet(field: &str, terms: &[String]) -> String {
+    let words: Vec<&str> = field.split_whitespace().collect();
+    let first = words
+        .iter()
+        .position(|word| word_matches(word, terms))
+        .unwrap_or(0);
+    let start = first.saturating_sub(3);
+    let end = (start + 10).min(words.len());
+
+    let mut parts: Vec<String> = words[start..end]
+        .iter()
+        .map(|word| {
+            if word_matches(word, terms) {
+                format!("{}{}{}", MARK_START, word, MARK_END)
+            } else {
+                word.to_string()
+            }
+        })
+        .collect();
+    if start > 0 {
+        parts.insert(0, "…".to_string());
+    }
+    if end < words.len() {
+        parts.push("…".to_string());
+    }
+    parts.join(" ")
+}
+
+impl State {
+    fn matches(&self, product: &StoredProduct, filter: &ProductFilter, terms: &[String]) -> bool {
+        let type_matches = match filter.type_id {
+            TypeIdFilter::None => true,
+            TypeIdFilter::IsNull => product.products_type_id.is_none(),
+            TypeIdFilter::Equal(id) => product.products_type_id == Some(id),
+        };
+        let price = product.price;
+        product.deleted_at.is_some() == filter.trashed
+            && type_matches
+            && filter
+                .min_price
+                .is_none_or(|min| price.currency == min.currency && price.amount >= min.amount)
+            && filter
+                .max_price
+                .is_none_or(|max| price.currency == max.currency && price.amount <= max.amount)
+            && filter.min_stock.is_none_or(|min| product.stock >= min)
+            && filter.max_stock.is_none_or(|max| product.stock <= max)
+            && filter
+                .created_from
+                .is_none_or(|from| product.created_at >= from)
+            && filter.created_to.is_none_or(|to| product.created_at < to)
+            && filter
+                .detail
+                .iter()
+                .all(|detail| detail_matches(&product.detail, detail))
+            && self.search_score(product, terms).is_some()
+    }
+
+    // images_path ยังว่าง เหมือนผลจาก SqlRepository
+    fn product(&self, id: i64, p: &StoredProduct) -> Products {
+        Products {
+            id,
+            name_product: p.name.clone(),
+            price: p.price,
+            detail: p.detail.clone(),
+            images_path: Vec::new(),
+            stock: p.stock,
+            allow_backorder: p.allow_backorder,
+            reorder_threshold: p.reorder_threshold,
+            reorder_quantity: p.reorder_quantity,
+            sku: p.sku.clone(),
+            barcode: p.barcode.as_ref().map(|(_, code)| code.clone()),
+            barcode_format: p.barcode.as_ref().map(|(format, _)| *format),
+            create_at: p.created_at,
+            products_type_id: p.products_type_id,
+            products_type_name: p
+                .products_type_id
+                .and_then(|type_id| self.types.get(&type_id))
+                .map(|t| t.name.clone()),
+            variant_options: p.variant_options.clone(),
+            variant_summary: self.variant_summary(id, p),
+            variants: None,
+            deleted_at: p.deleted_at,
+            snippet: None,
+        }
+    }
+
+    fn product_variants(&self, product_id: i64) -> impl Iterator<Item = (i64, &StoredVariant)> {
+        self.variants
+            .iter()
+            .filter(move |(_, v)| v.product_id == product_id)
+            .map(|(id, v)| (*id, v))
+    }
+
+    // เหมือน subquery ของ variant ใน PRODUCT_COLUMNS
+    fn variant_summary(&self, product_id: i64, p: &StoredProduct) -> Option<VariantSummary> {
+        let prices: Vec<i64> = self
+            .product_variants(product_id)
+            .map(|(_, v)| v.price_minor.unwrap_or(p.price.amount))
+            .collect();
+        Some(VariantSummary {
+            count: prices.len() as i64,
+            min_price: Money::new(*prices.iter().min()?, p.price.currency),
+            max_price: Money::new(*prices.iter().max()?, p.price.currency),
+            total_stock: self
+                .product_variants(product_id)
+                .map(|(_, v)| v.stock)
+                .sum(),
+        })
+    }
+
+    // รูปของ variant ที่ยังเป็นรูปของสินค้าอยู่ (ลบรูปแล้วความเชื่อมโยงหายตาม เหมือน ON DELETE CASCADE)
+    fn variant_image_ids(&self, variant: &StoredVariant) -> Vec<i64> {
+        variant
+            .image_ids
+            .iter()
+            .filter(|image_id| {
+                self.images
+                    .get(image_id)
+                    .is_some_and(|image| image.product_id == Some(variant.product_id))
+            })
+            .copied()
+            .collect()
+    }
+
+    // สินค้าต้องไม่อยู่ในถังขยะ และ sku/ค่าตัวเลือกต้องไม่ซ้ำกับ variant อื่น เหมือน check_variant ฝั่ง SQL
+    fn check_variant(
+        &self,
+        product_id: i64,
+        variant_id: Option<i64>,
+        sku: Option<&str>,
+        options: Option<&BTreeMap<String, String>>,
+    ) -> RepoResult<()> {
+        if self
+            .products
+            .get(&product_id)
+            .is_none_or(|p| p.deleted_at.is_some())
+        {
+            return Err(RepoError::NotFound);
+        }
+        let others = || {
+            self.variants
+                .iter()
+                .filter(|(id, _)| Some(**id) != variant_id)
+                .map(|(_, v)| v)
+        };
+        if let Some(sku) = sku
+            && (others().any(|v| v.sku == sku)
+                || self
+                    .products
+                    .values()
+                    .any(|p| p.sku.as_deref() == Some(sku)))
+        {
+            return Err(RepoError::Conflict(format!("SKU {} is already used", sku)));
+        }
+        if let Some(options) = options
+            && others().any(|v| v.product_id == product_id && &v.options == options)
+        {
+            return Err(RepoError::Conflict(
+                "Another variant already has these options".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    // เหมือน check_product_codes ของ SqlRepository
+    fn check_product_codes(&self, id: i64, changes: &ProductChanges) -> RepoResult<()> {
+        let others = || {
+            self.products
+                .iter()
+                .filter(move |(product_id, _)| **product_id != id)
+                .map(|(_, p)| p)
+        };
+        if let Some(Some(sku)) = &changes.sku
+            && (others().any(|p| p.sku.as_ref() == Some(sku))
+                || self.variants.values().any(|v| &v.sku == sku))
+        {
+            return Err(RepoError::Conflict(format!("SKU {} is already used", sku)));
+        }
+        if let Some(Some((_, barcode))) = &changes.barcode {
+            let codes = equivalent_codes(barcode);
+            if others().any(|p| {
+                p.barcode
+                    .as_ref()
+                    .is_some_and(|(_, code)| codes.contains(code))
+            }) {
+                return Err(RepoError::Conflict(format!(
+                    "Barcode {} is already used",
+                    barcode
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    // ข้อความที่ค้นหาได้: ชื่อสินค้า ชื่อประเภท และค่าใน detail
+    fn search_fields(&self, product: &StoredProduct) -> [String; 3] {
+        let type_name = product
+            .products_type_id
+            .and_then(|type_id| self.types.get(&type_id))
+            .map(|t| t.name.clone())
+            .unwrap_or_default();
+        let mut detail = Vec::new();
+        json_values_text(&product.detail, &mut detail);
+        [product.name.clone(), type_name, detail.join(" ")]
+    }
+
+    // คะแนนความเกี่ยวข้อง น้ำหนักเหมือน bm25 ฝั่ง SQL: ชื่อสินค้า > ชื่อประเภท > detail
+    // None = มีคำค้นที่ไม่ตรงกับคำใดเลย (ไม่มีคำค้นได้ 0 ทุกชิ้น)
+    fn search_score(&self, product: &StoredProduct, terms: &[String]) -> Option<u32> {
+        if terms.is_empty() {
+            return Some(0);
+        }
+        let fields = self.search_fields(product);
+        terms.iter().try_fold(0, |score, term| {
+            let best = [10, 5, 1]
+                .iter()
+                .zip(&fields)
+                .filter(|(_, field)| field_matches(field, term))
+                .map(|(weight, _)| *weight)
+                .max()?;
+            Some(score + best)
+        })
+    }
+
+    fn search_snippet(&self, product: &StoredProduct, terms: &[String]) -> Option<String> {
+        if terms.is_empty() {
+            return None;
+        }
+        let fields = self.search_fields(product);
+        let field = fields
+            .iter()
+            .find(|field| terms.iter().any(|term| field_matches(field, term)))?;
+        Some(snippet_html(&snippet(field, terms)))
+    }
+
+    fn active_type(&self, id: i64) -> Option<&StoredType> {
+        self.types.get(&id).filter(|t| t.deleted_at.is_none())
+    }
+
+    // (threshold, quantity) ที่ใช้จริง: ค่าของสินค้า ถ้าไม่ได้ตั้งใช้ค่าของประเภท เหมือน REORDER_COLUMNS
+    fn reorder_point(&self, product: &StoredProduct) -> (Option<i64>, Option<i64>) {
+        let stored_type = product
+            .products_type_id
+            .and_then(|type_id| self.types.get(&type_id));
+        (
+            product
+                .reorder_threshold
+                .or(stored_type.and_then(|t| t.reorder_threshold)),
+            product
+                .reorder_quantity
+                .or(stored_type.and_then(|t| t.reorder_quantity)),
+        )
+    }
+
+    // สินค้าที่ไม่อยู่ในถังขยะและ stock <= จุดสั่งซื้อ คู่กับ stock - threshold ที่ใช้เรียง
+    fn low_stock(&self) -> Vec<(i64, LowStockProduct)> {
+        let mut found: Vec<(i64, LowStockProduct)> = self
+            .products
+            .iter()
+            .filter(|(_, p)| p.deleted_at.is_none())
+            .filter_map(|(id, p)| {
+                let (threshold, quantity) = self.reorder_point(p);
+                let threshold = threshold.filter(|threshold| p.stock <= *threshold)?;
+                let product = self.product(*id, p);
+                Some((
+                    p.stock - threshold,
+                    LowStockProduct {
+                        id: *id,
+                        name_product: product.name_product,
+                        stock: p.stock,
+                        reorder_threshold: threshold,
+                        reorder_quantity: quantity,
+                        products_type_id: product.products_type_id,
+                        products_type_name: product.products_type_name,
+                    },
+                ))
+            })
+            .collect();
+        found.sort_by_key(|(shortfall, product)| (*shortfall, product.id));
+        found
+    }
+
+    fn insert_product(
+        &mut self,
+        actor: &str,
+        product: &ProductInput,
+        image_paths: &[String],
+    ) -> i64 {
+        let stored = StoredProduct {
+            name: product.name.clone(),
+            price: product.price,
+            detail: product.detail.clone(),
+            stock: product.stock,
+            allow_backorder: false,
+            reorder_threshold: None,
+            reorder_quantity: None,
+            sku: None,
+            barcode: None,
+            created_at: now(),
+            products_type_id: product.products_type_id,
+            variant_options: Vec::new(),
+            deleted_at: None,
+        };
+        self.insert_stored_product(actor, stored, image_paths)
+    }
+
+    fn insert_stored_product(
+        &mut self,
+        actor: &str,
+        stored: StoredProduct,
+        image_paths: &[String],
+    ) -> i64 {
+        self.last_product_id += 1;
+        let id = self.last_product_id;
+        self.products.insert(id, stored);
+        for path in image_paths {
+            self.add_image(path, Some(id), None);
+        }
+
+        let after = self.product_snapshot(id);
+        self.record_audit(
+            actor,
+            AuditAction::Create,
+            AuditEntity::Product,
+            id,
+            None,
+            after,
+        );
+        id
+    }
+
+    fn insert_type(&mut self, actor: &str, name: &str, image_paths: &[String]) -> i64 {
+        self.last_type_id += 1;
+        let id = self.last_type_id;
+        self.types.insert(
+            id,
+            StoredType {
+                name: name.to_string(),
+                reorder_threshold: None,
+                reorder_quantity: None,
+                deleted_at: None,
+            },
+        );
+        for path in image_paths {
+            self.add_image(path, None, Some(id));
+        }
+
+        let after = self.product_type_snapshot(id);
+        self.record_audit(
+            actor,
+            AuditAction::Create,
+            AuditEntity::ProductType,
+            id,
+            None,
+            after,
+        );
+        id
+    }
+
+    fn add_image(
+        &mut self,
+        image_path: &str,
+        product_id: Option<i64>,
+        product_type_id: Option<i64>,
+    ) {
+        self.last_image_id += 1;
+        self.images.insert(
+            self.last_image_id,
+            StoredImage {
+                image_path: image_path.to_string(),
+                product_id,
+                product_type_id,
+                position: 0,
+            },
+        );
+    }
+
+    // images เรียงตาม id อยู่แล้ว sort แบบ stable ตาม position จึงได้ลำดับ (position, id) เหมือนฝั่ง SQL
+    fn image_paths(&self, keep: impl Fn(&StoredImage) -> bool) -> Vec<String> {
+        self.ordered_images(keep)
+            .into_iter()
+            .map(|(_, image)| image.image_path.clone())
+            .collect()
+    }
+
+    fn ordered_images(&self, keep: impl Fn(&StoredImage) -> bool) -> Vec<(i64, &StoredImage)> {
+        let mut images: Vec<(i64, &StoredImage)> = self
+            .images
+            .iter()
+            .filter(|(_, image)| keep(image))
+            .map(|(id, image)| (*id, image))
+            .collect();
+        images.sort_by_key(|(_, image)| image.position);
+        images
+    }
+
+    // snapshot ของสินค้าสำหรับ audit_log (รวมสินค้าในถังขยะ)
+    fn product_snapshot(&self, id: i64) -> Option<Value> {
+        let p = self.products.get(&id)?;
+        snapshot_json(ProductSnapshot {
+            id,
+            name_product: p.name.clone(),
+            price: p.price,
+            detail: p.detail.clone(),
+            stock: p.stock,
+            allow_backorder: p.allow_backorder,
+            reorder_threshold: p.reorder_threshold,
+            reorder_quantity: p.reorder_quantity,
+            sku: p.sku.clone(),
+            barcode: p.barcode.as_ref().map(|(_, code)| code.clone()),
+            barcode_format: p.barcode.as_ref().map(|(format, _)| *format),
+            products_type_id: p.products_type_id,
+            images_path: self.image_paths(|image| image.product_id == Some(id)),
+            variant_options: p.variant_options.clone(),
+            variants: self
+                .product_variants(id)
+                .map(|(variant_id, v)| VariantSnapshot {
+                    id: variant_id,
+                    sku: v.sku.clone(),
+                    options: v.options.clone(),
+                    price_minor: v.price_minor,
+                    stock: v.stock,
+                    image_ids: self.variant_image_ids(v),
+                })
+                .collect(),
+            deleted_at: p.deleted_at,
+        })
+    }
+
+    fn product_type_snapshot(&self, id: i64) -> Option<Value> {
+        let t = self.types.get(&id)?;
+        snapshot_json(ProductTypeSnapshot {
+            id,
+            name: t.name.clone(),
+            images_path: self.image_paths(|image| image.product_type_id == Some(id)),
+            reorder_threshold: t.reorder_threshold,
+            reorder_quantity: t.reorder_quantity,
+            deleted_at: t.deleted_at,
+        })
+    }
+
+    fn record_audit(
+        &mut self,
+        actor: &str,
+        action: AuditAction,
+        entity_type: AuditEntity,
+        entity_id: i64,
+        before: Option<Value>,
+        after: Option<Value>,
+    ) {
+        self.last_audit_id += 1;
+        self.audit.push(AuditEntry {
+            id: self.last_audit_id,
+            actor: actor.to_string(),
+            action,
+            entity_type,
+            entity_id,
+            before,
+            after,
+            created_at: now(),
+        });
+    }
+
+    // id ของสินค้าในประเภท (รวมสินค้าในถังขยะ)
+    fn product_ids_of_type(&self, type_id: i64) -> Vec<i64> {
+        self.products
+            .iter()
+            .filter(|(_, p)| p.products_type_id == Some(type_id))
+            .map(|(id, _)| *id)
+            .collect()
+    }
+
+    fn product_snapshots(&self, ids: &[i64]) -> Vec<(i64, Option<Value>)> {
+        ids.iter()
+            .map(|id| (*id, self.product_snapshot(*id)))
+            .collect()
+    }
+
+    fn record_product_changes(
+        &mut self,
+        actor: &str,
+        action: AuditAction,
+        befores: Vec<(i64, Option<Value>)>,
+    ) {
+        for (id, before) in befores {
+            let after = self.product_snapshot(id);
+            self.record_audit(actor, action, AuditEntity::Product, id, before, after);
+        }
+    }
+
+    fn record_stock_movement(
+        &mut self,
+        actor: &str,
+        product_id: i64,
+        adjustment: &StockAdjustment,
+        stock_after: i64,
+    ) -> StockMovement {
+        self.last_stock_movement_id += 1;
+        let movement = StockMovement {
+            id: self.last_stock_movement_id,
+            product_id,
+            quantity: adjustment.quantity,
+            reason: adjustment.reason,
+            note: adjustment.note.clone(),
+            stock_after,
+            actor: actor.to_string(),
+            created_at: now(),
+        };
+        self.stock_movements.push(movement.clone());
+        self.record_stock_alert(&movement);
+        movement
+    }
+
+    // เพิ่มรายการใน stock_alerts ถ้า movement ทำให้ stock ลดผ่านจุดสั่งซื้อ
+    fn record_stock_alert(&mut self, movement: &StockMovement) {
+        let Some(product) = self.products.get(&movement.product_id) else {
+            return;
+        };
+        let (Some(threshold), quantity) = self.reorder_point(product) else {
+            return;
+        };
+        let stock_before = movement.stock_after - movement.quantity;
+        if !crosses_reorder_threshold(stock_before, movement.stock_after, threshold) {
+            return;
+        }
+
+        self.last_stock_alert_id += 1;
+        self.stock_alerts.push(StockAlert {
+            id: self.last_stock_alert_id,
+            product_id: movement.product_id,
+            stock_movement_id: movement.id,
+            stock: movement.stock_after,
+            reorder_threshold: threshold,
+            reorder_quantity: quantity,
+            created_at: now(),
+            acknowledged_at: None,
+        });
+    }
+
+    // stock ที่ถูกเขียนทับ (PUT/PATCH) บันทึกเป็น correction ด้วยส่วนต่างจากค่าเดิม
+    fn record_stock_overwrite(&mut self, actor: &str, id: i64, before: i64) {
+        let Some(after) = self.products.get(&id).map(|p| p.stock) else {
+            return;
+        };
+        if before != after {
+            let adjustment = StockAdjustment {
+                quantity: after - before,
+                reason: StockReason::Correction,
+                note: None,
+            };
+            self.record_stock_movement(actor, id, &adjustment, after);
+        }
+    }
+
+    // ลบสินค้าพร้อมรูป ประวัติ stock และการแจ้งเตือน (เหมือน ON DELETE CASCADE)
+    fn remove_product(&mut self, id: i64) {
+        self.products.remove(&id);
+        self.images.retain(|_, image| image.product_id != Some(id));
+        self.stock_movements
+            .retain(|movement| movement.product_id != id);
+        self.stock_alerts.retain(|alert| alert.product_id != id);
+        self.variants.retain(|_, variant| variant.product_id != id);
+    }
+
+    // ลบประเภทพร้อมรูป main และตั้งสินค้าในประเภทเป็นไม่มีประเภท (เหมือน ON DELETE SET NULL)
+    fn remove_type(&mut self, id: i64) {
+        self.types.remove(&id);
+        self.images
+            .retain(|_, image| image.product_type_id != Some(id));
+        for product in self.products.values_mut() {
+            if product.products_type_id == Some(id) {
+                product.products_type_id = None;
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl ProductRepository for MemoryRepository {
+    async fn count(&self, filter: &ProductFilter) -> RepoResult<i64> {
+        let state = self.lock();
+        let terms = search_terms(&filter.search);
+        let count = state
+            .products
+            .values()
+            .filter(|p| state.matches(p, filter, &terms))
+            .count();
+        Ok(count as i64)
+    }
+
+    async fn list(
+        &self,
+        filter: &ProductFilter,
+        seek: &PageSeek,
+        limit: i64,
+    ) -> RepoResult<Vec<(Products, SortKey)>> {
+        let state = self.lock();
+        let terms = search_terms(&filter.search);
+        let mut found: Vec<(SortKey, &StoredProduct)> = state
+            .products
+            .iter()
+            .filter(|(_, p)| state.matches(p, filter, &terms))
+            .map(|(id, p)| {
+                let score = (!terms.is_empty()).then(|| state.search_score(p, &terms).unwrap_or(0));
+                let key = SortKey {
+                    value: product_sort_value(filter.sort, p, score),
+                    id: *id,
+                };
+                (key, p)
+            })
+            .collect();
+        found.sort_by(|(a, _), (b, _)| compare_keys(filter.sort, a, b));
+
+        let limit = limit.max(0) as usize;
+        let page: Vec<(SortKey, &StoredProduct)> = match seek {
+            PageSeek::Offset(offset) => found
+                .into_iter()
+                .skip((*offset).max(0) as usize)
+                .take(limit)
+                .collect(),
+            PageSeek::After(after) => found
+                .into_iter()
+                .filter(|(key, _)| compare_keys(filter.sort, key, after) == Ordering::Greater)
+                .take(limit)
+                .collect(),
+            PageSeek::Before(before) => {
+                let earlier: Vec<_> = found
+                    .into_iter()
+                    .filter(|(key, _)| compare_keys(filter.sort, key, before) == Ordering::Less)
+                    .collect();
+                let start = earlier.len().saturating_sub(limit);
+                earlier.into_iter().skip(start).collect()
+            }
+        };
+
+        Ok(page
+            .into_iter()
+            .map(|(key, p)| {
+                let product = Products {
+                    snippet: state.search_snippet(p, &terms),
+                    ..state.product(key.id, p)
+                };
+                (product, key)
+            })
+            .collect())
+    }
+
+    async fn facets(
+        &self,
+        filter: &ProductFilter,
+        currency: Currency,
+        price_bounds: &[i64],
+        detail_paths: &[Vec<String>],
+    ) -> RepoResult<ProductFacets> {
+        let state = self.lock();
+        let terms = search_terms(&filter.search);
+        let found: Vec<&StoredProduct> = state
+            .products
+            .values()
+            .filter(|p| state.matches(p, filter, &terms))
+            .collect();
+
+        let mut type_counts: BTreeMap<Option<i64>, i64> = BTreeMap::new();
+        for product in &found {
+            *type_counts.entry(product.products_type_id).or_default() += 1;
+        }
+        // None อยู่ก่อนใน BTreeMap และ sort แบบ stable จึงเรียงเหมือน ORDER BY ฝั่ง SQL
+        let mut types: Vec<TypeFacet> = type_counts
+            .into_iter()
+            .map(|(id, count)| TypeFacet {
+                id,
+                name: id
+                    .and_then(|id| state.types.get(&id))
+                    .map(|t| t.name.clone()),
+                count,
+            })
+            .collect();
+        types.sort_by_key(|facet| Reverse(facet.count));
+
+        let mut bucket_counts: BTreeMap<i64, i64> = BTreeMap::new();
+        for product in found.iter().filter(|p| p.price.currency == currency) {
+            let bucket = price_bounds
+                .iter()
+                .filter(|bound| product.price.amount >= **bound)
+                .count();
+            *bucket_counts.entry(bucket as i64).or_default() += 1;
+        }
+        let bucket_counts: Vec<(i64, i64)> = bucket_counts.into_iter().collect();
+
+        let in_stock = found.iter().filter(|p| p.stock > 0).count() as i64;
+
+        let detail = detail_paths
+            .iter()
+            .map(|path| {
+                let mut value_counts: BTreeMap<String, i64> = BTreeMap::new();
+                for product in &found {
+                    let mut texts: Vec<String> = detail_scalars(&product.detail, path)
+                        .into_iter()
+                        .map(|(text, _)| text)
+                        .collect();
+                    texts.sort();
+                    texts.dedup();
+                    for text in texts {
+                        *value_counts.entry(text).or_default() += 1;
+                    }
+                }
+                let mut values: Vec<ValueFacet> = value_counts
+                    .into_iter()
+                    .map(|(value, count)| ValueFacet { value, count })
+                    .collect();
+                values.sort_by_key(|facet| Reverse(facet.count));
+                values.truncate(FACET_VALUE_LIMIT as usize);
+                DetailFacet {
+                    key: path.join("."),
+                    values,
+                }
+            })
+            .collect();
+
+        Ok(ProductFacets {
+            total_items: found.len() as i64,
+            types,
+            price: price_buckets(currency, price_bounds, &bucket_counts),
+            stock: StockFacet {
+                in_stock,
+                out_of_stock: found.len() as i64 - in_stock,
+            },
+            detail,
+        })
+    }
+
+    async fn find(&self, id: i64) -> RepoResult<Option<Products>> {
+        let state = self.lock();
+        Ok(state
+            .products
+            .get(&id)
+            .filter(|p| p.deleted_at.is_none())
+            .map(|p| state.product(id, p)))
+    }
+
+    async fn find_id_by_barcode(&self, codes: &[String]) -> RepoResult<Option<i64>> {
+        let state = self.lock();
+        Ok(state
+            .products
+            .iter()
+            .find(|(_, p)| {
+                p.deleted_at.is_none()
+                    && p.barcode
+                        .as_ref()
+                        .is_some_and(|(_, code)| codes.contains(code))
+            })
+            .map(|(id, _)| *id))
+    }
+
+    async fn create(
+        &self,
+        actor: &str,
+        product: &ProductInput,
+        image_paths: &[String],
+    ) -> RepoResult<i64> {
+        Ok(self.lock().insert_product(actor, product, image_paths))
+    }
+
+    async fn duplicate(
+        &self,
+        actor: &str,
+        source_id: i64,
+        product: &ProductInput,
+        image_paths: &[String],
+    ) -> RepoResult<i64> {
+        let mut state = self.lock();
+        let source = state
+            .products
+            .get(&source_id)
+            .filter(|p| p.deleted_at.is_none())
+            .ok_or(RepoError::NotFound)?;
+        let stored = StoredProduct {
+            name: product.name.clone(),
+            price: product.price,
+            detail: product.detail.clone(),
+            stock: product.stock,
+            allow_backorder: source.allow_backorder,
+            reorder_threshold: source.reorder_threshold,
+            reorder_quantity: source.reorder_quantity,
+            sku: None,
+            barcode: None,
+            created_at: now(),
+            products_type_id: product.products_type_id,
+            variant_options: Vec::new(),
+            deleted_at: None,
+        };
+        Ok(state.insert_stored_product(actor, stored, image_paths))
+    }
+
+    async fn import(
+        &self,
+        actor: &str,
+        new_types: &[String],
+        products: &[ImportedProduct],
+    ) -> RepoResult<Vec<i64>> {
+        let mut state = self.lock();
+
+        // ตรวจทั้งหมดก่อนแก้ไข state เพื่อให้ไม่มีอะไรถูกเพิ่มเมื่อไม่สำเร็จ เหมือน rollback ฝั่ง SQL
+        for name in new_types {
+            match state.types.values().find(|t| &t.name == name) {
+                Some(t) if t.deleted_at.is_some() => {
+                    return Err(RepoError::Conflict(format!(
+                        "Product type {} is in trash",
+                        name
+                    )));
+                }
+                Some(_) => {
+                    return Err(RepoError::Conflict(format!(
+                        "Product type {} already exists",
+                        name
+                    )));
+                }
+                None => {}
+            }
+        }
+        for name in products.iter().filter_map(|p| p.type_name.as_ref()) {
+            let active = state
+                .types
+                .values()
+                .any(|t| t.deleted_at.is_none() && &t.name == name);
+            if !active && !new_types.contains(name) {
+                return Err(RepoError::Conflict(format!(
+                    "Unknown product type: {}",
+                    name
+                )));
+            }
+        }
+
+        for name in new_types {
+            state.insert_type(actor, name, &[]);
+        }
+        let mut product_ids = Vec::with_capacity(products.len());
+        for product in products {
+            let products_type_id = product.type_name.as_ref().and_then(|name| {
+                state
+                    .types
+                    .iter()
+                    .find(|(_, t)| t.deleted_at.is_none() && &t.name == name)
+                    .map(|(id, _)| *id)
+            });
+            let input = ProductInput {
+                name: product.name.clone(),
+                price: product.price,
+                detail: product.detail.clone(),
+                stock: product.stock,
+                products_type_id,
+            };
+            product_ids.push(state.insert_product(actor, &input, &product.image_paths));
+        }
+        Ok(product_ids)
+    }
+
+    async fn update(
+        &self,
+        actor: &str,
+        id: i64,
+        product: &ProductInput,
+        image_paths: &[String],
+    ) -> RepoResult<()> {
+        let mut state = self.lock();
+        let before = state.product_snapshot(id);
+        let stored = state
+            .products
+            .get_mut(&id)
+            .filter(|p| p.deleted_at.is_none())
+            .ok_or(RepoError::NotFound)?;
+        let stock_before = stored.stock;
+        stored.name = product.name.clone();
+        stored.price = product.price;
+        stored.detail = product.detail.clone();
+        stored.stock = product.stock;
+        stored.products_type_id = product.products_type_id;
+
+        state.images.retain(|_, image| image.product_id != Some(id));
+        for path in image_paths {
+            state.add_image(path, Some(id), None);
+        }
+
+        state.record_stock_overwrite(actor, id, stock_before);
+        state.record_product_changes(actor, AuditAction::Update, vec![(id, before)]);
+        Ok(())
+    }
+
+    async fn patch(&self, actor: &str, id: i64, changes: &ProductChanges) -> RepoResult<()> {
+        let mut state = self.lock();
+        let before = state.product_snapshot(id);
+        if state
+            .products
+            .get(&id)
+            .is_none_or(|p| p.deleted_at.is_some())
+        {
+            return Err(RepoError::NotFound);
+        }
+        state.check_product_codes(id, changes)?;
+        let stored = state
+            .products
+            .get_mut(&id)
+            .filter(|p| p.deleted_at.is_none())
+            .ok_or(RepoError::NotFound)?;
+        let stock_before = stored.stock;
+        if let Some(name) = &changes.name {
+            stored.name = name.clone();
+        }
+        if let Some(price) = changes.price {
+            stored.price = price;
+        }
+        if let Some(detail) = &changes.detail {
+            stored.detail = detail.clone();
+        }
+        if let Some(stock) = changes.stock {
+            stored.stock = stock;
+        }
+        if let Some(allow_backorder) = changes.allow_backorder {
+            stored.allow_backorder = allow_backorder;
+        }
+        if let Some(reorder_threshold) = changes.reorder_threshold {
+            stored.reorder_threshold = reorder_threshold;
+        }
+        if let Some(reorder_quantity) = changes.reorder_quantity {
+            stored.reorder_quantity = reorder_quantity;
+        }
+        if let Some(products_type_id) = changes.products_type_id {
+            stored.products_type_id = products_type_id;
+        }
+        if let Some(sku) = &changes.sku {
+            stored.sku = sku.clone();
+        }
+        if let Some(barcode) = &changes.barcode {
+            stored.barcode = barcode.clone();
+        }
+
+        if let Some(image_paths) = &changes.image_paths {
+            state.images.retain(|_, image| image.product_id != Some(id));
+            for path in image_paths {
+                state.add_image(path, Some(id), None);
+            }
+        }
+
+        state.record_stock_overwrite(actor, id, stock_before);
+        state.record_product_changes(actor, AuditAction::Update, vec![(id, before)]);
+        Ok(())
+    }
+
+    async fn update_images(&self, actor: &str, id: i64, images: &[ImageSlot]) -> RepoResult<()> {
+        let mut state = self.lock();
+        let before = state.product_snapshot(id);
+        if state
+            .products
+            .get(&id)
+            .is_none_or(|p| p.deleted_at.is_some())
+        {
+            return Err(RepoError::NotFound);
+        }
+
+        let current: Vec<i64> = state
+            .images
+            .iter()
+            .filter(|(_, image)| image.product_id == Some(id))
+            .map(|(image_id, _)| *image_id)
+            .collect();
+        let kept: Vec<i64> = images
+            .iter()
+            .filter_map(|slot| match slot {
+                ImageSlot::Existing(image_id) => Some(*image_id),
+                ImageSlot::New(_) => None,
+            })
+            .collect();
+        if kept.iter().any(|image_id| !current.contains(image_id)) {
+            return Err(RepoError::Conflict(
+                "Product images changed, reload and try again".to_string(),
+            ));
+        }
+
+        state
+            .images
+            .retain(|image_id, image| image.product_id != Some(id) || kept.contains(image_id));
+        for (position, slot) in images.iter().enumerate() {
+            let image_id = match slot {
+                ImageSlot::Existing(image_id) => *image_id,
+                ImageSlot::New(path) => {
+                    state.add_image(path, Some(id), None);
+                    state.last_image_id
+                }
+            };
+            if let Some(image) = state.images.get_mut(&image_id) {
+                image.position = position as i64;
+            }
+        }
+
+        state.record_product_changes(actor, AuditAction::Update, vec![(id, before)]);
+        Ok(())
+    }
+
+    async fn adjust_stock(
+        &self,
+        actor: &str,
+        id: i64,
+        adjustment: &StockAdjustment,
+    ) -> RepoResult<StockMovement> {
+        let mut state = self.lock();
+        let before = state.product_snapshot(id);
+        let stored = state
+            .products
+            .get_mut(&id)
+            .filter(|p| p.deleted_at.is_none())
+            .ok_or(RepoError::NotFound)?;
+        let stock_after = stored.stock + adjustment.quantity;
+        if adjustment.quantity <= 0 && !stored.allow_backorder && stock_after < 0 {
+            return Err(RepoError::Conflict(format!(
+                "Not enough stock ({} left)",
+                stored.stock
+            )));
+        }
+        stored.stock = stock_after;
+
+        let movement = state.record_stock_movement(actor, id, adjustment, stock_after);
+        state.record_product_changes(actor, AuditAction::Update, vec![(id, before)]);
+        Ok(movement)
+    }
+
+    async fn count_stock_movements(&self, id: i64, reason: Option<StockReason>) -> RepoResult<i64> {
+        let state = self.lock();
+        let count = state
+            .stock_movements
+            .iter()
+            .filter(|m| m.product_id == id && reason.is_none_or(|reason| m.reason == reason))
+            .count();
+        Ok(count as i64)
+    }
+
+    async fn stock_movements(
+        &self,
+        id: i64,
+        reason: Option<StockReason>,
+        limit: i64,
+        offset: i64,
+    ) -> RepoResult<Vec<StockMovement>> {
+        let state = self.lock();
+        Ok(state
+            .stock_movements
+            .iter()
+            .rev()
+            .filter(|m| m.product_id == id && reason.is_none_or(|reason| m.reason == reason))
+            .skip(offset.max(0) as usize)
+            .take(limit.max(0) as usize)
+            .cloned()
+            .collect())
+    }
+
+    async fn set_variant_options(
+        &self,
+        actor: &str,
+        id: i64,
+        options: &[VariantOption],
+    ) -> RepoResult<()> {
+        let mut state = self.lock();
+        let before = state.product_snapshot(id);
+        state.check_variant(id, None, None, None)?;
+        for (_, variant) in state.product_variants(id) {
+            if let Some(error) = variant_options_error(options, &variant.options) {
+                return Err(RepoError::Conflict(format!(
+                    "Variant {} does not fit the new options: {}",
+                    variant.sku, error
+                )));
+            }
+        }
+        if let Some(stored) = state.products.get_mut(&id) {
+            stored.variant_options = options.to_vec();
+        }
+
+        state.record_product_changes(actor, AuditAction::Update, vec![(id, before)]);
+        Ok(())
+    }
+
+    async fn variants(&self, id: i64) -> RepoResult<Vec<ProductVariant>> {
+        let state = self.lock();
+        let Some(product) = state.products.get(&id) else {
+            return Ok(Vec::new());
+        };
+        Ok(state
+            .product_variants(id)
+            .map(|(variant_id, v)| ProductVariant {
+                id: variant_id,
+                sku: v.sku.clone(),
+                options: v.options.clone(),
+                price: v
+                    .price_minor
+                    .map(|amount| Money::new(amount, product.price.currency)),
+                stock: v.stock,
+                images: state
+                    .variant_image_ids(v)
+                    .into_iter()
+                    .map(|image_id| ProductImage {
+                        id: image_id,
+                        image_path: state.images[&image_id].image_path.clone(),
+                    })
+                    .collect(),
+            })
+            .collect())
+    }
+
+    async fn create_variant(
+        &self,
+        actor: &str,
+        id: i64,
+        variant: &VariantInput,
+    ) -> RepoResult<i64> {
+        let mut state = self.lock();
+        let before = state.product_snapshot(id);
+        state.check_variant(id, None, Some(&variant.sku), Some(&variant.options))?;
+
+        state.last_variant_id += 1;
+        let variant_id = state.last_variant_id;
+        state.variants.insert(
+            variant_id,
+            StoredVariant {
+                product_id: id,
+                sku: variant.sku.clone(),
+                options: variant.options.clone(),
+                price_minor: variant.price_minor,
+                stock: variant.stock,
+                image_ids: variant.image_ids.clone(),
+            },
+        );
+
+        state.record_product_changes(actor, AuditAction::Update, vec![(id, before)]);
+        Ok(variant_id)
+    }
+
+    async fn patch_variant(
+        &self,
+        actor: &str,
+        id: i64,
+        variant_id: i64,
+        changes: &VariantChanges,
+    ) -> RepoResult<()> {
+        let mut state = self.lock();
+        let before = state.product_snapshot(id);
+        state.check_variant(
+            id,
+            Some(variant_id),
+            changes.sku.as_deref(),
+            changes.options.as_ref(),
+        )?;
+        let stored = state
+            .variants
+            .get_mut(&variant_id)
+            .filter(|v| v.product_id == id)
+            .ok_or(RepoError::NotFound)?;
+        if let Some(sku) = &changes.sku {
+            stored.sku = sku.clone();
+        }
+        if let Some(options) = &changes.options {
+            stored.options = options.clone();
+        }
+        if let Some(price_minor) = changes.price_minor {
+            stored.price_minor = price_minor;
+        }
+        if let Some(stock) = changes.stock {
+            stored.stock = stock;
+        }
+        if let Some(image_ids) = &changes.image_ids {
+            stored.image_ids = image_ids.clone();
+        }
+
+        state.record_product_changes(actor, AuditAction::Update, vec![(id, before)]);
+        Ok(())
+    }
+
+    async fn delete_variant(&self, actor: &str, id: i64, variant_id: i64) -> RepoResult<()> {
+        let mut state = self.lock();
+        let before = state.product_snapshot(id);
+        state.check_variant(id, None, None, None)?;
+        if state
+            .variants
+            .get(&variant_id)
+            .is_none_or(|v| v.product_id != id)
+        {
+            return Err(RepoError::NotFound);
+        }
+        state.variants.remove(&variant_id);
+
+        state.record_product_changes(actor, AuditAction::Update, vec![(id, before)]);
+        Ok(())
+    }
+
+    async fn count_low_stock(&self) -> RepoResult<i64> {
+        Ok(self.lock().low_stock().len() as i64)
+    }
+
+    async fn low_stock(&self, limit: i64, offset: i64) -> RepoResult<Vec<LowStockProduct>> {
+        Ok(self
+            .lock()
+            .low_stock()
+            .into_iter()
+            .skip(offset.max(0) as usize)
+            .take(limit.max(0) as usize)
+            .map(|(_, product)| product)
+            .collect())
+    }
+
+    async fn count_stock_alerts(&self, pending: Option<bool>) -> RepoResult<i64> {
+        let state = self.lock();
+        let count = state
+            .stock_alerts
+            .iter()
+            .filter(|a| pending.is_none_or(|pending| a.acknowledged_at.is_none() == pending))
+            .count();
+        Ok(count as i64)
+    }
+
+    async fn stock_alerts(
+        &self,
+        pending: Option<bool>,
+        limit: i64,
+        offset: i64,
+    ) -> RepoResult<Vec<StockAlert>> {
+        let state = self.lock();
+        Ok(state
+            .stock_alerts
+            .iter()
+            .filter(|a| pending.is_none_or(|pending| a.acknowledged_at.is_none() == pending))
+            .skip(offset.max(0) as usize)
+            .take(limit.max(0) as usize)
+            .cloned()
+            .collect())
+    }
+
+    async fn acknowledge_stock_alert(&self, id: i64) -> RepoResult<StockAlert> {
+        let mut state = self.lock();
+        let alert = state
+            .stock_alerts
+            .iter_mut()
+            .find(|a| a.id == id)
+            .ok_or(RepoError::NotFound)?;
+        alert.acknowledged_at.get_or_insert_with(now);
+        Ok(alert.clone())
+    }
+
+    async fn soft_delete(&self, actor: &str, id: i64) -> RepoResult<()> {
+        let mut state = self.lock();
+        let before = state.product_snapshot(id);
+        let stored = state
+            .products
+            .get_mut(&id)
+            .filter(|p| p.deleted_at.is_none())
+            .ok_or(RepoError::NotFound)?;
+        stored.deleted_at = Some(now());
+
+        state.record_product_changes(actor, AuditAction::Delete, vec![(id, before)]);
+        Ok(())
+    }
+
+    async fn restore(&self, actor: &str, id: i64) -> RepoResult<()> {
+        let mut state = self.lock();
+        let type_id = match state.products.get(&id) {
+            Some(p) if p.deleted_at.is_some() => p.products_type_id,
+            _ => return Err(RepoError::NotFound),
+        };
+
+        let type_trashed = type_id
+            .and_then(|type_id| state.types.get(&type_id))
+            .is_some_and(|t| t.deleted_at.is_some());
+        if type_trashed {
+            return Err(RepoError::Conflict(
+                "Product type is in the trash, restore it first".to_string(),
+            ));
+        }
+
+        let before = state.product_snapshot(id);
+        if let Some(stored) = state.products.get_mut(&id) {
+            stored.deleted_at = None;
+        }
+
+        state.record_product_changes(actor, AuditAction::Restore, vec![(id, before)]);
+        Ok(())
+    }
+
+    async fn purge(&self, actor: &str, id: i64) -> RepoResult<()> {
+        let mut state = self.lock();
+        if state
+            .products
+            .get(&id)
+            .is_none_or(|p| p.deleted_at.is_none())
+        {
+            return Err(RepoError::NotFound);
+        }
+
+        let before = state.product_snapshot(id);
+        state.remove_product(id);
+
+        state.record_product_changes(actor, AuditAction::Purge, vec![(id, before)]);
+        Ok(())
+    }
+
+    async fn trashed_before(&self, cutoff: NaiveDateTime) -> RepoResult<Vec<i64>> {
+        Ok(self
+            .lock()
+            .products
+            .iter()
+            .filter(|(_, p)| p.deleted_at.is_some_and(|at| at < cutoff))
+            .map(|(id, _)| *id)
+            .collect())
+    }
+}
+
+#[async_trait]
+impl ProductTypeRepository for MemoryRepository {
+    async fn count(&self, search: &str, trashed: bool) -> RepoResult<i64> {
+        let state = self.lock();
+        let count = state
+            .types
+            .values()
+            .filter(|t| t.deleted_at.is_some() == trashed)
+            .filter(|t| contains_ignore_case(&t.name, search))
+            .count();
+        Ok(count as i64)
+    }
+
+    async fn list(
+        &self,
+        search: &str,
+        trashed: bool,
+        sort: ListSort,
+        limit: i64,
+        offset: i64,
+    ) -> RepoResult<Vec<ProductType>> {
+        let state = self.lock();
+        let mut found: Vec<(&i64, &StoredType)> = state
+            .types
+            .iter()
+            .filter(|(_, t)| t.deleted_at.is_some() == trashed)
+            .filter(|(_, t)| contains_ignore_case(&t.name, search))
+            .collect();
+        found.sort_by(|(a_id, a), (b_id, b)| {
+            let ordering = match sort {
+                ListSort::NameAsc | ListSort::NameDesc => {
+                    a.name.to_lowercase().cmp(&b.name.to_lowercase())
+                }
+                _ => Ordering::Equal,
+            }
+            .then(a_id.cmp(b_id));
+            if sort.is_desc() {
+                ordering.reverse()
+            } else {
+                ordering
+            }
+        });
+
+        Ok(found
+            .into_iter()
+            .skip(offset.max(0) as usize)
+            .take(limit.max(0) as usize)
+            .map(|(id, t)| ProductType {
+                id: *id,
+                name: t.name.clone(),
+                images_path: Vec::new(),
+                reorder_threshold: t.reorder_threshold,
+                reorder_quantity: t.reorder_quantity,
+                deleted_at: t.deleted_at,
+            })
+            .collect())
+    }
+
+    async fn find_id_by_name(&self, name: &str) -> RepoResult<Option<i64>> {
+        Ok(self
+            .lock()
+            .types
+            .iter()
+            .find(|(_, t)| t.deleted_at.is_none() && t.name == name)
+            .map(|(id, _)| *id))
+    }
+
+    async fn create(&self, actor: &str, name: &str, image_paths: &[String]) -> RepoResult<i64> {
+        Ok(self.lock().insert_type(actor, name, image_paths))
+    }
+
+    async fn patch(&self, actor: &str, id: i64, changes: &ProductTypeChanges) -> RepoResult<()> {
+        let mut state = self.lock();
+        let before = state.product_type_snapshot(id);
+        let stored = state
+            .types
+            .get_mut(&id)
+            .filter(|t| t.deleted_at.is_none())
+            .ok_or(RepoError::NotFound)?;
+        if let Some(reorder_threshold) = changes.reorder_threshold {
+            stored.reorder_threshold = reorder_threshold;
+        }
+        if let Some(reorder_quantity) = changes.reorder_quantity {
+            stored.reorder_quantity = reorder_quantity;
+        }
+
+        let after = state.product_type_snapshot(id);
+        state.record_audit(
+            actor,
+            AuditAction::Update,
+            AuditEntity::ProductType,
+            id,
+            before,
+            after,
+        );
+        Ok(())
+    }
+
+    async fn soft_delete_with_products(&self, actor: &str, id: i64) -> RepoResult<()> {
+        let mut state = self.lock();
+        let deleted_at = now();
+        if state.active_type(id).is_none() {
+            return Err(RepoError::NotFound);
+        }
+
+        let before = state.product_type_snapshot(id);
+        let product_ids: Vec<i64> = state
+            .product_ids_of_type(id)
+            .into_iter()
+            .filter(|product_id| state.products[product_id].deleted_at.is_none())
+            .collect();
+        let product_befores = state.product_snapshots(&product_ids);
+
+        if let Some(stored) = state.types.get_mut(&id) {
+            stored.deleted_at = Some(deleted_at);
+        }
+        for product_id in &product_ids {
+            if let Some(product) = state.products.get_mut(product_id) {
+                product.deleted_at = Some(deleted_at);
+            }
+        }
+
+        let after = state.product_type_snapshot(id);
+        state.record_audit(
+            actor,
+            AuditAction::Delete,
+            AuditEntity::ProductType,
+            id,
+            before,
+            after,
+        );
+        state.record_product_changes(actor, AuditAction::Delete, product_befores);
+        Ok(())
+    }
+
+    async fn restore(&self, actor: &str, id: i64) -> RepoResult<()> {
+        let mut state = self.lock();
+        let deleted_at = state
+            .types
+            .get(&id)
+            .and_then(|t| t.deleted_at)
+            .ok_or(RepoError::NotFound)?;
+
+        // สินค้าที่ deleted_at ตรงกับของประเภทคือสินค้าที่ถูกลบไปพร้อมประเภท
+        let before = state.product_type_snapshot(id);
+        let product_ids: Vec<i64> = state
+            .product_ids_of_type(id)
+            .into_iter()
+            .filter(|product_id| state.products[product_id].deleted_at == Some(deleted_at))
+            .collect();
+        let product_befores = state.product_snapshots(&product_ids);
+
+        if let Some(stored) = state.types.get_mut(&id) {
+            stored.deleted_at = None;
+        }
+        for product_id in &product_ids {
+            if let Some(product) = state.products.get_mut(product_id) {
+                product.deleted_at = None;
+            }
+        }
+
+        let after = state.product_type_snapshot(id);
+        state.record_audit(
+            actor,
+            AuditAction::Restore,
+            AuditEntity::ProductType,
+            id,
+            before,
+            after,
+        );
+        state.record_product_changes(actor, AuditAction::Restore, product_befores);
+        Ok(())
+    }
+
+    async fn purge(&self, actor: &str, id: i64) -> RepoResult<()> {
+        let mut state = self.lock();
+        if state.types.get(&id).is_none_or(|t| t.deleted_at.is_none()) {
+            return Err(RepoError::NotFound);
+        }
+
+        let before = state.product_type_snapshot(id);
+        let product_ids = state.product_ids_of_type(id);
+        let product_befores = state.product_snapshots(&product_ids);
+
+        for product_id in &product_ids {
+            state.remove_product(*product_id);
+        }
+        state.remove_type(id);
+
+        state.record_audit(
+            actor,
+            AuditAction::Purge,
+            AuditEntity::ProductType,
+            id,
+            before,
+            None,
+        );
+        state.record_product_changes(actor, AuditAction::Purge, product_befores);
+        Ok(())
+    }
+
+    async fn trashed_before(&self, cutoff: NaiveDateTime) -> RepoResult<Vec<i64>> {
+        Ok(self
+            .lock()
+            .types
+            .iter()
+            .filter(|(_, t)| t.deleted_at.is_some_and(|at| at < cutoff))
+            .map(|(id, _)| *id)
+            .collect())
+    }
+
+    async fn soft_delete_keep_products(&self, actor: &str, id: i64) -> RepoResult<()> {
+        let mut state = self.lock();
+        if state.active_type(id).is_none() {
+            return Err(RepoError::NotFound);
+        }
+
+        let before = state.product_type_snapshot(id);
+        let product_ids = state.product_ids_of_type(id);
+        let product_befores = state.product_snapshots(&product_ids);
+
+        if let Some(stored) = state.types.get_mut(&id) {
+            stored.deleted_at = Some(now());
+        }
+        for product_id in &product_ids {
+            if let Some(product) = state.products.get_mut(product_id) {
+                product.products_type_id = None;
+            }
+        }
+
+        let after = state.product_type_snapshot(id);
+        state.record_audit(
+            actor,
+            AuditAction::Delete,
+            AuditEntity::ProductType,
+            id,
+            before,
+            after,
+        );
+        state.record_product_changes(actor, AuditAction::Update, product_befores);
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ImageRepository for MemoryRepository {
+    async fn paths_for_products(&self, product_ids: &[i64]) -> RepoResult<Vec<(i64, String)>> {
+        let state = self.lock();
+        let mut paths: Vec<(i64, String)> = state
+            .ordered_images(|image| image.product_id.is_some_and(|id| product_ids.contains(&id)))
+            .into_iter()
+            .filter_map(|(_, image)| Some((image.product_id?, image.image_path.clone())))
+            .collect();
+        // sort แบบ stable จึงคงลำดับรูปภายในสินค้าเดียวกัน
+        paths.sort_by_key(|(product_id, _)| *product_id);
+        Ok(paths)
+    }
+
+    async fn paths_for_types(&self, type_ids: &[i64]) -> RepoResult<Vec<(i64, String)>> {
+        let state = self.lock();
+        let mut paths: Vec<(i64, String)> = state
+            .images
+            .values()
+            .filter_map(|image| {
+                let type_id = image.product_type_id.filter(|id| type_ids.contains(id))?;
+                Some((type_id, image.image_path.clone()))
+            })
+            .collect();
+        paths.sort_by_key(|(type_id, _)| *type_id);
+        Ok(paths)
+    }
+
+    async fn paths_for_product(&self, product_id: i64) -> RepoResult<Vec<String>> {
+        Ok(self
+            .lock()
+            .image_paths(|image| image.product_id == Some(product_id)))
+    }
+
+    async fn images_for_product(&self, product_id: i64) -> RepoResult<Vec<ImageRecord>> {
+        Ok(self
+            .lock()
+            .ordered_images(|image| image.product_id == Some(product_id))
+            .into_iter()
+            .map(|(id, image)| ImageRecord {
+                id,
+                image_path: image.image_path.clone(),
+            })
+            .collect())
+    }
+
+    async fn paths_for_type(&self, type_id: i64) -> RepoResult<Vec<String>> {
+        Ok(self
+            .lock()
+            .images
+            .values()
+            .filter(|image| image.product_type_id == Some(type_id))
+            .map(|image| image.image_path.clone())
+            .collect())
+    }
+
+    async fn product_images_for_type(&self, type_id: i64) -> RepoResult<Vec<ImageRecord>> {
+        let state = self.lock();
+        Ok(state
+            .images
+            .iter()
+            .filter(|(_, image)| {
+                image
+                    .product_id
+                    .and_then(|id| state.products.get(&id))
+                    .is_some_and(|p| p.products_type_id == Some(type_id))
+            })
+            .map(|(id, image)| ImageRecord {
+                id: *id,
+                image_path: image.image_path.clone(),
+            })
+            .collect())
+    }
+}
+
+impl AuditFilter {
+    fn matches(&self, entry: &AuditEntry) -> bool {
+        self.actor
+            .as_ref()
+            .is_none_or(|actor| &entry.actor == actor)
+            && self.action.is_none_or(|action| entry.action == action)
+            && self
+                .entity_type
+                .is_none_or(|entity_type| entry.entity_type == entity_type)
+            && self.entity_id.is_none_or(|id| entry.entity_id == id)
+            && self.from.is_none_or(|from| entry.created_at >= from)
+            && self.to.is_none_or(|to| entry.created_at < to)
+    }
+}
+
+#[async_trait]
+impl AuditRepository for MemoryRepository {
+    async fn count(&self, filter: &AuditFilter) -> RepoResult<i64> {
+        let state = self.lock();
+        let count = state.audit.iter().filter(|e| filter.matches(e)).count();
+        Ok(count as i64)
+    }
+
+    async fn list(
+        &self,
+        filter: &AuditFilter,
+        limit: i64,
+        offset: i64,
+    ) -> RepoResult<Vec<AuditEntry>> {
+        let state = self.lock();
+        Ok(state
+            .audit
+            .iter()
+            .rev()
+            .filter(|e| filter.matches(e))
+            .skip(offset.max(0) as usize)
+            .take(limit.max(0) as usize)
+            .cloned()
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ACTOR: &str = "test";
+
+    fn input(name: &str, stock: i64, products_type_id: Option<i64>) -> ProductInput {
+        ProductInput {
+            name: name.to_string(),
+            price: Money::new(1000, Currency::Thb),
+            detail: Value::Null,
+            stock,
+            products_type_id,
+        }
+    }
+
+    fn paths(paths: &[&str]) -> Vec<String> {
+        paths.iter().map(|p| p.to_string()).collect()
+    }
+
+    fn sale(quantity: i64) -> StockAdjustment {
+        StockAdjustment {
+            quantity,
+            reason: StockReason::Sale,
+            note: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn create_update_and_delete_product() {
+        let repo = MemoryRepository::new();
+        let id = ProductRepository::create(
+            &repo,
+            ACTOR,
+            &input("tee", 5, None),
+            &paths(&["other/tee/tee_0.jpg"]),
+        )
+        .await
+        .unwrap();
+
+        let product = ProductRepository::find(&repo, id).await.unwrap().unwrap();
+        assert_eq!(product.name_product, "tee");
+        assert_eq!(product.stock, 5);
+        assert_eq!(
+            repo.paths_for_product(id).await.unwrap(),
+            ["other/tee/tee_0.jpg"]
+        );
+
+        repo.update(
+            ACTOR,
+            id,
+            &input("polo", 8, None),
+            &paths(&["other/polo/polo_0.jpg", "other/polo/polo_1.jpg"]),
+        )
+        .await
+        .unwrap();
+        let product = ProductRepository::find(&repo, id).await.unwrap().unwrap();
+        assert_eq!(product.name_product, "polo");
+        assert_eq!(product.stock, 8);
+        assert_eq!(
+            repo.paths_for_product(id).await.unwrap(),
+            ["other/polo/polo_0.jpg", "other/polo/polo_1.jpg"]
+        );
+        // stock ที่เขียนทับถูกบันทึกเป็น correction
+        let movements = repo.stock_movements(id, None, 10, 0).await.unwrap();
+        assert_eq!(movements.len(), 1);
+        assert_eq!(movements[0].quantity, 3);
+        assert_eq!(movements[0].reason, StockReason::Correction);
+
+        ProductRepository::soft_delete(&repo, ACTOR, id)
+            .await
+            .unwrap();
+        assert!(ProductRepository::find(&repo, id).await.unwrap().is_none());
+        assert!(matches!(
+            repo.update(ACTOR, id, &input("polo", 8, None), &[]).await,
+            Err(RepoError::NotFound)
+        ));
+        assert!(matches!(
+            ProductRepository::soft_delete(&repo, ACTOR, id).await,
+            Err(RepoError::NotFound)
+        ));
+    }
+
+    #[tokio::test]
+    async fn deleting_type_keeps_products_without_type() {
+        let repo = MemoryRepository::new();
+        let type_id = ProductTypeRepository::create(
+            &repo,
+            ACTOR,
+            "shirt",
+            &paths(&["shirt/main/main_0.jpg"]),
+        )
+        .await
+        .unwrap();
+        let id = ProductRepository::create(
+            &repo,
+            ACTOR,
+            &input("tee", 1, Some(type_id)),
+            &paths(&["shirt/tee/tee_0.jpg", "shared/tee.jpg"]),
+        )
+        .await
+        .unwrap();
+
+        repo.soft_delete_keep_products(ACTOR, type_id)
+            .await
+            .unwrap();
+        assert!(repo.find_id_by_name("shirt").await.unwrap().is_none());
+        assert_eq!(
+            ProductTypeRepository::count(&repo, "", true).await.unwrap(),
+            1
+        );
+        assert!(matches!(
+            repo.soft_delete_keep_products(ACTOR, type_id).await,
+            Err(RepoError::NotFound)
+        ));
+
+        // สินค้าไม่มีประเภทแล้ว แต่ path รูปเดิมไม่เปลี่ยน ส่วนรูป main ยังอยู่กับประเภทในถังขยะ
+        let product = ProductRepository::find(&repo, id).await.unwrap().unwrap();
+        assert_eq!(product.products_type_id, None);
+        assert_eq!(
+            repo.paths_for_product(id).await.unwrap(),
+            ["shirt/tee/tee_0.jpg", "shared/tee.jpg"]
+        );
+        assert_eq!(
+            repo.paths_for_type(type_id).await.unwrap(),
+            ["shirt/main/main_0.jpg"]
+        );
+
+        // purge ประเภทไม่ลบสินค้าที่แยกออกมาแล้ว
+        ProductTypeRepository::purge(&repo, ACTOR, type_id)
+            .await
+            .unwrap();
+        assert!(ProductRepository::find(&repo, id).await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn restore_and_purge_from_trash() {
+        let repo = MemoryRepository::new();
+        let type_id = ProductTypeRepository::create(&repo, ACTOR, "shirt", &[])
+            .await
+            .unwrap();
+        let id = ProductRepository::create(
+            &repo,
+            ACTOR,
+            &input("tee", 1, Some(type_id)),
+            &paths(&["shirt/tee/tee_0.jpg"]),
+        )
+        .await
+        .unwrap();
+
+        // ลบถาวรได้เฉพาะรายการในถังขยะ
+        assert!(matches!(
+            ProductRepository::purge(&repo, ACTOR, id).await,
+            Err(RepoError::NotFound)
+        ));
+        ProductRepository::soft_delete(&repo, ACTOR, id)
+            .await
+            .unwrap();
+        ProductRepository::restore(&repo, ACTOR, id).await.unwrap();
+        assert!(ProductRepository::find(&repo, id).await.unwrap().is_some());
+
+        // สินค้าที่ถูกลบพร้อมประเภทกู้คืนเดี่ยว ๆ ไม่ได้จนกว่าจะกู้คืนประเภท
+        repo.soft_delete_with_products(ACTOR, type_id)
+            .await
+            .unwrap();
+        assert!(ProductRepository::find(&repo, id).await.unwrap().is_none());
+        assert!(matches!(
+            ProductRepository::restore(&repo, ACTOR, id).await,
+            Err(RepoError::Conflict(_))
+        ));
+        ProductTypeRepository::restore(&repo, ACTOR, type_id)
+            .await
+            .unwrap();
+        assert!(ProductRepository::find(&repo, id).await.unwrap().is_some());
+
+        ProductRepository::soft_delete(&repo, ACTOR, id)
+            .await
+            .unwrap();
+        ProductRepository::purge(&repo, ACTOR, id).await.unwrap();
+        assert!(repo.paths_for_product(id).await.unwrap().is_empty());
+        assert!(matches!(
+            ProductRepository::restore(&repo, ACTOR, id).await,
+            Err(RepoError::NotFound)
+        ));
+
+        // ลบถาวรประเภทพร้อมสินค้าในประเภท
+        let other_id = ProductRepository::create(
+            &repo,
+            ACTOR,
+            &input("polo", 1, Some(type_id)),
+            &paths(&["shirt/polo/polo_0.jpg"]),
+        )
+        .await
+        .unwrap();
+        repo.soft_delete_with_products(ACTOR, type_id)
+            .await
+            .unwrap();
+        ProductTypeRepository::purge(&repo, ACTOR, type_id)
+            .await
+            .unwrap();
+        assert!(
+            ProductRepository::trashed_before(&repo, NaiveDateTime::MAX)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+        assert!(repo.paths_for_product(other_id).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn adjust_stock_rejects_negative_stock_without_backorder() {
+        let repo = MemoryRepository::new();
+        let id = ProductRepository::create(&repo, ACTOR, &input("tee", 2, None), &[])
+            .await
+            .unwrap();
+
+        let movement = repo.adjust_stock(ACTOR, id, &sale(-2)).await.unwrap();
+        assert_eq!(movement.stock_after, 0);
+        assert!(matches!(
+            repo.adjust_stock(ACTOR, id, &sale(-1)).await,
+            Err(RepoError::Conflict(_))
+        ));
+        // ไม่มีการเปลี่ยน stock หรือบันทึกรายการเมื่อไม่สำเร็จ
+        assert_eq!(
+            ProductRepository::find(&repo, id)
+                .await
+                .unwrap()
+                .unwrap()
+                .stock,
+            0
+        );
+        assert_eq!(repo.count_stock_movements(id, None).await.unwrap(), 1);
+
+        let changes = ProductChanges {
+            allow_backorder: Some(true),
+            ..Default::default()
+        };
+        ProductRepository::patch(&repo, ACTOR, id, &changes)
+            .await
+            .unwrap();
+        let movement = repo.adjust_stock(ACTOR, id, &sale(-1)).await.unwrap();
+        assert_eq!(movement.stock_after, -1);
+    }
+
+    fn sort_filter(sort: ListSort) -> ProductFilter {
+        ProductFilter {
+            search: String::new(),
+            type_id: TypeIdFilter::None,
+            trashed: false,
+            sort,
+            min_price: None,
+            max_price: None,
+            min_stock: None,
+            max_stock: None,
+            created_from: None,
+            created_to: None,
+            detail: Vec::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn price_cursor_pages_currencies_in_order() {
+        let repo = MemoryRepository::new();
+        for (name, price) in [
+            ("a", Money::new(500, Currency::Usd)),
+            ("b", Money::new(1000, Currency::Thb)),
+            ("c", Money::new(100, Currency::Usd)),
+            ("d", Money::new(100, Currency::Thb)),
+        ] {
+            let product = ProductInput {
+                price,
+                ..input(name, 1, None)
+            };
+            ProductRepository::create(&repo, ACTOR, &product, &[])
+                .await
+                .unwrap();
+        }
+
+        for (sort, expected) in [
+            (ListSort::PriceAsc, ["d", "b", "c", "a"]),
+            (ListSort::PriceDesc, ["a", "c", "b", "d"]),
+        ] {
+            let filter = sort_filter(sort);
+            let mut seek = PageSeek::Offset(0);
+            let mut names = Vec::new();
+            loop {
+                let page = ProductRepository::list(&repo, &filter, &seek, 1)
+                    .await
+                    .unwrap();
+                let Some((product, key)) = page.into_iter().next() else {
+                    break;
+                };
+                assert!(key.value.fits(sort));
+                names.push(product.name_product);
+                seek = PageSeek::After(key);
+            }
+            assert_eq!(names, expected);
+
+            // ย้อนกลับจากรายการสุดท้ายได้ลำดับเดิม
+            let (_, last) = ProductRepository::list(&repo, &filter, &PageSeek::Offset(3), 1)
+                .await
+                .unwrap()
+                .remove(0);
+            let before: Vec<String> =
+                ProductRepository::list(&repo, &filter, &PageSeek::Before(last), 10)
+                    .await
+                    .unwrap()
+                    .into_iter()
+                    .map(|(product, _)| product.name_product)
+                    .collect();
+            assert_eq!(before, expected[..3]);
+        }
+    }
+}
@@ -0,0 +1,717 @@
+pub mod memory;
+pub mod sql;
+
+use crate::models::{
+    AuditAction, AuditEntity, AuditEntry, BarcodeFormat, Currency, ListSort, LowStockProduct,
+    Money, PriceBucket, ProductFacets, ProductType, ProductVariant, Products, StockAdjustment,
+    StockAlert, StockMovement, StockReason, VariantOption,
+};
+use async_trait::async_trait;
+use chrono::{NaiveDateTime, SubsecRound};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::Arc;
+
+pub use memory::MemoryRepository;
+pub use sql::SqlRepository;
+
+// ชื่อประเภท/โฟลเดอร์รูปของสินค้าที่ไม่มีประเภท
+pub const OTHER_TYPE: &str = "other";
+
+#[derive(Debug)]
+pub enum RepoError {
+    NotFound,
+    // ทำไม่ได้เพราะสถานะของข้อมูลอื่น เช่น กู้คืนสินค้าที่ประเภทยังอยู่ในถังขยะ
+    Conflict(String),
+    Database(sqlx::Error),
+}
+
+impl fmt::Display for RepoError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RepoError::NotFound => write!(f, "record not found"),
+            RepoError::Conflict(reason) => write!(f, "{}", reason),
+            RepoError::Database(e) => write!(f, "database error: {}", e),
+        }
+    }
+}
+
+impl From<sqlx::Error> for RepoError {
+    fn from(e: sqlx::Error) -> Self {
+        RepoError::Database(e)
+    }
+}
+
+pub type RepoResult<T> = Result<T, RepoError>;
+
+// เวลาปัจจุบัน (UTC) ความละเอียดระดับวินาทีเหมือน CURRENT_TIMESTAMP ใช้เป็นค่า deleted_at
+pub fn now() -> NaiveDateTime {
+    chrono::Utc::now().naive_utc().trunc_subsecs(0)
+}
+
+pub enum TypeIdFilter {
+    None,
+    IsNull,
+    Equal(i64),
+}
+
+// การเทียบค่าตัวเลขใน detail
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    Greater,
+    GreaterOrEqual,
+    Less,
+    LessOrEqual,
+}
+
+impl Comparison {
+    pub fn sql(&self) -> &'static str {
+        match self {
+            Comparison::Greater => ">",
+            Comparison::GreaterOrEqual => ">=",
+            Comparison::Less => "<",
+            Comparison::LessOrEqual => "<=",
+        }
+    }
+
+    pub fn holds(&self, left: f64, right: f64) -> bool {
+        match self {
+            Comparison::Greater => left > right,
+            Comparison::GreaterOrEqual => left >= right,
+            Comparison::Less => left < right,
+            Comparison::LessOrEqual => left <= right,
+        }
+    }
+}
+
+pub enum DetailCondition {
+    // ข้อความตรงกันทุกตัวอักษร (ตัวเลขและ true/false เทียบในรูปข้อความ)
+    Equals(String),
+    // ค่าที่เป็นตัวเลข หรือข้อความที่เป็นตัวเลขตามรูปแบบ JSON เช่น "8" จากฟอร์ม
+    Compare(Comparison, f64),
+}
+
+// เงื่อนไขบนค่าใน detail ที่ path (ชื่อ key ตามลำดับความลึก ผ่านการตรวจแล้ว)
+// ถ้าค่าเป็น array จะผ่านเมื่อมีสมาชิกตัวใดตัวหนึ่งตรงเงื่อนไข
+pub struct DetailFilter {
+    pub path: Vec<String>,
+    pub condition: DetailCondition,
+}
+
+// ข้อความที่เป็นตัวเลขตามไวยากรณ์ของ JSON (ไม่รับ "08", "+1", ".5", "1.", "inf")
+pub fn json_number_text(text: &str) -> Option<f64> {
+    let text = text.trim_matches([' ', '\t', '\n', '\r']);
+    let rest = text.strip_prefix('-').unwrap_or(text);
+    let int_len = rest.bytes().take_while(u8::is_ascii_digit).count();
+    let (int, rest) = rest.split_at(int_len);
+    if int.is_empty() || (int.len() > 1 && int.starts_with('0')) {
+        return None;
+    }
+    let rest = match rest.strip_prefix('.') {
+        Some(fraction) => {
+            let digits = fraction.bytes().take_while(u8::is_ascii_digit).count();
+            if digits == 0 {
+                return None;
+            }
+            &fraction[digits..]
+        }
+        None => rest,
+    };
+    if let Some(exponent) = rest.strip_prefix(['e', 'E']) {
+        let exponent = exponent.strip_prefix(['+', '-']).unwrap_or(exponent);
+        if exponent.is_empty() || !exponent.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+    } else if !rest.is_empty() {
+        return None;
+    }
+    text.parse().ok()
+}
+
+// เงื่อนไขค้นหาสินค้าที่ใช้ร่วมกันระหว่างการนับจำนวนและการดึงข้อมูล
+pub struct ProductFilter {
+    // ค้นแบบ full-text ในชื่อสินค้า ชื่อประเภท และค่าใน detail เรียงตามความเกี่ยวข้อง
+    pub search: String,
+    pub type_id: TypeIdFilter,
+    // false = เฉพาะรายการปกติ, true = เฉพาะรายการในถังขยะ
+    pub trashed: bool,
+    // Relevance ต้องมีคำค้น ถ้าไม่มีจะเรียงตาม id
+    pub sort: ListSort,
+    // ช่วงราคารวมขอบ เทียบเฉพาะสินค้าในสกุลเงินเดียวกับขอบ (ถ้ามีทั้งสองขอบต้องเป็นสกุลเดียวกัน)
+    pub min_price: Option<Money>,
+    pub max_price: Option<Money>,
+    // ช่วง stock รวมขอบ
+    pub min_stock: Option<i64>,
+    pub max_stock: Option<i64>,
+    // ช่วงเวลา created_from <= created_at < created_to
+    pub created_from: Option<NaiveDateTime>,
+    pub created_to: Option<NaiveDateTime>,
+    // ทุกเงื่อนไขต้องผ่าน (AND)
+    pub detail: Vec<DetailFilter>,
+}
+
+// ค่าที่ใช้เรียงของรายการหนึ่ง ชนิดขึ้นกับ sort (เรียงตาม id อย่างเดียวไม่มีค่า)
+// ชื่อเป็นตัวพิมพ์เล็กตามที่ storage ใช้เรียง relevance คือคะแนนของ storage นั้น
+// ราคาเป็นคู่ (รหัสสกุลเงิน, หน่วยย่อย) เพราะหน่วยย่อยของคนละสกุลเงินเทียบกันไม่ได้
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, PartialOrd)]
+#[serde(rename_all = "snake_case")]
+pub enum SortValue {
+    None,
+    Int(i64),
+    Float(f64),
+    Text(String),
+    Time(NaiveDateTime),
+    Price(String, i64),
+}
+
+impl SortValue {
+    // ค่าจาก cursor ต้องเป็นชนิดเดียวกับที่ sort นั้นใช้
+    pub fn fits(&self, sort: ListSort) -> bool {
+        match sort {
+            ListSort::IdAsc | ListSort::IdDesc => matches!(self, SortValue::None),
+            ListSort::NameAsc | ListSort::NameDesc => matches!(self, SortValue::Text(_)),
+            ListSort::PriceAsc | ListSort::PriceDesc => matches!(self, SortValue::Price(_, _)),
+            ListSort::StockAsc | ListSort::StockDesc => matches!(self, SortValue::Int(_)),
+            ListSort::CreatedAtAsc | ListSort::CreatedAtDesc => matches!(self, SortValue::Time(_)),
+            // คะแนนเปลี่ยนได้เมื่อมีสินค้าเพิ่มหรือแก้ไข จึงไม่มี cursor (ใช้ page แทน)
+            ListSort::Relevance => false,
+        }
+    }
+}
+
+// ตำแหน่งของรายการในลำดับ: ค่าที่ใช้เรียงกับ id ที่ตัดสินเมื่อค่าเท่ากัน
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SortKey {
+    pub value: SortValue,
+    pub id: i64,
+}
+
+// หน้าที่ต้องการ: ข้ามไป offset รายการ หรือ keyset ต่อจาก/ก่อนหน้าตำแหน่งที่ระบุ (ไม่รวมตำแหน่งนั้น)
+pub enum PageSeek {
+    Offset(i64),
+    After(SortKey),
+    Before(SortKey),
+}
+
+pub const FACET_VALUE_LIMIT: i64 = 50;
+
+// ช่วงราคาทั้งหมดตามขอบ (รวมช่วงที่ไม่มีสินค้า) จากคู่ (เลขช่วง, จำนวน)
+pub fn price_buckets(
+    currency: Currency,
+    bounds: &[i64],
+    counts: &[(i64, i64)],
+) -> Vec<PriceBucket> {
+    (0..=bounds.len())
+        .map(|index| PriceBucket {
+            min: index
+                .checked_sub(1)
+                .map(|i| Money::new(bounds[i], currency)),
+            max: bounds.get(index).map(|bound| Money::new(*bound, currency)),
+            count: counts
+                .iter()
+                .find(|(bucket, _)| *bucket == index as i64)
+                .map_or(0, |(_, count)| *count),
+        })
+        .collect()
+}
+
+// แยกคำค้นด้วยช่องว่างและเครื่องหมาย ASCII ทุกคำต้องตรงแบบขึ้นต้นคำ (prefix)
+// เครื่องหมายถูกตัดทิ้งจึงไม่ต้อง escape ไวยากรณ์ของ FTS5 / tsquery
+pub fn search_terms(search: &str) -> Vec<String> {
+    search
+        .split(|c: char| c.is_whitespace() || c.is_ascii_punctuation())
+        .filter(|term| !term.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+// ตัวคั่นคำที่ตรงใน snippet ที่ได้จาก storage (ตัวอักษรควบคุม ไม่ปนกับข้อความของสินค้าตามปกติ)
+pub const MARK_START: char = '\u{2}';
+pub const MARK_END: char = '\u{3}';
+
+// snippet สำหรับตอบ client: escape ข้อความของสินค้าเป็น HTML แล้วเปลี่ยนตัวคั่นเป็น <mark></mark>
+// ข้อความจากชื่อหรือ detail จึงแสดงเป็นข้อความเสมอ ไม่กลายเป็น markup
+pub fn snippet_html(snippet: &str) -> String {
+    let mut html = String::with_capacity(snippet.len());
+    for c in snippet.chars() {
+        match c {
+            MARK_START => html.push_str("<mark>"),
+            MARK_END => html.push_str("</mark>"),
+            '&' => html.push_str("&amp;"),
+            '<' => html.push_str("&lt;"),
+            '>' => html.push_str("&gt;"),
+            '"' => html.push_str("&quot;"),
+            '\'' => html.push_str("&#39;"),
+            c => html.push(c),
+        }
+    }
+    html
+}
+
+// ข้อมูลสินค้าที่จะเขียนลงฐานข้อมูล (ไม่รวมรูปภาพ)
+pub struct ProductInput {
+    pub name: String,
+    pub price: Money,
+    pub detail: Value,
+    pub stock: i64,
+    pub products_type_id: Option<i64>,
+}
+
+// สินค้าหนึ่งแถวจากการ import อ้างประเภทด้วยชื่อ เพราะอาจเป็นประเภทที่สร้างใน transaction เดียวกัน
+pub struct ImportedProduct {
+    pub name: String,
+    pub price: Money,
+    pub detail: Value,
+    pub stock: i64,
+    pub type_name: Option<String>,
+    pub image_paths: Vec<String>,
+}
+
+// การแก้ไขบางส่วนของสินค้า None = ไม่แตะค่านั้น
+#[derive(Default)]
+pub struct ProductChanges {
+    pub name: Option<String>,
+    pub price: Option<Money>,
+    pub detail: Option<Value>,
+    pub stock: Option<i64>,
+    pub allow_backorder: Option<bool>,
+    // Some(None) = กลับไปใช้ค่าของประเภท
+    pub reorder_threshold: Option<Option<i64>>,
+    pub reorder_quantity: Option<Option<i64>>,
+    // Some(None) = เปลี่ยนเป็นสินค้าไม่มีประเภท
+    pub products_type_id: Option<Option<i64>>,
+    // Some = แทนที่รูปภาพทั้งหมดของสินค้า
+    pub image_paths: Option<Vec<String>>,
+    // Some(None) = ลบ sku/barcode barcode ต้องผ่าน barcode_error แล้ว
+    pub sku: Option<Option<String>>,
+    pub barcode: Option<Option<(BarcodeFormat, String)>>,
+}
+
+// การแก้ไขค่าเริ่มต้นของประเภท None = ไม่แตะค่านั้น Some(None) = ไม่มีค่าเริ่มต้น
+#[derive(Default)]
+pub struct ProductTypeChanges {
+    pub reorder_threshold: Option<Option<i64>>,
+    pub reorder_quantity: Option<Option<i64>>,
+}
+
+// variant ที่จะเขียนลงฐานข้อมูล price_minor เป็นหน่วยย่อยในสกุลเงินของสินค้า
+pub struct VariantInput {
+    pub sku: String,
+    pub options: BTreeMap<String, String>,
+    pub price_minor: Option<i64>,
+    pub stock: i64,
+    // id ของรูปของสินค้าตามลำดับที่แสดง
+    pub image_ids: Vec<i64>,
+}
+
+// การแก้ไขบางส่วนของ variant None = ไม่แตะค่านั้น
+#[derive(Default)]
+pub struct VariantChanges {
+    pub sku: Option<String>,
+    pub options: Option<BTreeMap<String, String>>,
+    // Some(None) = กลับไปใช้ราคาของสินค้า
+    pub price_minor: Option<Option<i64>>,
+    pub stock: Option<i64>,
+    pub image_ids: Option<Vec<i64>>,
+}
+
+// ค่าของ variant ต้องมีครบทุกแกนของสินค้า ไม่มีแกนอื่น และเป็นค่าที่แกนนั้นกำหนดไว้
+pub fn variant_options_error(
+    axes: &[VariantOption],
+    options: &BTreeMap<String, String>,
+) -> Option<String> {
+    if axes.is_empty() {
+        return Some("Product has no variant options".to_string());
+    }
+    for axis in axes {
+        match options.get(&axis.name) {
+            None => return Some(format!("Missing value for option {}", axis.name)),
+            Some(value) if !axis.values.contains(value) => {
+                return Some(format!("Invalid value for option {}: {}", axis.name, value));
+            }
+            Some(_) => {}
+        }
+    }
+    if let Some(name) = options
+        .keys()
+        .find(|name| !axes.iter().any(|axis| &axis.name == *name))
+    {
+        return Some(format!("Unknown option: {}", name));
+    }
+    None
+}
+
+// รูปหนึ่งตำแหน่งในลำดับใหม่ของสินค้า: รูปเดิม (id) หรือไฟล์ที่เพิ่งบันทึก (path)
+#[derive(PartialEq)]
+pub enum ImageSlot {
+    Existing(i64),
+    New(String),
+}
+
+pub struct ImageRecord {
+    pub id: i64,
+    pub image_path: String,
+}
+
+// snapshot ที่เก็บใน audit_log ทั้งสอง implementation ต้องสร้างจาก struct เดียวกันให้รูปแบบตรงกัน
+#[derive(Serialize)]
+pub struct ProductSnapshot {
+    pub id: i64,
+    pub name_product: String,
+    pub price: Money,
+    pub detail: Value,
+    pub stock: i64,
+    pub allow_backorder: bool,
+    pub reorder_threshold: Option<i64>,
+    pub reorder_quantity: Option<i64>,
+    pub sku: Option<String>,
+    pub barcode: Option<String>,
+    pub barcode_format: Option<BarcodeFormat>,
+    pub products_type_id: Option<i64>,
+    pub images_path: Vec<String>,
+    pub variant_options: Vec<VariantOption>,
+    pub variants: Vec<VariantSnapshot>,
+    pub deleted_at: Option<NaiveDateTime>,
+}
+
+#[derive(Serialize)]
+pub struct VariantSnapshot {
+    pub id: i64,
+    pub sku: String,
+    pub options: BTreeMap<String, String>,
+    pub price_minor: Option<i64>,
+    pub stock: i64,
+    pub image_ids: Vec<i64>,
+}
+
+#[derive(Serialize)]
+pub struct ProductTypeSnapshot {
+    pub id: i64,
+    pub name: String,
+    pub images_path: Vec<String>,
+    pub reorder_threshold: Option<i64>,
+    pub reorder_quantity: Option<i64>,
+    pub deleted_at: Option<NaiveDateTime>,
+}
+
+// การแจ้งเตือนเกิดเมื่อ stock ลดจากเหนือจุดสั่งซื้อลงมาถึงหรือต่ำกว่า ไม่ซ้ำจนกว่า stock จะกลับขึ้นไปเหนือจุดนั้น
+pub fn crosses_reorder_threshold(stock_before: i64, stock_after: i64, threshold: i64) -> bool {
+    stock_before > threshold && stock_after <= threshold
+}
+
+// None เมื่อ entity ไม่มีอยู่ (ก่อน create / หลัง purge)
+pub fn snapshot_json<T: Serialize>(snapshot: T) -> Option<Value> {
+    serde_json::to_value(snapshot).ok()
+}
+
+pub struct AuditFilter {
+    pub actor: Option<String>,
+    pub action: Option<AuditAction>,
+    pub entity_type: Option<AuditEntity>,
+    pub entity_id: Option<i64>,
+    // ช่วงเวลา from <= created_at < to
+    pub from: Option<NaiveDateTime>,
+    pub to: Option<NaiveDateTime>,
+}
+
+#[async_trait]
+pub trait ProductRepository: Send + Sync {
+    async fn count(&self, filter: &ProductFilter) -> RepoResult<i64>;
+
+    // รายการสินค้าเรียงตาม filter.sort พร้อมตำแหน่งของแต่ละรายการ (images_path ยังว่าง ให้ดึงผ่าน ImageRepository)
+    // PageSeek::Before ได้รายการที่อยู่ติดก่อนตำแหน่งนั้น แต่ยังเรียงตาม filter.sort
+    async fn list(
+        &self,
+        filter: &ProductFilter,
+        seek: &PageSeek,
+        limit: i64,
+    ) -> RepoResult<Vec<(Products, SortKey)>>;
+
+    // จำนวนสินค้าที่ผ่าน filter แยกตามประเภท ช่วงราคา สถานะ stock และค่าใน detail ของแต่ละ path
+    // ช่วงราคานับเฉพาะสินค้าในสกุล currency โดย price_bounds (หน่วยย่อย) เรียงจากน้อยไปมาก
+    // ค่าใน detail นับสินค้าละครั้งต่อค่า เรียงจำนวนมากก่อน ไม่เกิน FACET_VALUE_LIMIT ค่า
+    async fn facets(
+        &self,
+        filter: &ProductFilter,
+        currency: Currency,
+        price_bounds: &[i64],
+        detail_paths: &[Vec<String>],
+    ) -> RepoResult<ProductFacets>;
+
+    // สินค้าหนึ่งชิ้นที่ไม่อยู่ในถังขยะ (images_path ยังว่าง)
+    async fn find(&self, id: i64) -> RepoResult<Option<Products>>;
+
+    // id ของสินค้าที่ไม่อยู่ในถังขยะที่มี barcode เป็นค่าใดค่าหนึ่งใน codes
+    async fn find_id_by_barcode(&self, codes: &[String]) -> RepoResult<Option<i64>>;
+
+    // ทุกการแก้ไขบันทึก audit_log ของ actor ใน transaction เดียวกัน
+
+    // เพิ่มสินค้าพร้อมรูปภาพใน transaction เดียว คืนค่า id ใหม่
+    async fn create(
+        &self,
+        actor: &str,
+        product: &ProductInput,
+        image_paths: &[String],
+    ) -> RepoResult<i64>;
+
+    // เพิ่มสินค้าใหม่จาก product พร้อมรูป โดยคัดลอก allow_backorder และจุดสั่งซื้อจากสินค้า source_id
+    // ที่ไม่อยู่ในถังขยะ คืนค่า id ใหม่ ไม่คัดลอก sku, barcode และ variant เพราะต้องไม่ซ้ำ
+    // สำเนาจึงไม่มีแกนตัวเลือกของ variant ([]) ตั้งแกนและเพิ่ม variant ใหม่ได้ภายหลัง
+    async fn duplicate(
+        &self,
+        actor: &str,
+        source_id: i64,
+        product: &ProductInput,
+        image_paths: &[String],
+    ) -> RepoResult<i64>;
+
+    // เพิ่มประเภท new_types (ไม่มีรูป main) แล้วเพิ่มสินค้าทั้งหมดใน transaction เดียว คืน id ตามลำดับ
+    // Conflict ถ้าชื่อใน new_types มีอยู่แล้ว (รวมในถังขยะ) หรือสินค้าอ้างประเภทที่ไม่มี
+    async fn import(
+        &self,
+        actor: &str,
+        new_types: &[String],
+        products: &[ImportedProduct],
+    ) -> RepoResult<Vec<i64>>;
+
+    // เขียนทับทุกคอลัมน์ (ยกเว้น allow_backorder และจุดสั่งซื้อ) และแทนที่รูปภาพทั้งหมดของสินค้า
+    // stock ที่เปลี่ยนถูกบันทึกใน stock_movements เป็น correction เหมือนกับ patch
+    async fn update(
+        &self,
+        actor: &str,
+        id: i64,
+        product: &ProductInput,
+        image_paths: &[String],
+    ) -> RepoResult<()>;
+
+    // แก้ไขเฉพาะค่าที่ระบุของสินค้าที่ไม่อยู่ในถังขยะ รูปภาพไม่เปลี่ยนถ้าไม่ระบุ image_paths
+    // Conflict ถ้า sku ซ้ำกับสินค้าหรือ variant อื่น หรือ barcode (รวม equivalent_codes) ซ้ำกับสินค้าอื่น
+    // (นับสินค้าในถังขยะด้วย)
+    async fn patch(&self, actor: &str, id: i64, changes: &ProductChanges) -> RepoResult<()>;
+
+    // ตั้งรูปของสินค้าตามลำดับใน images รูปเดิมที่ไม่อยู่ในรายการถูกลบออกจากฐานข้อมูล
+    // Conflict ถ้า id ของรูปเดิมไม่ใช่รูปของสินค้านี้แล้ว (ถูกแก้ไขไปก่อน)
+    async fn update_images(&self, actor: &str, id: i64, images: &[ImageSlot]) -> RepoResult<()>;
+
+    // เพิ่มหรือลด stock ของสินค้าที่ไม่อยู่ในถังขยะจากค่าปัจจุบัน พร้อมบันทึก stock_movements ใน transaction เดียว
+    // Conflict ถ้าเป็นการลดที่ทำให้ stock ติดลบและสินค้าไม่ได้เปิด allow_backorder
+    async fn adjust_stock(
+        &self,
+        actor: &str,
+        id: i64,
+        adjustment: &StockAdjustment,
+    ) -> RepoResult<StockMovement>;
+
+    // ประวัติ stock ของสินค้า รายการล่าสุดก่อน (reason = None คือทุกเหตุผล)
+    async fn count_stock_movements(&self, id: i64, reason: Option<StockReason>) -> RepoResult<i64>;
+    async fn stock_movements(
+        &self,
+        id: i64,
+        reason: Option<StockReason>,
+        limit: i64,
+        offset: i64,
+    ) -> RepoResult<Vec<StockMovement>>;
+
+    // ตั้งแกนตัวเลือกของ variant ของสินค้าที่ไม่อยู่ในถังขยะ
+    // Conflict ถ้า variant ที่มีอยู่ใช้แกนหรือค่าที่ไม่อยู่ในแกนใหม่
+    async fn set_variant_options(
+        &self,
+        actor: &str,
+        id: i64,
+        options: &[VariantOption],
+    ) -> RepoResult<()>;
+
+    // variant ของสินค้าเรียงตาม id (path รูปยังไม่มี /images/ นำหน้า)
+    async fn variants(&self, id: i64) -> RepoResult<Vec<ProductVariant>>;
+
+    // การแก้ไข variant บันทึก audit_log เป็นการแก้ไขสินค้า
+    // Conflict ถ้า sku ซ้ำกับ variant อื่นหรือ sku ของสินค้า หรือสินค้ามี variant ที่มีค่าตัวเลือกเดียวกันแล้ว
+    async fn create_variant(&self, actor: &str, id: i64, variant: &VariantInput)
+    -> RepoResult<i64>;
+    async fn patch_variant(
+        &self,
+        actor: &str,
+        id: i64,
+        variant_id: i64,
+        changes: &VariantChanges,
+    ) -> RepoResult<()>;
+    async fn delete_variant(&self, actor: &str, id: i64, variant_id: i64) -> RepoResult<()>;
+
+    // ทุกการเปลี่ยน stock ที่บันทึกใน stock_movements เพิ่มรายการใน stock_alerts เมื่อ stock ลดผ่านจุดสั่งซื้อ
+    // (ของสินค้า หรือของประเภทถ้าสินค้าไม่ได้ตั้ง) ใน transaction เดียวกัน
+
+    // สินค้าที่ไม่อยู่ในถังขยะและ stock <= จุดสั่งซื้อ เรียงตามส่วนที่ขาดมากก่อน
+    async fn count_low_stock(&self) -> RepoResult<i64>;
+    async fn low_stock(&self, limit: i64, offset: i64) -> RepoResult<Vec<LowStockProduct>>;
+
+    // การแจ้งเตือนเก่าสุดก่อน pending: Some(true) = ยังไม่ acknowledge, Some(false) = acknowledge แล้ว
+    async fn count_stock_alerts(&self, pending: Option<bool>) -> RepoResult<i64>;
+    async fn stock_alerts(
+        &self,
+        pending: Option<bool>,
+        limit: i64,
+        offset: i64,
+    ) -> RepoResult<Vec<StockAlert>>;
+
+    // ตั้ง acknowledged_at ถ้ายังไม่ได้ตั้ง (เรียกซ้ำได้) คืนค่ารายการหลังแก้ไข
+    async fn acknowledge_stock_alert(&self, id: i64) -> RepoResult<StockAlert>;
+
+    // ย้ายสินค้าไปถังขยะ (ตั้ง deleted_at) ไม่แตะรูปภาพ
+    async fn soft_delete(&self, actor: &str, id: i64) -> RepoResult<()>;
+
+    // กู้คืนจากถังขยะ ถ้าประเภทของสินค้ายังอยู่ในถังขยะจะได้ Conflict
+    async fn restore(&self, actor: &str, id: i64) -> RepoResult<()>;
+
+    // ลบถาวรเฉพาะสินค้าที่อยู่ในถังขยะ (รูปภาพในฐานข้อมูลถูกลบตาม)
+    async fn purge(&self, actor: &str, id: i64) -> RepoResult<()>;
+
+    // id ของสินค้าที่อยู่ในถังขยะตั้งแต่ก่อน cutoff
+    async fn trashed_before(&self, cutoff: NaiveDateTime) -> RepoResult<Vec<i64>>;
+}
+
+#[async_trait]
+pub trait ProductTypeRepository: Send + Sync {
+    // trashed เหมือน ProductFilter::trashed
+    async fn count(&self, search: &str, trashed: bool) -> RepoResult<i64>;
+
+    // รายการประเภทเรียงตาม sort ที่ applies_to_types (images_path ยังว่าง ให้ดึงผ่าน ImageRepository)
+    async fn list(
+        &self,
+        search: &str,
+        trashed: bool,
+        sort: ListSort,
+        limit: i64,
+        offset: i64,
+    ) -> RepoResult<Vec<ProductType>>;
+
+    // ค้นหาเฉพาะประเภทที่ไม่อยู่ในถังขยะ
+    async fn find_id_by_name(&self, name: &str) -> RepoResult<Option<i64>>;
+
+    // เพิ่มประเภทพร้อมรูป main ใน transaction เดียว คืนค่า id ใหม่
+    async fn create(&self, actor: &str, name: &str, image_paths: &[String]) -> RepoResult<i64>;
+
+    // แก้ไขเฉพาะค่าที่ระบุของประเภทที่ไม่อยู่ในถังขยะ
+    async fn patch(&self, actor: &str, id: i64, changes: &ProductTypeChanges) -> RepoResult<()>;
+
+    // ย้ายประเภทพร้อมสินค้าทั้งหมดในประเภทไปถังขยะ ด้วย deleted_at เดียวกัน
+    async fn soft_delete_with_products(&self, actor: &str, id: i64) -> RepoResult<()>;
+
+    // กู้คืนประเภทพร้อมสินค้าที่ถูกย้ายไปถังขยะพร้อมกัน (สินค้าที่ลบแยกก่อนหน้ายังอยู่ในถังขยะ)
+    async fn restore(&self, actor: &str, id: i64) -> RepoResult<()>;
+
+    // ลบถาวรประเภทที่อยู่ในถังขยะพร้อมสินค้าทั้งหมดในประเภท
+    async fn purge(&self, actor: &str, id: i64) -> RepoResult<()>;
+
+    // id ของประเภทที่อยู่ในถังขยะตั้งแต่ก่อน cutoff
+    async fn trashed_before(&self, cutoff: NaiveDateTime) -> RepoResult<Vec<i64>>;
+
+    // ย้ายประเภทไปถังขยะแต่เก็บสินค้าไว้ สินค้าทุกชิ้นในประเภท (รวมที่อยู่ในถังขยะ) กลายเป็นสินค้าไม่มีประเภท
+    // ไม่ย้ายไฟล์รูป path ของรูปสินค้าเดิมยังใช้ได้ รูป main ของประเภทถูกลบตอน purge
+    async fn soft_delete_keep_products(&self, actor: &str, id: i64) -> RepoResult<()>;
+}
+
+#[async_trait]
+pub trait ImageRepository: Send + Sync {
+    // คู่ (product_id, image_path) ของสินค้าหลายชิ้น เรียงตามสินค้าแล้วตามลำดับรูป (position, id)
+    async fn paths_for_products(&self, product_ids: &[i64]) -> RepoResult<Vec<(i64, String)>>;
+
+    // คู่ (product_type_id, image_path) ของรูป main ของหลายประเภท
+    async fn paths_for_types(&self, type_ids: &[i64]) -> RepoResult<Vec<(i64, String)>>;
+
+    async fn paths_for_product(&self, product_id: i64) -> RepoResult<Vec<String>>;
+
+    // รูปของสินค้าพร้อม id ตามลำดับที่แสดง
+    async fn images_for_product(&self, product_id: i64) -> RepoResult<Vec<ImageRecord>>;
+
+    // รูป main ของประเภท
+    async fn paths_for_type(&self, type_id: i64) -> RepoResult<Vec<String>>;
+
+    // รูปของสินค้าทุกชิ้นที่อยู่ในประเภทนี้
+    async fn product_images_for_type(&self, type_id: i64) -> RepoResult<Vec<ImageRecord>>;
+}
+
+#[async_trait]
+pub trait AuditRepository: Send + Sync {
+    async fn count(&self, filter: &AuditFilter) -> RepoResult<i64>;
+
+    // รายการล่าสุดก่อน
+    async fn list(
+        &self,
+        filter: &AuditFilter,
+        limit: i64,
+        offset: i64,
+    ) -> RepoResult<Vec<AuditEntry>>;
+}
+
+// ชุด repository ที่ handler ใช้ ทุกตัวชี้ไปที่ storage เดียวกัน
+#[derive(Clone)]
+pub struct Repositories {
+    pub products: Arc<dyn ProductRepository>,
+    pub product_types: Arc<dyn ProductTypeRepository>,
+    pub images: Arc<dyn ImageRepository>,
+    pub audit: Arc<dyn AuditRepository>,
+}
+
+impl Repositories {
+    pub fn new<S>(store: S) -> Self
+    where
+        S: ProductRepository + ProductTypeRepository + ImageRepository + AuditRepository + 'static,
+    {
+        let store = Arc::new(store);
+        Repositories {
+            products: store.clone(),
+            product_types: store.clone(),
+            images: store.clone(),
+            audit: store,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snippet_html_escapes_text_and_marks_matches() {
+        let snippet = format!("{}<b>Tee</b>{} & \"friends\" 'x'", MARK_START, MARK_END);
+        assert_eq!(
+            snippet_html(&snippet),
+            "<mark>&lt;b&gt;Tee&lt;/b&gt;</mark> &amp; &quot;friends&quot; &#39;x&#39;"
+        );
+        assert_eq!(snippet_html("เสื้อ"), "เสื้อ");
+    }
+
+    #[test]
+    fn search_terms_split_on_punctuation() {
+        assert_eq!(search_terms("  Think-Pad  x1*"), ["think", "pad", "x1"]);
+        assert!(search_terms("\"()*").is_empty());
+    }
+
+    #[test]
+    fn json_number_text_follows_json_grammar() {
+        for (text, number) in [("8", 8.0), (" -1.5e2 ", -150.0), ("0.25", 0.25)] {
+            assert_eq!(json_number_text(text), Some(number));
+        }
+        for text in ["08", "+1", ".5", "1.", "inf", "1e", "", "-"] {
+            assert_eq!(json_number_text(text), None, "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn price_buckets_cover_every_range() {
+        let buckets = price_buckets(Currency::Thb, &[100, 500], &[(0, 2), (2, 1)]);
+        assert_eq!(
+            buckets
+                .iter()
+                .map(|b| (b.min.map(|m| m.amount), b.max.map(|m| m.amount), b.count))
+                .collect::<Vec<_>>(),
+            [
+                (None, Some(100), 2),
+                (Some(100), Some(500), 0),
+                (Some(500), None, 1)
+            ]
+        );
+    }
+}
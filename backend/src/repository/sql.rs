@@ -0,0 +1,3535 @@
+use super::{
+    AuditFilter, AuditRepository, DetailCondition, DetailFilter, FACET_VALUE_LIMIT, ImageRecord,
+    ImageRepository, ImageSlot, ImportedProduct, PageSeek, ProductChanges, ProductFilter,
+    ProductInput, ProductRepository, ProductSnapshot, ProductTypeChanges, ProductTypeRepository,
+    ProductTypeSnapshot, RepoError, RepoResult, SortKey, SortValue, TypeIdFilter, VariantChanges,
+    VariantInput, VariantSnapshot, crosses_reorder_threshold, now, price_buckets, search_terms,
+    snapshot_json, snippet_html, variant_options_error,
+};
+use crate::barcode::equivalent_codes;
+use crate::db::{BEGIN_WRITE, Db, DbPool, LIKE};
+use crate::models::{
+    AuditAction, AuditEntity, AuditEntry, BarcodeFormat, Currency, DetailFacet, ListSort,
+    LowStockProduct, Money, ProductFacets, ProductImage, ProductType, ProductVariant, Products,
+    StockAdjustment, StockAlert, StockFacet, StockMovement, StockReason, TypeFacet, ValueFacet,
+    VariantOption, VariantSummary,
+};
+use async_trait::async_trait;
+use chrono::NaiveDateTime;
+use serde_json::Value;
+use sqlx::types::Json;
+use sqlx::{QueryBuilder, Row};
+use std::collections::{BTreeMap, HashMap};
+
+type DbConnection = <Db as sqlx::Database>::Connection;
+
+// repository ที่เก็บข้อมูลในฐานข้อมูลจริง (SQLite หรือ Postgres ตาม feature ที่ build)
+pub struct SqlRepository {
+    pool: DbPool,
+}
+
+impl SqlRepository {
+    pub fn new(pool: DbPool) -> Self {
+        SqlRepository { pool }
+    }
+}
+
+// เงื่อนไขแยกรายการปกติกับรายการในถังขยะ
+fn trash_condition(column: &str, trashed: bool) -> String {
+    if trashed {
+        format!("{} IS NOT NULL", column)
+    } else {
+        format!("{} IS NULL", column)
+    }
+}
+
+// ค้นหาสินค้าด้วย FTS5 ผ่านตาราง products_fts (ดู migrations/sqlite/0008_product_search.sql)
+// คำค้นอยู่ในรูป "abc"* "def"* คือทุกคำต้องมีคำที่ขึ้นต้นด้วยคำค้น
+#[cfg(feature = "sqlite")]
+fn search_query(terms: &[String]) -> String {
+    terms
+        .iter()
+        .map(|term| format!("\"{}\"*", term))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(feature = "sqlite")]
+const SEARCH_JOIN: &str = " JOIN products_fts ON products_fts.rowid = p.id";
+
+#[cfg(feature = "sqlite")]
+fn push_search_match(builder: &mut QueryBuilder<'_, Db>, query: &str) {
+    builder
+        .push(" AND products_fts MATCH ")
+        .push_bind(query.to_string());
+}
+
+#[cfg(feature = "sqlite")]
+fn push_search_snippet(builder: &mut QueryBuilder<'_, Db>, _query: &str) {
+    // ตัวคั่น char(2)/char(3) คือ MARK_START/MARK_END แปลงเป็น HTML ใน snippet_html
+    builder.push(", snippet(products_fts, -1, char(2), char(3), '…', 10) AS snippet");
+}
+
+// ชื่อสินค้ามีน้ำหนักมากที่สุด ตามด้วยชื่อประเภทและ detail (bm25 ยิ่งน้อยยิ่งเกี่ยวข้อง)
+#[cfg(feature = "sqlite")]
+fn push_search_rank(builder: &mut QueryBuilder<'_, Db>, _query: &str) {
+    builder.push("bm25(products_fts, 10.0, 5.0, 1.0)");
+}
+
+#[cfg(feature = "sqlite")]
+const SEARCH_RANK_DESC: bool = false;
+
+// ค้นหาสินค้าด้วยคอลัมน์ tsvector products.search_vector (ดู migrations/postgres/0008_product_search.sql)
+// คำค้นอยู่ในรูป abc:* & def:*
+#[cfg(feature = "postgres")]
+fn search_query(terms: &[String]) -> String {
+    terms
+        .iter()
+        .map(|term| format!("{}:*", term))
+        .collect::<Vec<_>>()
+        .join(" & ")
+}
+
+#[cfg(feature = "postgres")]
+const SEARCH_JOIN: &str = "";
+
+#[cfg(feature = "postgres")]
+fn push_search_match(builder: &mut QueryBuilder<'_, Db>, query: &str) {
+    builder
+        .push(" AND p.search_vector @@ to_tsquery('simple', ")
+        .push_bind(query.to_string())
+        .push(")");
+}
+
+#[cfg(feature = "postgres")]
+fn push_search_snippet(builder: &mut QueryBuilder<'_, Db>, query: &str) {
+    builder
+        .push(
+            r#",
+            ts_headline(
+                'simple',
+                concat_ws(' ', p.name_products, pt.products_type_name, jsonb_values_text(p.detail)),
+                to_tsquery('simple', "#,
+        )
+        .push_bind(query.to_string())
+        .push(
+            r#"),
+                'StartSel=' || chr(2) || ', StopSel=' || chr(3) || ', MaxWords=10, MinWords=3'
+            ) AS snippet"#,
+        );
+}
+
+// ts_rank เป็น real แปลงเป็น double precision ให้ตรงกับค่าใน cursor
+#[cfg(feature = "postgres")]
+fn push_search_rank(builder: &mut QueryBuilder<'_, Db>, query: &str) {
+    builder
+        .push("CAST(ts_rank(p.search_vector, to_tsquery('simple', ")
+        .push_bind(query.to_string())
+        .push(")) AS DOUBLE PRECISION)");
+}
+
+#[cfg(feature = "postgres")]
+const SEARCH_RANK_DESC: bool = true;
+
+// ค่าใน detail ตาม path ทีละตัว (สมาชิกของ array หรือค่าเดี่ยว) เป็นตาราง detail_value ที่อ้างถึง p
+// path ส่งเป็น parameter และแต่ละ key ถูกครอบด้วย "" (key ผ่านการตรวจแล้วว่าไม่มี " หรือ \)
+#[cfg(feature = "sqlite")]
+fn push_detail_source(builder: &mut QueryBuilder<'_, Db>, path: &[String]) {
+    let path = path
+        .iter()
+        .fold("$".to_string(), |path, key| format!("{}.\"{}\"", path, key));
+    builder
+        .push("json_each(CASE WHEN json_valid(p.detail) THEN p.detail END, ")
+        .push_bind(path)
+        .push(") AS detail_value");
+}
+
+// ค่าเดี่ยวในรูปข้อความ (true/false เป็นคำ ไม่ใช่ 1/0)
+#[cfg(feature = "sqlite")]
+const DETAIL_TEXT: &str = r#"
+    CASE detail_value.type
+        WHEN 'true' THEN 'true'
+        WHEN 'false' THEN 'false'
+        ELSE CAST(detail_value.value AS TEXT)
+    END"#;
+
+// ตัวเลข หรือข้อความที่เป็นตัวเลขตามรูปแบบ JSON
+#[cfg(feature = "sqlite")]
+const DETAIL_IS_NUMBER: &str = r#"
+    (detail_value.type IN ('integer', 'real')
+        OR (detail_value.type = 'text'
+            AND CASE WHEN json_valid(detail_value.value)
+                THEN json_type(detail_value.value)
+            END IN ('integer', 'real')))"#;
+
+#[cfg(feature = "sqlite")]
+const DETAIL_IS_SCALAR: &str = "detail_value.type NOT IN ('object', 'array', 'null')";
+
+#[cfg(feature = "postgres")]
+fn push_detail_source(builder: &mut QueryBuilder<'_, Db>, path: &[String]) {
+    builder
+        .push("LATERAL jsonb_array_elements(CASE jsonb_typeof(p.detail #> ")
+        .push_bind(path.to_vec())
+        .push(") WHEN 'array' THEN p.detail #> ")
+        .push_bind(path.to_vec())
+        .push(" ELSE jsonb_build_array(p.detail #> ")
+        .push_bind(path.to_vec())
+        .push(") END) AS detail_value");
+}
+
+#[cfg(feature = "postgres")]
+const DETAIL_TEXT: &str = "(detail_value #>> '{}')";
+
+#[cfg(feature = "postgres")]
+const DETAIL_IS_NUMBER: &str = r#"
+    (jsonb_typeof(detail_value) = 'number'
+        OR (jsonb_typeof(detail_value) = 'string'
+            AND detail_value #>> '{}' ~ '^[ \t\n\r]*-?(0|[1-9][0-9]*)(\.[0-9]+)?([eE][+-]?[0-9]+)?[ \t\n\r]*$'))"#;
+
+#[cfg(feature = "postgres")]
+const DETAIL_IS_SCALAR: &str = "jsonb_typeof(detail_value) NOT IN ('object', 'array', 'null')";
+
+fn push_detail_filter(builder: &mut QueryBuilder<'_, Db>, filter: &DetailFilter) {
+    builder.push(" AND EXISTS (SELECT 1 FROM ");
+    push_detail_source(builder, &filter.path);
+    builder.push(" WHERE ").push(DETAIL_IS_SCALAR).push(" AND ");
+    match &filter.condition {
+        DetailCondition::Equals(text) => {
+            builder
+                .push(DETAIL_TEXT)
+                .push(" = ")
+                .push_bind(text.clone());
+        }
+        // CASE กันไม่ให้แปลงข้อความที่ไม่ใช่ตัวเลข
+        DetailCondition::Compare(comparison, number) => {
+            builder
+                .push(format!(
+                    "CASE WHEN {} THEN CAST({} AS DOUBLE PRECISION) END ",
+                    DETAIL_IS_NUMBER, DETAIL_TEXT
+                ))
+                .push(comparison.sql())
+                .push(" ")
+                .push_bind(*number);
+        }
+    }
+    builder.push(")");
+}
+
+// ORDER BY ที่ใช้ id ทิศทางเดียวกันตัดสินเมื่อค่าเท่ากัน ลำดับจึงคงที่ระหว่างหน้า
+fn order_by(sort_column: Option<&str>, id_column: &str, desc: bool) -> String {
+    let direction = if desc { "DESC" } else { "ASC" };
+    match sort_column {
+        Some(column) => format!(
+            " ORDER BY {} {}, {} {}",
+            column, direction, id_column, direction
+        ),
+        None => format!(" ORDER BY {} {}", id_column, direction),
+    }
+}
+
+// ชื่อเรียงแบบไม่สนตัวพิมพ์เล็กใหญ่ ราคาเรียงตามรหัสสกุลเงินก่อนแล้วจึงหน่วยย่อย (ไม่แปลงสกุลเงิน)
+fn product_sort_column(sort: ListSort) -> Option<&'static str> {
+    match sort {
+        ListSort::NameAsc | ListSort::NameDesc => Some("LOWER(p.name_products)"),
+        ListSort::PriceAsc | ListSort::PriceDesc => Some("p.price_minor"),
+        ListSort::CreatedAtAsc | ListSort::CreatedAtDesc => Some("p.created_at"),
+        ListSort::StockAsc | ListSort::StockDesc => Some("p.stock"),
+        ListSort::IdAsc | ListSort::IdDesc | ListSort::Relevance => None,
+    }
+}
+
+fn type_sort_column(sort: ListSort) -> Option<&'static str> {
+    match sort {
+        ListSort::NameAsc | ListSort::NameDesc => Some("LOWER(pt.products_type_name)"),
+        _ => None,
+    }
+}
+
+// สิ่งที่ใช้เรียงสินค้า ก่อน id ที่ตัดสินเมื่อค่าเท่ากัน
+enum ProductSortExpr<'a> {
+    Id,
+    Column(&'static str),
+    Rank(&'a str),
+}
+
+impl<'a> ProductSortExpr<'a> {
+    // relevance ที่ไม่มีคำค้นเรียงตาม id
+    fn new(sort: ListSort, search: Option<&'a str>) -> Self {
+        match (sort, search) {
+            (ListSort::Relevance, Some(query)) => ProductSortExpr::Rank(query),
+            _ => product_sort_column(sort)
+                .map(ProductSortExpr::Column)
+                .unwrap_or(ProductSortExpr::Id),
+        }
+    }
+
+    fn push(&self, builder: &mut QueryBuilder<'_, Db>) {
+        match self {
+            ProductSortExpr::Id => {
+                builder.push("p.id");
+            }
+            ProductSortExpr::Column(column) => {
+                builder.push(*column);
+            }
+            ProductSortExpr::Rank(query) => push_search_rank(builder, query),
+        }
+    }
+
+    // ทิศทางของค่าที่เรียงและของ id (relevance เรียง id จากน้อยไปมากเสมอ)
+    fn directions(&self, sort: ListSort) -> (bool, bool) {
+        match self {
+            ProductSortExpr::Rank(_) => (SEARCH_RANK_DESC, false),
+            _ => (sort.is_desc(), sort.is_desc()),
+        }
+    }
+
+    fn value_from_row(&self, row: &<Db as sqlx::Database>::Row, sort: ListSort) -> SortValue {
+        match (self, sort) {
+            (ProductSortExpr::Id, _) => SortValue::None,
+            (ProductSortExpr::Rank(_), _) => SortValue::Float(row.get("sort_key")),
+            (_, ListSort::PriceAsc | ListSort::PriceDesc) => {
+                SortValue::Price(row.get("currency"), row.get("sort_key"))
+            }
+            (_, ListSort::NameAsc | ListSort::NameDesc) => SortValue::Text(row.get("sort_key")),
+            (_, ListSort::CreatedAtAsc | ListSort::CreatedAtDesc) => {
+                SortValue::Time(row.get("sort_key"))
+            }
+            _ => SortValue::Int(row.get("sort_key")),
+        }
+    }
+}
+
+fn push_sort_value(builder: &mut QueryBuilder<'_, Db>, value: &SortValue) {
+    match value {
+        SortValue::None => builder.push("NULL"),
+        SortValue::Int(value) => builder.push_bind(*value),
+        SortValue::Float(value) => builder.push_bind(*value),
+        SortValue::Text(value) => builder.push_bind(value.clone()),
+        SortValue::Time(value) => builder.push_bind(*value),
+        SortValue::Price(_, amount) => builder.push_bind(*amount),
+    };
+}
+
+// คอลัมน์ที่เรียงตามลำดับ: สกุลเงิน (เฉพาะราคา), ค่าที่เรียง แล้วจึง id
+#[derive(Clone, Copy)]
+enum SeekColumn {
+    Currency,
+    Value,
+    Id,
+}
+
+impl SeekColumn {
+    fn push(self, builder: &mut QueryBuilder<'_, Db>, expr: &ProductSortExpr) {
+        match self {
+            SeekColumn::Currency => {
+                builder.push("p.currency");
+            }
+            SeekColumn::Value => expr.push(builder),
+            SeekColumn::Id => {
+                builder.push("p.id");
+            }
+        }
+    }
+}
+
+// (คอลัมน์, ทิศทาง desc) ที่ใช้ทั้งใน ORDER BY และเงื่อนไข keyset
+fn seek_columns(
+    expr: &ProductSortExpr,
+    sort: ListSort,
+    (value_desc, id_desc): (bool, bool),
+) -> Vec<(SeekColumn, bool)> {
+    match expr {
+        ProductSortExpr::Id => vec![(SeekColumn::Id, id_desc)],
+        _ if matches!(sort, ListSort::PriceAsc | ListSort::PriceDesc) => vec![
+            (SeekColumn::Currency, value_desc),
+            (SeekColumn::Value, value_desc),
+            (SeekColumn::Id, id_desc),
+        ],
+        _ => vec![(SeekColumn::Value, value_desc), (SeekColumn::Id, id_desc)],
+    }
+}
+
+// เงื่อนไข keyset: รายการที่อยู่หลัง key ตามลำดับ (before = อยู่ก่อน key)
+// เทียบทีละคอลัมน์: c1 > v1 OR (c1 = v1 AND (c2 > v2 OR (c2 = v2 AND ...)))
+fn push_seek_condition(
+    builder: &mut QueryBuilder<'_, Db>,
+    expr: &ProductSortExpr,
+    columns: &[(SeekColumn, bool)],
+    key: &SortKey,
+    before: bool,
+) {
+    let operator = |desc: bool| if desc != before { " < " } else { " > " };
+    let push_value = |builder: &mut QueryBuilder<'_, Db>, column: SeekColumn| match column {
+        SeekColumn::Currency => {
+            let code = match &key.value {
+                SortValue::Price(code, _) => code.clone(),
+                _ => String::new(),
+            };
+            builder.push_bind(code);
+        }
+        SeekColumn::Value => push_sort_value(builder, &key.value),
+        SeekColumn::Id => {
+            builder.push_bind(key.id);
+        }
+    };
+
+    builder.push(" AND ");
+    for (index, (column, desc)) in columns.iter().enumerate() {
+        builder.push("(");
+        column.push(builder, expr);
+        builder.push(operator(*desc));
+        push_value(builder, *column);
+        if index + 1 < columns.len() {
+            builder.push(" OR (");
+            column.push(builder, expr);
+            builder.push(" = ");
+            push_value(builder, *column);
+            builder.push(" AND ");
+        }
+    }
+    for index in 0..columns.len() {
+        builder.push(if index + 1 < columns.len() { "))" } else { ")" });
+    }
+}
+
+// ORDER BY ของสินค้า reverse = กลับทิศทั้งหมด (ใช้ตอนดึงหน้าก่อนหน้า)
+fn push_product_order(
+    builder: &mut QueryBuilder<'_, Db>,
+    expr: &ProductSortExpr,
+    columns: &[(SeekColumn, bool)],
+    reverse: bool,
+) {
+    let direction = |desc: bool| if desc != reverse { " DESC" } else { " ASC" };
+    builder.push(" ORDER BY ");
+    for (index, (column, desc)) in columns.iter().enumerate() {
+        if index > 0 {
+            builder.push(", ");
+        }
+        column.push(builder, expr);
+        builder.push(direction(*desc));
+    }
+}
+
+// คำค้นในรูปแบบของฐานข้อมูล None = ไม่ได้ค้นหา
+fn product_search_query(filter: &ProductFilter) -> Option<String> {
+    let terms = search_terms(&filter.search);
+    (!terms.is_empty()).then(|| search_query(&terms))
+}
+
+// เงื่อนไข WHERE ที่ใช้ร่วมกันระหว่าง query นับจำนวนและ query ดึงข้อมูล
+fn push_product_filters(
+    builder: &mut QueryBuilder<'_, Db>,
+    filter: &ProductFilter,
+    search: Option<&str>,
+) {
+    builder
+        .push(" WHERE ")
+        .push(trash_condition("p.deleted_at", filter.trashed));
+
+    if let Some(query) = search {
+        push_search_match(builder, query);
+    }
+
+    // เพิ่มเงื่อนไขกรองตาม type_id ถ้ามีการระบุ
+    match filter.type_id {
+        TypeIdFilter::IsNull => {
+            builder.push(" AND p.products_type_id IS NULL");
+        }
+        TypeIdFilter::Equal(id) => {
+            builder.push(" AND p.products_type_id = ").push_bind(id);
+        }
+        TypeIdFilter::None => {}
+    }
+
+    for (bound, operator) in [(filter.min_price, " >= "), (filter.max_price, " <= ")] {
+        if let Some(price) = bound {
+            builder
+                .push(" AND p.currency = ")
+                .push_bind(price.currency.code())
+                .push(" AND p.price_minor")
+                .push(operator)
+                .push_bind(price.amount);
+        }
+    }
+    if let Some(min_stock) = filter.min_stock {
+        builder.push(" AND p.stock >= ").push_bind(min_stock);
+    }
+    if let Some(max_stock) = filter.max_stock {
+        builder.push(" AND p.stock <= ").push_bind(max_stock);
+    }
+    if let Some(from) = filter.created_from {
+        builder.push(" AND p.created_at >= ").push_bind(from);
+    }
+    if let Some(to) = filter.created_to {
+        builder.push(" AND p.created_at < ").push_bind(to);
+    }
+    for detail in &filter.detail {
+        push_detail_filter(builder, detail);
+    }
+}
+
+// คอลัมน์ของ Products (ตามด้วย snippet) และตารางที่ใช้ร่วมกันระหว่าง list และ find
+const PRODUCT_COLUMNS: &str = r#"
+    SELECT
+        p.id,
+        p.name_products,
+        p.price_minor,
+        p.currency,
+        p.detail,
+        p.stock,
+        p.allow_backorder,
+        p.reorder_threshold,
+        p.reorder_quantity,
+        p.sku,
+        p.barcode,
+        p.barcode_format,
+        p.created_at,
+        p.products_type_id,
+        pt.products_type_name,
+        p.variant_options,
+        (SELECT COUNT(*) FROM product_variants v WHERE v.product_id = p.id) AS variant_count,
+        (SELECT MIN(COALESCE(v.price_minor, p.price_minor))
+         FROM product_variants v WHERE v.product_id = p.id) AS variant_min_price,
+        (SELECT MAX(COALESCE(v.price_minor, p.price_minor))
+         FROM product_variants v WHERE v.product_id = p.id) AS variant_max_price,
+        (SELECT CAST(SUM(v.stock) AS BIGINT)
+         FROM product_variants v WHERE v.product_id = p.id) AS variant_stock,
+        p.deleted_at
+    "#;
+
+const PRODUCT_FROM: &str = r#"
+    FROM
+        products p
+    LEFT JOIN
+        products_type pt ON p.products_type_id = pt.id
+    "#;
+
+fn product_from_row(row: &<Db as sqlx::Database>::Row) -> Products {
+    let currency: Currency = row.get::<String, _>("currency").parse().unwrap_or_default();
+    let variant_count: i64 = row.get("variant_count");
+    let variant_summary = (variant_count > 0).then(|| VariantSummary {
+        count: variant_count,
+        min_price: Money::new(
+            row.get::<Option<i64>, _>("variant_min_price")
+                .unwrap_or_default(),
+            currency,
+        ),
+        max_price: Money::new(
+            row.get::<Option<i64>, _>("variant_max_price")
+                .unwrap_or_default(),
+            currency,
+        ),
+        total_stock: row
+            .get::<Option<i64>, _>("variant_stock")
+            .unwrap_or_default(),
+    });
+    Products {
+        id: row.get("id"),
+        name_product: row.get("name_products"),
+        price: Money::new(row.get("price_minor"), currency),
+        detail: row.get("detail"),
+        images_path: Vec::new(),
+        stock: row.get("stock"),
+        allow_backorder: row.get("allow_backorder"),
+        reorder_threshold: row.get("reorder_threshold"),
+        reorder_quantity: row.get("reorder_quantity"),
+        sku: row.get("sku"),
+        barcode: row.get("barcode"),
+        barcode_format: barcode_format_from_row(row),
+        create_at: row.get("created_at"),
+        products_type_id: row.get("products_type_id"),
+        products_type_name: row.get("products_type_name"),
+        variant_options: row.get::<Json<Vec<VariantOption>>, _>("variant_options").0,
+        variant_summary,
+        variants: None,
+        deleted_at: row.get("deleted_at"),
+        snippet: row
+            .get::<Option<String>, _>("snippet")
+            .map(|snippet| snippet_html(&snippet)),
+    }
+}
+
+fn barcode_format_from_row(row: &<Db as sqlx::Database>::Row) -> Option<BarcodeFormat> {
+    row.get::<Option<String>, _>("barcode_format")
+        .and_then(|format| format.parse().ok())
+}
+
+// คู่ (owner_id, image_path) ของรูปที่ column = หนึ่งใน ids
+async fn image_paths_in(
+    pool: &DbPool,
+    column: &str,
+    ids: &[i64],
+) -> RepoResult<Vec<(i64, String)>> {
+    if ids.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut query = QueryBuilder::<Db>::new(format!(
+        "SELECT {col}, image_path FROM images WHERE {col} IN (",
+        col = column
+    ));
+    let mut separated = query.separated(", ");
+    for id in ids {
+        separated.push_bind(*id);
+    }
+    query.push(format!(") ORDER BY {}, position, id", column));
+
+    let rows = query.build().fetch_all(pool).await?;
+    Ok(rows
+        .iter()
+        .map(|row| (row.get(column), row.get("image_path")))
+        .collect())
+}
+
+// snapshot ของสินค้าสำหรับ audit_log (รวมสินค้าในถังขยะ)
+async fn product_snapshot(conn: &mut DbConnection, id: i64) -> RepoResult<Option<Value>> {
+    let row = sqlx::query(
+        r#"
+        SELECT id, name_products, price_minor, currency, detail, stock, allow_backorder,
+               reorder_threshold, reorder_quantity, sku, barcode, barcode_format, products_type_id,
+               variant_options, deleted_at
+        FROM products
+        WHERE id = $1
+        "#,
+    )
+    .bind(id)
+    .fetch_optional(&mut *conn)
+    .await?;
+
+    let Some(row) = row else {
+        return Ok(None);
+    };
+
+    let images_path = sqlx::query_scalar::<_, String>(
+        "SELECT image_path FROM images WHERE product_id = $1 ORDER BY position, id",
+    )
+    .bind(id)
+    .fetch_all(&mut *conn)
+    .await?;
+    let variants = variant_snapshots(conn, id).await?;
+
+    Ok(snapshot_json(ProductSnapshot {
+        id: row.get("id"),
+        name_product: row.get("name_products"),
+        price: Money::new(
+            row.get("price_minor"),
+            row.get::<String, _>("currency").parse().unwrap_or_default(),
+        ),
+        detail: row.get("detail"),
+        stock: row.get("stock"),
+        allow_backorder: row.get("allow_backorder"),
+        reorder_threshold: row.get("reorder_threshold"),
+        reorder_quantity: row.get("reorder_quantity"),
+        sku: row.get("sku"),
+        barcode: row.get("barcode"),
+        barcode_format: barcode_format_from_row(&row),
+        products_type_id: row.get("products_type_id"),
+        images_path,
+        variant_options: row.get::<Json<Vec<VariantOption>>, _>("variant_options").0,
+        variants,
+        deleted_at: row.get("deleted_at"),
+    }))
+}
+
+// id ของรูปของแต่ละ variant ของสินค้า ตามลำดับที่แสดง
+async fn variant_image_ids(
+    conn: &mut DbConnection,
+    product_id: i64,
+) -> RepoResult<Vec<(i64, i64)>> {
+    let rows = sqlx::query(
+        r#"
+        SELECT vi.variant_id, vi.image_id
+        FROM product_variant_images vi
+        JOIN product_variants v ON v.id = vi.variant_id
+        WHERE v.product_id = $1
+        ORDER BY vi.position
+        "#,
+    )
+    .bind(product_id)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    Ok(rows
+        .iter()
+        .map(|row| (row.get("variant_id"), row.get("image_id")))
+        .collect())
+}
+
+async fn variant_snapshots(
+    conn: &mut DbConnection,
+    product_id: i64,
+) -> RepoResult<Vec<VariantSnapshot>> {
+    let rows = sqlx::query(
+        "SELECT id, sku, options, price_minor, stock FROM product_variants WHERE product_id = $1 ORDER BY id",
+    )
+    .bind(product_id)
+    .fetch_all(&mut *conn)
+    .await?;
+    let image_ids = variant_image_ids(conn, product_id).await?;
+
+    Ok(rows
+        .iter()
+        .map(|row| {
+            let id: i64 = row.get("id");
+            VariantSnapshot {
+                id,
+                sku: row.get("sku"),
+                options: row.get::<Json<BTreeMap<String, String>>, _>("options").0,
+                price_minor: row.get("price_minor"),
+                stock: row.get("stock"),
+                image_ids: image_ids
+                    .iter()
+                    .filter(|(variant_id, _)| *variant_id == id)
+                    .map(|(_, image_id)| *image_id)
+                    .collect(),
+            }
+        })
+        .collect())
+}
+
+// สินค้าต้องไม่อยู่ในถังขยะ sku ต้องไม่ซ้ำกับ variant อื่น (ยกเว้น variant_id ที่กำลังแก้) หรือ sku ของสินค้า
+// และค่าตัวเลือกต้องไม่ซ้ำกับ variant อื่นของสินค้าเดียวกัน
+async fn check_variant(
+    conn: &mut DbConnection,
+    product_id: i64,
+    variant_id: Option<i64>,
+    sku: Option<&str>,
+    options: Option<&BTreeMap<String, String>>,
+) -> RepoResult<()> {
+    let active = sqlx::query_scalar::<_, i64>(
+        "SELECT COUNT(*) FROM products WHERE id = $1 AND deleted_at IS NULL",
+    )
+    .bind(product_id)
+    .fetch_one(&mut *conn)
+    .await?;
+    if active == 0 {
+        return Err(RepoError::NotFound);
+    }
+
+    if let Some(sku) = sku {
+        let used = sqlx::query_scalar::<_, i64>(
+            r#"
+            SELECT (SELECT COUNT(*) FROM product_variants WHERE sku = $1 AND id <> $2)
+                 + (SELECT COUNT(*) FROM products WHERE sku = $1)
+            "#,
+        )
+        .bind(sku)
+        .bind(variant_id.unwrap_or(0))
+        .fetch_one(&mut *conn)
+        .await?;
+        if used > 0 {
+            return Err(RepoError::Conflict(format!("SKU {} is already used", sku)));
+        }
+    }
+
+    if let Some(options) = options {
+        let used = sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*) FROM product_variants WHERE product_id = $1 AND options = $2 AND id <> $3",
+        )
+        .bind(product_id)
+        .bind(Json(options))
+        .bind(variant_id.unwrap_or(0))
+        .fetch_one(&mut *conn)
+        .await?;
+        if used > 0 {
+            return Err(RepoError::Conflict(
+                "Another variant already has these options".to_string(),
+            ));
+        }
+    }
+    Ok(())
+}
+
+// sku/barcode ใหม่ของสินค้าต้องไม่ถูกใช้แล้ว (รวมสินค้าในถังขยะ)
+async fn check_product_codes(
+    conn: &mut DbConnection,
+    id: i64,
+    changes: &ProductChanges,
+) -> RepoResult<()> {
+    if let Some(Some(sku)) = &changes.sku {
+        let used = sqlx::query_scalar::<_, i64>(
+            r#"
+            SELECT (SELECT COUNT(*) FROM products WHERE sku = $1 AND id <> $2)
+                 + (SELECT COUNT(*) FROM product_variants WHERE sku = $1)
+            "#,
+        )
+        .bind(sku)
+        .bind(id)
+        .fetch_one(&mut *conn)
+        .await?;
+        if used > 0 {
+            return Err(RepoError::Conflict(format!("SKU {} is already used", sku)));
+        }
+    }
+
+    if let Some(Some((_, barcode))) = &changes.barcode {
+        let mut query = QueryBuilder::<Db>::new("SELECT COUNT(*) FROM products WHERE id <> ");
+        query.push_bind(id).push(" AND barcode IN (");
+        let mut separated = query.separated(", ");
+        for code in equivalent_codes(barcode) {
+            separated.push_bind(code);
+        }
+        query.push(")");
+        let used: i64 = query.build_query_scalar().fetch_one(&mut *conn).await?;
+        if used > 0 {
+            return Err(RepoError::Conflict(format!(
+                "Barcode {} is already used",
+                barcode
+            )));
+        }
+    }
+    Ok(())
+}
+
+// unique index ของ sku และค่าตัวเลือกกันกรณีเขียนพร้อมกันที่ผ่าน check_variant มาได้ทั้งคู่
+fn product_write_error(e: sqlx::Error) -> RepoError {
+    match &e {
+        sqlx::Error::Database(db) if db.is_unique_violation() => {
+            RepoError::Conflict("SKU or barcode is already used by another product".to_string())
+        }
+        _ => RepoError::Database(e),
+    }
+}
+
+fn variant_write_error(e: sqlx::Error) -> RepoError {
+    match &e {
+        sqlx::Error::Database(db) if db.is_unique_violation() => {
+            RepoError::Conflict("SKU or options are already used by another variant".to_string())
+        }
+        _ => RepoError::Database(e),
+    }
+}
+
+async fn set_variant_images(
+    conn: &mut DbConnection,
+    variant_id: i64,
+    image_ids: &[i64],
+) -> RepoResult<()> {
+    sqlx::query("DELETE FROM product_variant_images WHERE variant_id = $1")
+        .bind(variant_id)
+        .execute(&mut *conn)
+        .await?;
+
+    for (position, image_id) in image_ids.iter().enumerate() {
+        sqlx::query(
+            "INSERT INTO product_variant_images (variant_id, image_id, position) VALUES ($1, $2, $3)",
+        )
+        .bind(variant_id)
+        .bind(image_id)
+        .bind(position as i64)
+        .execute(&mut *conn)
+        .await?;
+    }
+    Ok(())
+}
+
+async fn product_type_snapshot(conn: &mut DbConnection, id: i64) -> RepoResult<Option<Value>> {
+    let row = sqlx::query(
+        r#"
+        SELECT id, products_type_name, reorder_threshold, reorder_quantity, deleted_at
+        FROM products_type
+        WHERE id = $1
+        "#,
+    )
+    .bind(id)
+    .fetch_optional(&mut *conn)
+    .await?;
+
+    let Some(row) = row else {
+        return Ok(None);
+    };
+
+    let images_path = sqlx::query_scalar::<_, String>(
+        "SELECT image_path FROM images WHERE product_type_id = $1 ORDER BY id",
+    )
+    .bind(id)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    Ok(snapshot_json(ProductTypeSnapshot {
+        id: row.get("id"),
+        name: row.get("products_type_name"),
+        images_path,
+        reorder_threshold: row.get("reorder_threshold"),
+        reorder_quantity: row.get("reorder_quantity"),
+        deleted_at: row.get("deleted_at"),
+    }))
+}
+
+// เพิ่มสินค้าพร้อมรูปและบันทึก audit_log ใน transaction ของผู้เรียก
+async fn insert_product(
+    conn: &mut DbConnection,
+    actor: &str,
+    product: &ProductInput,
+    image_paths: &[String],
+) -> RepoResult<i64> {
+    let product_id = sqlx::query_scalar::<_, i64>(
+        "INSERT INTO products (name_products, price_minor, currency, detail, stock, products_type_id)
+         VALUES ($1, $2, $3, $4, $5, $6)
+         RETURNING id",
+    )
+    .bind(&product.name)
+    .bind(product.price.amount)
+    .bind(product.price.currency.code())
+    .bind(&product.detail)
+    .bind(product.stock)
+    .bind(product.products_type_id)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    record_new_product(conn, actor, product_id, image_paths).await?;
+    Ok(product_id)
+}
+
+// รูปและ audit_log ของสินค้าที่เพิ่งเพิ่ม
+async fn record_new_product(
+    conn: &mut DbConnection,
+    actor: &str,
+    product_id: i64,
+    image_paths: &[String],
+) -> RepoResult<()> {
+    for path in image_paths {
+        sqlx::query("INSERT INTO images (image_path, product_id) VALUES ($1, $2)")
+            .bind(path)
+            .bind(product_id)
+            .execute(&mut *conn)
+            .await?;
+    }
+
+    let after = product_snapshot(conn, product_id).await?;
+    record_audit(
+        conn,
+        actor,
+        AuditAction::Create,
+        AuditEntity::Product,
+        product_id,
+        None,
+        after,
+    )
+    .await
+}
+
+async fn insert_product_type(
+    conn: &mut DbConnection,
+    actor: &str,
+    name: &str,
+    image_paths: &[String],
+) -> RepoResult<i64> {
+    let type_id = sqlx::query_scalar::<_, i64>(
+        "INSERT INTO products_type (products_type_name) VALUES ($1) RETURNING id",
+    )
+    .bind(name)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    for path in image_paths {
+        sqlx::query("INSERT INTO images (image_path, product_type_id) VALUES ($1, $2)")
+            .bind(path)
+            .bind(type_id)
+            .execute(&mut *conn)
+            .await?;
+    }
+
+    let after = product_type_snapshot(conn, type_id).await?;
+    record_audit(
+        conn,
+        actor,
+        AuditAction::Create,
+        AuditEntity::ProductType,
+        type_id,
+        None,
+        after,
+    )
+    .await?;
+    Ok(type_id)
+}
+
+async fn record_audit(
+    conn: &mut DbConnection,
+    actor: &str,
+    action: AuditAction,
+    entity_type: AuditEntity,
+    entity_id: i64,
+    before: Option<Value>,
+    after: Option<Value>,
+) -> RepoResult<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO audit_log (actor, action, entity_type, entity_id, before_data, after_data, created_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        "#,
+    )
+    .bind(actor)
+    .bind(action.as_str())
+    .bind(entity_type.as_str())
+    .bind(entity_id)
+    .bind(before)
+    .bind(after)
+    .bind(now())
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
+
+// snapshot ก่อนแก้ไขของสินค้าหลายชิ้น ใช้คู่กับ record_product_changes หลังแก้ไขเสร็จ
+async fn product_snapshots(
+    conn: &mut DbConnection,
+    ids: &[i64],
+) -> RepoResult<Vec<(i64, Option<Value>)>> {
+    let mut snapshots = Vec::with_capacity(ids.len());
+    for id in ids {
+        snapshots.push((*id, product_snapshot(&mut *conn, *id).await?));
+    }
+    Ok(snapshots)
+}
+
+async fn record_product_changes(
+    conn: &mut DbConnection,
+    actor: &str,
+    action: AuditAction,
+    befores: Vec<(i64, Option<Value>)>,
+) -> RepoResult<()> {
+    for (id, before) in befores {
+        let after = product_snapshot(&mut *conn, id).await?;
+        record_audit(
+            &mut *conn,
+            actor,
+            action,
+            AuditEntity::Product,
+            id,
+            before,
+            after,
+        )
+        .await?;
+    }
+    Ok(())
+}
+
+// id ของสินค้าในประเภท (รวมสินค้าในถังขยะ)
+async fn product_ids_of_type(conn: &mut DbConnection, type_id: i64) -> RepoResult<Vec<i64>> {
+    let ids = sqlx::query_scalar::<_, i64>(
+        "SELECT id FROM products WHERE products_type_id = $1 ORDER BY id",
+    )
+    .bind(type_id)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    Ok(ids)
+}
+
+// stock ของสินค้า (รวมสินค้าในถังขยะ)
+async fn current_stock(conn: &mut DbConnection, id: i64) -> RepoResult<Option<i64>> {
+    let stock = sqlx::query_scalar::<_, i64>("SELECT stock FROM products WHERE id = $1")
+        .bind(id)
+        .fetch_optional(&mut *conn)
+        .await?;
+
+    Ok(stock)
+}
+
+async fn insert_stock_movement(
+    conn: &mut DbConnection,
+    actor: &str,
+    product_id: i64,
+    adjustment: &StockAdjustment,
+    stock_after: i64,
+) -> RepoResult<StockMovement> {
+    let created_at = now();
+    let id = sqlx::query_scalar::<_, i64>(
+        r#"
+        INSERT INTO stock_movements (product_id, quantity, reason, note, stock_after, actor, created_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        RETURNING id
+        "#,
+    )
+    .bind(product_id)
+    .bind(adjustment.quantity)
+    .bind(adjustment.reason.as_str())
+    .bind(&adjustment.note)
+    .bind(stock_after)
+    .bind(actor)
+    .bind(created_at)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    let movement = StockMovement {
+        id,
+        product_id,
+        quantity: adjustment.quantity,
+        reason: adjustment.reason,
+        note: adjustment.note.clone(),
+        stock_after,
+        actor: actor.to_string(),
+        created_at,
+    };
+    record_stock_alert(conn, &movement).await?;
+    Ok(movement)
+}
+
+// จุดสั่งซื้อที่ใช้จริงของสินค้า: ค่าของสินค้า ถ้าไม่ได้ตั้งใช้ค่าของประเภท
+const REORDER_COLUMNS: &str = r#"
+    COALESCE(p.reorder_threshold, pt.reorder_threshold) AS reorder_threshold,
+    COALESCE(p.reorder_quantity, pt.reorder_quantity) AS reorder_quantity
+    "#;
+
+// เพิ่มรายการใน stock_alerts ถ้า movement ทำให้ stock ลดผ่านจุดสั่งซื้อ
+async fn record_stock_alert(conn: &mut DbConnection, movement: &StockMovement) -> RepoResult<()> {
+    let row = sqlx::query(&format!(
+        "SELECT {} {} WHERE p.id = $1",
+        REORDER_COLUMNS, PRODUCT_FROM
+    ))
+    .bind(movement.product_id)
+    .fetch_optional(&mut *conn)
+    .await?;
+
+    let Some(row) = row else {
+        return Ok(());
+    };
+    let Some(threshold) = row.get::<Option<i64>, _>("reorder_threshold") else {
+        return Ok(());
+    };
+    let stock_before = movement.stock_after - movement.quantity;
+    if !crosses_reorder_threshold(stock_before, movement.stock_after, threshold) {
+        return Ok(());
+    }
+
+    sqlx::query(
+        r#"
+        INSERT INTO stock_alerts
+            (product_id, stock_movement_id, stock, reorder_threshold, reorder_quantity, created_at)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        "#,
+    )
+    .bind(movement.product_id)
+    .bind(movement.id)
+    .bind(movement.stock_after)
+    .bind(threshold)
+    .bind(row.get::<Option<i64>, _>("reorder_quantity"))
+    .bind(now())
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
+
+const STOCK_ALERT_COLUMNS: &str = r#"
+    id, product_id, stock_movement_id, stock, reorder_threshold, reorder_quantity, created_at,
+    acknowledged_at
+    "#;
+
+fn stock_alert_from_row(row: &<Db as sqlx::Database>::Row) -> StockAlert {
+    StockAlert {
+        id: row.get("id"),
+        product_id: row.get("product_id"),
+        stock_movement_id: row.get("stock_movement_id"),
+        stock: row.get("stock"),
+        reorder_threshold: row.get("reorder_threshold"),
+        reorder_quantity: row.get("reorder_quantity"),
+        created_at: row.get("created_at"),
+        acknowledged_at: row.get("acknowledged_at"),
+    }
+}
+
+fn push_stock_alert_filters(builder: &mut QueryBuilder<'_, Db>, pending: Option<bool>) {
+    if let Some(pending) = pending {
+        builder.push(if pending {
+            " WHERE acknowledged_at IS NULL"
+        } else {
+            " WHERE acknowledged_at IS NOT NULL"
+        });
+    }
+}
+
+const LOW_STOCK_CONDITION: &str = r#"
+    WHERE p.deleted_at IS NULL
+      AND p.stock <= COALESCE(p.reorder_threshold, pt.reorder_threshold)
+    "#;
+
+// stock ที่ถูกเขียนทับ (PUT/PATCH) บันทึกเป็น correction ด้วยส่วนต่างจากค่าเดิม
+async fn record_stock_overwrite(
+    conn: &mut DbConnection,
+    actor: &str,
+    id: i64,
+    before: Option<i64>,
+) -> RepoResult<()> {
+    let (Some(before), Some(after)) = (before, current_stock(&mut *conn, id).await?) else {
+        return Ok(());
+    };
+    if before != after {
+        let adjustment = StockAdjustment {
+            quantity: after - before,
+            reason: StockReason::Correction,
+            note: None,
+        };
+        insert_stock_movement(conn, actor, id, &adjustment, after).await?;
+    }
+    Ok(())
+}
+
+fn push_stock_movement_filters(
+    builder: &mut QueryBuilder<'_, Db>,
+    product_id: i64,
+    reason: Option<StockReason>,
+) {
+    builder.push(" WHERE product_id = ").push_bind(product_id);
+    if let Some(reason) = reason {
+        builder.push(" AND reason = ").push_bind(reason.as_str());
+    }
+}
+
+#[async_trait]
+impl ProductRepository for SqlRepository {
+    async fn count(&self, filter: &ProductFilter) -> RepoResult<i64> {
+        let search = product_search_query(filter);
+        let mut query = QueryBuilder::<Db>::new("SELECT COUNT(*) FROM products p");
+        if search.is_some() {
+            query.push(SEARCH_JOIN);
+        }
+        push_product_filters(&mut query, filter, search.as_deref());
+        Ok(query
+            .build_query_scalar::<i64>()
+            .fetch_one(&self.pool)
+            .await?)
+    }
+
+    async fn list(
+        &self,
+        filter: &ProductFilter,
+        seek: &PageSeek,
+        limit: i64,
+    ) -> RepoResult<Vec<(Products, SortKey)>> {
+        let search = product_search_query(filter);
+        let expr = ProductSortExpr::new(filter.sort, search.as_deref());
+        let columns = seek_columns(&expr, filter.sort, expr.directions(filter.sort));
+
+        let mut query = QueryBuilder::<Db>::new(PRODUCT_COLUMNS);
+        match &search {
+            Some(search) => push_search_snippet(&mut query, search),
+            None => {
+                query.push(", CAST(NULL AS TEXT) AS snippet");
+            }
+        }
+        query.push(", ");
+        expr.push(&mut query);
+        query.push(" AS sort_key");
+        query.push(PRODUCT_FROM);
+        if search.is_some() {
+            query.push(SEARCH_JOIN);
+        }
+        push_product_filters(&mut query, filter, search.as_deref());
+        match seek {
+            PageSeek::Offset(_) => {}
+            PageSeek::After(key) => push_seek_condition(&mut query, &expr, &columns, key, false),
+            PageSeek::Before(key) => push_seek_condition(&mut query, &expr, &columns, key, true),
+        }
+        let before = matches!(seek, PageSeek::Before(_));
+        push_product_order(&mut query, &expr, &columns, before);
+        query.push(" LIMIT ").push_bind(limit);
+        if let PageSeek::Offset(offset) = seek {
+            query.push(" OFFSET ").push_bind(*offset);
+        }
+
+        let rows = query.build().fetch_all(&self.pool).await?;
+
+        let mut products: Vec<(Products, SortKey)> = rows
+            .iter()
+            .map(|row| {
+                let product = product_from_row(row);
+                let key = SortKey {
+                    value: expr.value_from_row(row, filter.sort),
+                    id: product.id,
+                };
+                (product, key)
+            })
+            .collect();
+        if before {
+            products.reverse();
+        }
+        Ok(products)
+    }
+
+    async fn facets(
+        &self,
+        filter: &ProductFilter,
+        currency: Currency,
+        price_bounds: &[i64],
+        detail_paths: &[Vec<String>],
+    ) -> RepoResult<ProductFacets> {
+        let search = product_search_query(filter);
+        let push_from = |query: &mut QueryBuilder<'_, Db>| {
+            query.push(PRODUCT_FROM);
+            if search.is_some() {
+                query.push(SEARCH_JOIN);
+            }
+        };
+
+        let mut query = QueryBuilder::<Db>::new(
+            "SELECT COUNT(*), COALESCE(SUM(CASE WHEN p.stock > 0 THEN 1 ELSE 0 END), 0)",
+        );
+        push_from(&mut query);
+        push_product_filters(&mut query, filter, search.as_deref());
+        let (total_items, in_stock): (i64, i64) =
+            query.build_query_as().fetch_one(&self.pool).await?;
+
+        // สินค้าไม่มีประเภทอยู่ก่อน แล้วเรียงตาม id ของประเภท
+        let mut query = QueryBuilder::<Db>::new(
+            "SELECT p.products_type_id, pt.products_type_name, COUNT(*) AS count",
+        );
+        push_from(&mut query);
+        push_product_filters(&mut query, filter, search.as_deref());
+        query.push(
+            " GROUP BY p.products_type_id, pt.products_type_name \
+             ORDER BY count DESC, COALESCE(p.products_type_id, 0)",
+        );
+        let types = query
+            .build_query_as::<(Option<i64>, Option<String>, i64)>()
+            .fetch_all(&self.pool)
+            .await?
+            .into_iter()
+            .map(|(id, name, count)| TypeFacet { id, name, count })
+            .collect();
+
+        // เลขช่วง = จำนวนขอบที่ราคาไม่ต่ำกว่า
+        let mut query = QueryBuilder::<Db>::new("SELECT CAST(");
+        if price_bounds.is_empty() {
+            query.push("0");
+        } else {
+            query.push("CASE");
+            for (index, bound) in price_bounds.iter().enumerate() {
+                query
+                    .push(" WHEN p.price_minor < ")
+                    .push_bind(*bound)
+                    .push(" THEN ")
+                    .push(index);
+            }
+            query.push(" ELSE ").push(price_bounds.len()).push(" END");
+        }
+        query.push(" AS BIGINT) AS bucket, COUNT(*)");
+        push_from(&mut query);
+        push_product_filters(&mut query, filter, search.as_deref());
+        query
+            .push(" AND p.currency = ")
+            .push_bind(currency.code())
+            .push(" GROUP BY 1");
+        let bucket_counts: Vec<(i64, i64)> = query.build_query_as().fetch_all(&self.pool).await?;
+        let price = price_buckets(currency, price_bounds, &bucket_counts);
+
+        let mut detail = Vec::new();
+        for path in detail_paths {
+            let mut query = QueryBuilder::<Db>::new("SELECT ");
+            query
+                .push(DETAIL_TEXT)
+                .push(" AS value, COUNT(DISTINCT p.id) AS count");
+            push_from(&mut query);
+            query.push(" CROSS JOIN ");
+            push_detail_source(&mut query, path);
+            push_product_filters(&mut query, filter, search.as_deref());
+            query
+                .push(" AND ")
+                .push(DETAIL_IS_SCALAR)
+                .push(" GROUP BY 1 ORDER BY count DESC, value LIMIT ")
+                .push_bind(FACET_VALUE_LIMIT);
+            let values = query
+                .build_query_as::<(String, i64)>()
+                .fetch_all(&self.pool)
+                .await?
+                .into_iter()
+                .map(|(value, count)| ValueFacet { value, count })
+                .collect();
+            detail.push(DetailFacet {
+                key: path.join("."),
+                values,
+            });
+        }
+
+        Ok(ProductFacets {
+            total_items,
+            types,
+            price,
+            stock: StockFacet {
+                in_stock,
+                out_of_stock: total_items - in_stock,
+            },
+            detail,
+        })
+    }
+
+    async fn find(&self, id: i64) -> RepoResult<Option<Products>> {
+        let mut query = QueryBuilder::<Db>::new(PRODUCT_COLUMNS);
+        query
+            .push(", CAST(NULL AS TEXT) AS snippet")
+            .push(PRODUCT_FROM)
+            .push(" WHERE p.deleted_at IS NULL AND p.id = ")
+            .push_bind(id);
+
+        let row = query.build().fetch_optional(&self.pool).await?;
+        Ok(row.as_ref().map(product_from_row))
+    }
+
+    async fn find_id_by_barcode(&self, codes: &[String]) -> RepoResult<Option<i64>> {
+        if codes.is_empty() {
+            return Ok(None);
+        }
+
+        let mut query = QueryBuilder::<Db>::new(
+            "SELECT id FROM products WHERE deleted_at IS NULL AND barcode IN (",
+        );
+        let mut separated = query.separated(", ");
+        for code in codes {
+            separated.push_bind(code.clone());
+        }
+        query.push(") ORDER BY id LIMIT 1");
+
+        Ok(query
+            .build_query_scalar::<i64>()
+            .fetch_optional(&self.pool)
+            .await?)
+    }
+
+    async fn create(
+        &self,
+        actor: &str,
+        product: &ProductInput,
+        image_paths: &[String],
+    ) -> RepoResult<i64> {
+        let mut tx = self.pool.begin().await?;
+        let product_id = insert_product(&mut tx, actor, product, image_paths).await?;
+        tx.commit().await?;
+        Ok(product_id)
+    }
+
+    async fn duplicate(
+        &self,
+        actor: &str,
+        source_id: i64,
+        product: &ProductInput,
+        image_paths: &[String],
+    ) -> RepoResult<i64> {
+        let mut tx = self.pool.begin().await?;
+        let product_id = sqlx::query_scalar::<_, i64>(
+            r#"
+            INSERT INTO products (name_products, price_minor, currency, detail, stock, products_type_id,
+                                  allow_backorder, reorder_threshold, reorder_quantity)
+            SELECT $1, $2, $3, $4, $5, $6,
+                   allow_backorder, reorder_threshold, reorder_quantity
+            FROM products
+            WHERE id = $7 AND deleted_at IS NULL
+            RETURNING id
+            "#,
+        )
+        .bind(&product.name)
+        .bind(product.price.amount)
+        .bind(product.price.currency.code())
+        .bind(&product.detail)
+        .bind(product.stock)
+        .bind(product.products_type_id)
+        .bind(source_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or(RepoError::NotFound)?;
+
+        record_new_product(&mut tx, actor, product_id, image_paths).await?;
+        tx.commit().await?;
+        Ok(product_id)
+    }
+
+    async fn import(
+        &self,
+        actor: &str,
+        new_types: &[String],
+        products: &[ImportedProduct],
+    ) -> RepoResult<Vec<i64>> {
+        let mut tx = self.pool.begin().await?;
+
+        for name in new_types {
+            let existing = sqlx::query_scalar::<_, Option<NaiveDateTime>>(
+                "SELECT deleted_at FROM products_type WHERE products_type_name = $1",
+            )
+            .bind(name)
+            .fetch_optional(&mut *tx)
+            .await?;
+            match existing {
+                Some(Some(_)) => {
+                    return Err(RepoError::Conflict(format!(
+                        "Product type {} is in trash",
+                        name
+                    )));
+                }
+                Some(None) => {
+                    return Err(RepoError::Conflict(format!(
+                        "Product type {} already exists",
+                        name
+                    )));
+                }
+                None => {}
+            }
+            insert_product_type(&mut tx, actor, name, &[]).await?;
+        }
+
+        let mut type_ids: HashMap<&str, i64> = HashMap::new();
+        let mut product_ids = Vec::with_capacity(products.len());
+        for product in products {
+            let products_type_id = match product.type_name.as_deref() {
+                None => None,
+                Some(name) => match type_ids.get(name) {
+                    Some(id) => Some(*id),
+                    None => {
+                        let id = sqlx::query_scalar::<_, i64>(
+                            "SELECT id FROM products_type WHERE products_type_name = $1 AND deleted_at IS NULL",
+                        )
+                        .bind(name)
+                        .fetch_optional(&mut *tx)
+                        .await?
+                        .ok_or_else(|| {
+                            RepoError::Conflict(format!("Unknown product type: {}", name))
+                        })?;
+                        type_ids.insert(name, id);
+                        Some(id)
+                    }
+                },
+            };
+            let input = ProductInput {
+                name: product.name.clone(),
+                price: product.price,
+                detail: product.detail.clone(),
+                stock: product.stock,
+                products_type_id,
+            };
+            product_ids.push(insert_product(&mut tx, actor, &input, &product.image_paths).await?);
+        }
+
+        tx.commit().await?;
+        Ok(product_ids)
+    }
+
+    async fn update(
+        &self,
+        actor: &str,
+        id: i64,
+        product: &ProductInput,
+        image_paths: &[String],
+    ) -> RepoResult<()> {
+        let mut tx = self.pool.begin_with(BEGIN_WRITE).await?;
+        let before = product_snapshot(&mut tx, id).await?;
+        let stock_before = current_stock(&mut tx, id).await?;
+
+        let result = sqlx::query(
+            "UPDATE products
+             SET name_products = $1, price_minor = $2, currency = $3, detail = $4, stock = $5,
+                 products_type_id = $6
+             WHERE id = $7 AND deleted_at IS NULL",
+        )
+        .bind(&product.name)
+        .bind(product.price.amount)
+        .bind(product.price.currency.code())
+        .bind(&product.detail)
+        .bind(product.stock)
+        .bind(product.products_type_id)
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(RepoError::NotFound);
+        }
+
+        // ลบรูปเก่าก่อนแล้วเพิ่มรูปใหม่
+        sqlx::query("DELETE FROM images WHERE product_id = $1")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+
+        for path in image_paths {
+            sqlx::query("INSERT INTO images (image_path, product_id) VALUES ($1, $2)")
+                .bind(path)
+                .bind(id)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        record_stock_overwrite(&mut tx, actor, id, stock_before).await?;
+        record_product_changes(&mut tx, actor, AuditAction::Update, vec![(id, before)]).await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn patch(&self, actor: &str, id: i64, changes: &ProductChanges) -> RepoResult<()> {
+        let mut tx = self.pool.begin_with(BEGIN_WRITE).await?;
+        let before = product_snapshot(&mut tx, id).await?;
+        let stock_before = current_stock(&mut tx, id).await?;
+
+        let active = sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*) FROM products WHERE id = $1 AND deleted_at IS NULL",
+        )
+        .bind(id)
+        .fetch_one(&mut *tx)
+        .await?;
+        if active == 0 {
+            return Err(RepoError::NotFound);
+        }
+        check_product_codes(&mut tx, id, changes).await?;
+
+        let mut query = QueryBuilder::<Db>::new("UPDATE products SET ");
+        let mut columns = query.separated(", ");
+        let mut has_columns = false;
+        if let Some(name) = &changes.name {
+            columns
+                .push("name_products = ")
+                .push_bind_unseparated(name.clone());
+            has_columns = true;
+        }
+        if let Some(price) = changes.price {
+            columns
+                .push("price_minor = ")
+                .push_bind_unseparated(price.amount);
+            columns
+                .push("currency = ")
+                .push_bind_unseparated(price.currency.code());
+            has_columns = true;
+        }
+        if let Some(detail) = &changes.detail {
+            columns
+                .push("detail = ")
+                .push_bind_unseparated(detail.clone());
+            has_columns = true;
+        }
+        if let Some(stock) = changes.stock {
+            columns.push("stock = ").push_bind_unseparated(stock);
+            has_columns = true;
+        }
+        if let Some(allow_backorder) = changes.allow_backorder {
+            columns
+                .push("allow_backorder = ")
+                .push_bind_unseparated(allow_backorder);
+            has_columns = true;
+        }
+        if let Some(reorder_threshold) = changes.reorder_threshold {
+            columns
+                .push("reorder_threshold = ")
+                .push_bind_unseparated(reorder_threshold);
+            has_columns = true;
+        }
+        if let Some(reorder_quantity) = changes.reorder_quantity {
+            columns
+                .push("reorder_quantity = ")
+                .push_bind_unseparated(reorder_quantity);
+            has_columns = true;
+        }
+        if let Some(products_type_id) = changes.products_type_id {
+            columns
+                .push("products_type_id = ")
+                .push_bind_unseparated(products_type_id);
+            has_columns = true;
+        }
+        if let Some(sku) = &changes.sku {
+            columns.push("sku = ").push_bind_unseparated(sku.clone());
+            has_columns = true;
+        }
+        if let Some(barcode) = &changes.barcode {
+            let (format, code) = match barcode {
+                Some((format, code)) => (Some(format.as_str()), Some(code.clone())),
+                None => (None, None),
+            };
+            columns.push("barcode = ").push_bind_unseparated(code);
+            columns
+                .push("barcode_format = ")
+                .push_bind_unseparated(format);
+            has_columns = true;
+        }
+        if has_columns {
+            query.push(" WHERE id = ").push_bind(id);
+            query
+                .build()
+                .execute(&mut *tx)
+                .await
+                .map_err(product_write_error)?;
+        }
+
+        if let Some(image_paths) = &changes.image_paths {
+            sqlx::query("DELETE FROM images WHERE product_id = $1")
+                .bind(id)
+                .execute(&mut *tx)
+                .await?;
+
+            for path in image_paths {
+                sqlx::query("INSERT INTO images (image_path, product_id) VALUES ($1, $2)")
+                    .bind(path)
+                    .bind(id)
+                    .execute(&mut *tx)
+                    .await?;
+            }
+        }
+
+        record_stock_overwrite(&mut tx, actor, id, stock_before).await?;
+        record_product_changes(&mut tx, actor, AuditAction::Update, vec![(id, before)]).await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn update_images(&self, actor: &str, id: i64, images: &[ImageSlot]) -> RepoResult<()> {
+        let mut tx = self.pool.begin_with(BEGIN_WRITE).await?;
+        let before = product_snapshot(&mut tx, id).await?;
+
+        let active = sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*) FROM products WHERE id = $1 AND deleted_at IS NULL",
+        )
+        .bind(id)
+        .fetch_one(&mut *tx)
+        .await?;
+        if active == 0 {
+            return Err(RepoError::NotFound);
+        }
+
+        let current = sqlx::query_scalar::<_, i64>("SELECT id FROM images WHERE product_id = $1")
+            .bind(id)
+            .fetch_all(&mut *tx)
+            .await?;
+        let kept: Vec<i64> = images
+            .iter()
+            .filter_map(|slot| match slot {
+                ImageSlot::Existing(image_id) => Some(*image_id),
+                ImageSlot::New(_) => None,
+            })
+            .collect();
+        if kept.iter().any(|image_id| !current.contains(image_id)) {
+            return Err(RepoError::Conflict(
+                "Product images changed, reload and try again".to_string(),
+            ));
+        }
+
+        for image_id in current.iter().filter(|image_id| !kept.contains(image_id)) {
+            sqlx::query("DELETE FROM images WHERE id = $1")
+                .bind(image_id)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        for (position, slot) in images.iter().enumerate() {
+            match slot {
+                ImageSlot::Existing(image_id) => {
+                    sqlx::query("UPDATE images SET position = $1 WHERE id = $2")
+                        .bind(position as i64)
+                        .bind(image_id)
+                        .execute(&mut *tx)
+                        .await?;
+                }
+                ImageSlot::New(path) => {
+                    sqlx::query(
+                        "INSERT INTO images (image_path, product_id, position) VALUES ($1, $2, $3)",
+                    )
+                    .bind(path)
+                    .bind(id)
+                    .bind(position as i64)
+                    .execute(&mut *tx)
+                    .await?;
+                }
+            }
+        }
+
+        record_product_changes(&mut tx, actor, AuditAction::Update, vec![(id, before)]).await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn adjust_stock(
+        &self,
+        actor: &str,
+        id: i64,
+        adjustment: &StockAdjustment,
+    ) -> RepoResult<StockMovement> {
+        let mut tx = self.pool.begin_with(BEGIN_WRITE).await?;
+        let before = product_snapshot(&mut tx, id).await?;
+
+        // ตรวจใน UPDATE เดียวกับที่เปลี่ยนค่า การปรับพร้อมกันหลายรายการจึงไม่ทับกันและไม่ทำให้ stock ติดลบ
+        let stock_after = sqlx::query_scalar::<_, i64>(
+            "UPDATE products SET stock = stock + $1
+             WHERE id = $2 AND deleted_at IS NULL AND ($3 OR allow_backorder OR stock + $4 >= 0)
+             RETURNING stock",
+        )
+        .bind(adjustment.quantity)
+        .bind(id)
+        .bind(adjustment.quantity > 0)
+        .bind(adjustment.quantity)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        let Some(stock_after) = stock_after else {
+            let stock = sqlx::query_scalar::<_, i64>(
+                "SELECT stock FROM products WHERE id = $1 AND deleted_at IS NULL",
+            )
+            .bind(id)
+            .fetch_optional(&mut *tx)
+            .await?;
+            return Err(match stock {
+                Some(stock) => RepoError::Conflict(format!("Not enough stock ({} left)", stock)),
+                None => RepoError::NotFound,
+            });
+        };
+
+        let movement = insert_stock_movement(&mut tx, actor, id, adjustment, stock_after).await?;
+        record_product_changes(&mut tx, actor, AuditAction::Update, vec![(id, before)]).await?;
+
+        tx.commit().await?;
+        Ok(movement)
+    }
+
+    async fn count_stock_movements(&self, id: i64, reason: Option<StockReason>) -> RepoResult<i64> {
+        let mut query = QueryBuilder::<Db>::new("SELECT COUNT(*) FROM stock_movements");
+        push_stock_movement_filters(&mut query, id, reason);
+        Ok(query
+            .build_query_scalar::<i64>()
+            .fetch_one(&self.pool)
+            .await?)
+    }
+
+    async fn stock_movements(
+        &self,
+        id: i64,
+        reason: Option<StockReason>,
+        limit: i64,
+        offset: i64,
+    ) -> RepoResult<Vec<StockMovement>> {
+        let mut query = QueryBuilder::<Db>::new(
+            r#"
+            SELECT id, product_id, quantity, reason, note, stock_after, actor, created_at
+            FROM stock_movements
+            "#,
+        );
+        push_stock_movement_filters(&mut query, id, reason);
+        query
+            .push(" ORDER BY id DESC LIMIT ")
+            .push_bind(limit)
+            .push(" OFFSET ")
+            .push_bind(offset);
+
+        let rows = query.build().fetch_all(&self.pool).await?;
+
+        let mut movements = Vec::with_capacity(rows.len());
+        for row in &rows {
+            let reason: String = row.get("reason");
+            movements.push(StockMovement {
+                id: row.get("id"),
+                product_id: row.get("product_id"),
+                quantity: row.get("quantity"),
+                reason: reason.parse().map_err(RepoError::Conflict)?,
+                note: row.get("note"),
+                stock_after: row.get("stock_after"),
+                actor: row.get("actor"),
+                created_at: row.get("created_at"),
+            });
+        }
+        Ok(movements)
+    }
+
+    async fn set_variant_options(
+        &self,
+        actor: &str,
+        id: i64,
+        options: &[VariantOption],
+    ) -> RepoResult<()> {
+        let mut tx = self.pool.begin_with(BEGIN_WRITE).await?;
+        let before = product_snapshot(&mut tx, id).await?;
+        check_variant(&mut tx, id, None, None, None).await?;
+
+        let rows = sqlx::query(
+            "SELECT sku, options FROM product_variants WHERE product_id = $1 ORDER BY id",
+        )
+        .bind(id)
+        .fetch_all(&mut *tx)
+        .await?;
+        for row in &rows {
+            let variant_options = row.get::<Json<BTreeMap<String, String>>, _>("options").0;
+            if let Some(error) = variant_options_error(options, &variant_options) {
+                return Err(RepoError::Conflict(format!(
+                    "Variant {} does not fit the new options: {}",
+                    row.get::<String, _>("sku"),
+                    error
+                )));
+            }
+        }
+
+        sqlx::query("UPDATE products SET variant_options = $1 WHERE id = $2")
+            .bind(Json(options))
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+
+        record_product_changes(&mut tx, actor, AuditAction::Update, vec![(id, before)]).await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn variants(&self, id: i64) -> RepoResult<Vec<ProductVariant>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT v.id, v.sku, v.options, v.price_minor, v.stock, p.currency
+            FROM product_variants v
+            JOIN products p ON p.id = v.product_id
+            WHERE v.product_id = $1
+            ORDER BY v.id
+            "#,
+        )
+        .bind(id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let image_rows = sqlx::query(
+            r#"
+            SELECT vi.variant_id, i.id, i.image_path
+            FROM product_variant_images vi
+            JOIN product_variants v ON v.id = vi.variant_id
+            JOIN images i ON i.id = vi.image_id
+            WHERE v.product_id = $1
+            ORDER BY vi.position
+            "#,
+        )
+        .bind(id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| {
+                let variant_id: i64 = row.get("id");
+                let currency: Currency =
+                    row.get::<String, _>("currency").parse().unwrap_or_default();
+                ProductVariant {
+                    id: variant_id,
+                    sku: row.get("sku"),
+                    options: row.get::<Json<BTreeMap<String, String>>, _>("options").0,
+                    price: row
+                        .get::<Option<i64>, _>("price_minor")
+                        .map(|amount| Money::new(amount, currency)),
+                    stock: row.get("stock"),
+                    images: image_rows
+                        .iter()
+                        .filter(|image| image.get::<i64, _>("variant_id") == variant_id)
+                        .map(|image| ProductImage {
+                            id: image.get("id"),
+                            image_path: image.get("image_path"),
+                        })
+                        .collect(),
+                }
+            })
+            .collect())
+    }
+
+    async fn create_variant(
+        &self,
+        actor: &str,
+        id: i64,
+        variant: &VariantInput,
+    ) -> RepoResult<i64> {
+        let mut tx = self.pool.begin_with(BEGIN_WRITE).await?;
+        let before = product_snapshot(&mut tx, id).await?;
+        check_variant(
+            &mut tx,
+            id,
+            None,
+            Some(&variant.sku),
+            Some(&variant.options),
+        )
+        .await?;
+
+        let variant_id = sqlx::query_scalar::<_, i64>(
+            r#"
+            INSERT INTO product_variants (product_id, sku, options, price_minor, stock, created_at)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            RETURNING id
+            "#,
+        )
+        .bind(id)
+        .bind(&variant.sku)
+        .bind(Json(&variant.options))
+        .bind(variant.price_minor)
+        .bind(variant.stock)
+        .bind(now())
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(variant_write_error)?;
+        set_variant_images(&mut tx, variant_id, &variant.image_ids).await?;
+
+        record_product_changes(&mut tx, actor, AuditAction::Update, vec![(id, before)]).await?;
+
+        tx.commit().await?;
+        Ok(variant_id)
+    }
+
+    async fn patch_variant(
+        &self,
+        actor: &str,
+        id: i64,
+        variant_id: i64,
+        changes: &VariantChanges,
+    ) -> RepoResult<()> {
+        let mut tx = self.pool.begin_with(BEGIN_WRITE).await?;
+        let before = product_snapshot(&mut tx, id).await?;
+        check_variant(
+            &mut tx,
+            id,
+            Some(variant_id),
+            changes.sku.as_deref(),
+            changes.options.as_ref(),
+        )
+        .await?;
+
+        let found = sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*) FROM product_variants WHERE id = $1 AND product_id = $2",
+        )
+        .bind(variant_id)
+        .bind(id)
+        .fetch_one(&mut *tx)
+        .await?;
+        if found == 0 {
+            return Err(RepoError::NotFound);
+        }
+
+        let mut query = QueryBuilder::<Db>::new("UPDATE product_variants SET ");
+        let mut columns = query.separated(", ");
+        let mut has_columns = false;
+        if let Some(sku) = &changes.sku {
+            columns.push("sku = ").push_bind_unseparated(sku.clone());
+            has_columns = true;
+        }
+        if let Some(options) = &changes.options {
+            columns
+                .push("options = ")
+                .push_bind_unseparated(Json(options.clone()));
+            has_columns = true;
+        }
+        if let Some(price_minor) = changes.price_minor {
+            columns
+                .push("price_minor = ")
+                .push_bind_unseparated(price_minor);
+            has_columns = true;
+        }
+        if let Some(stock) = changes.stock {
+            columns.push("stock = ").push_bind_unseparated(stock);
+            has_columns = true;
+        }
+        if has_columns {
+            query.push(" WHERE id = ").push_bind(variant_id);
+            query
+                .build()
+                .execute(&mut *tx)
+                .await
+                .map_err(variant_write_error)?;
+        }
+
+        if let Some(image_ids) = &changes.image_ids {
+            set_variant_images(&mut tx, variant_id, image_ids).await?;
+        }
+
+        record_product_changes(&mut tx, actor, AuditAction::Update, vec![(id, before)]).await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn delete_variant(&self, actor: &str, id: i64, variant_id: i64) -> RepoResult<()> {
+        let mut tx = self.pool.begin().await?;
+        let before = product_snapshot(&mut tx, id).await?;
+        check_variant(&mut tx, id, None, None, None).await?;
+
+        let result = sqlx::query("DELETE FROM product_variants WHERE id = $1 AND product_id = $2")
+            .bind(variant_id)
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        if result.rows_affected() == 0 {
+            return Err(RepoError::NotFound);
+        }
+
+        record_product_changes(&mut tx, actor, AuditAction::Update, vec![(id, before)]).await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn count_low_stock(&self) -> RepoResult<i64> {
+        let count = sqlx::query_scalar::<_, i64>(&format!(
+            "SELECT COUNT(*) {} {}",
+            PRODUCT_FROM, LOW_STOCK_CONDITION
+        ))
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(count)
+    }
+
+    async fn low_stock(&self, limit: i64, offset: i64) -> RepoResult<Vec<LowStockProduct>> {
+        let rows = sqlx::query(&format!(
+            r#"
+            SELECT p.id, p.name_products, p.stock, p.products_type_id, pt.products_type_name, {}
+            {}
+            {}
+            ORDER BY p.stock - COALESCE(p.reorder_threshold, pt.reorder_threshold), p.id
+            LIMIT $1 OFFSET $2
+            "#,
+            REORDER_COLUMNS, PRODUCT_FROM, LOW_STOCK_CONDITION
+        ))
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| LowStockProduct {
+                id: row.get("id"),
+                name_product: row.get("name_products"),
+                stock: row.get("stock"),
+                reorder_threshold: row.get("reorder_threshold"),
+                reorder_quantity: row.get("reorder_quantity"),
+                products_type_id: row.get("products_type_id"),
+                products_type_name: row.get("products_type_name"),
+            })
+            .collect())
+    }
+
+    async fn count_stock_alerts(&self, pending: Option<bool>) -> RepoResult<i64> {
+        let mut query = QueryBuilder::<Db>::new("SELECT COUNT(*) FROM stock_alerts");
+        push_stock_alert_filters(&mut query, pending);
+        Ok(query
+            .build_query_scalar::<i64>()
+            .fetch_one(&self.pool)
+            .await?)
+    }
+
+    async fn stock_alerts(
+        &self,
+        pending: Option<bool>,
+        limit: i64,
+        offset: i64,
+    ) -> RepoResult<Vec<StockAlert>> {
+        let mut query =
+            QueryBuilder::<Db>::new(format!("SELECT {} FROM stock_alerts", STOCK_ALERT_COLUMNS));
+        push_stock_alert_filters(&mut query, pending);
+        query
+            .push(" ORDER BY id LIMIT ")
+            .push_bind(limit)
+            .push(" OFFSET ")
+            .push_bind(offset);
+
+        let rows = query.build().fetch_all(&self.pool).await?;
+        Ok(rows.iter().map(stock_alert_from_row).collect())
+    }
+
+    async fn acknowledge_stock_alert(&self, id: i64) -> RepoResult<StockAlert> {
+        let row = sqlx::query(&format!(
+            r#"
+            UPDATE stock_alerts SET acknowledged_at = COALESCE(acknowledged_at, $1)
+            WHERE id = $2
+            RETURNING {}
+            "#,
+            STOCK_ALERT_COLUMNS
+        ))
+        .bind(now())
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        row.as_ref()
+            .map(stock_alert_from_row)
+            .ok_or(RepoError::NotFound)
+    }
+
+    async fn soft_delete(&self, actor: &str, id: i64) -> RepoResult<()> {
+        let mut tx = self.pool.begin().await?;
+        let before = product_snapshot(&mut tx, id).await?;
+
+        let result =
+            sqlx::query("UPDATE products SET deleted_at = $1 WHERE id = $2 AND deleted_at IS NULL")
+                .bind(now())
+                .bind(id)
+                .execute(&mut *tx)
+                .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(RepoError::NotFound);
+        }
+
+        record_product_changes(&mut tx, actor, AuditAction::Delete, vec![(id, before)]).await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn restore(&self, actor: &str, id: i64) -> RepoResult<()> {
+        let mut tx = self.pool.begin().await?;
+
+        let row = sqlx::query(
+            r#"
+            SELECT
+                p.deleted_at IS NOT NULL AS trashed,
+                pt.deleted_at IS NOT NULL AS type_trashed
+            FROM products p
+            LEFT JOIN products_type pt ON p.products_type_id = pt.id
+            WHERE p.id = $1
+            "#,
+        )
+        .bind(id)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        match row {
+            Some(row) if row.get::<bool, _>("trashed") => {
+                if row.get::<bool, _>("type_trashed") {
+                    return Err(RepoError::Conflict(
+                        "Product type is in the trash, restore it first".to_string(),
+                    ));
+                }
+            }
+            _ => return Err(RepoError::NotFound),
+        }
+
+        let before = product_snapshot(&mut tx, id).await?;
+
+        sqlx::query("UPDATE products SET deleted_at = NULL WHERE id = $1")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+
+        record_product_changes(&mut tx, actor, AuditAction::Restore, vec![(id, before)]).await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn purge(&self, actor: &str, id: i64) -> RepoResult<()> {
+        let mut tx = self.pool.begin().await?;
+        let before = product_snapshot(&mut tx, id).await?;
+
+        // แถวใน images ถูกลบตามด้วย ON DELETE CASCADE
+        let result = sqlx::query("DELETE FROM products WHERE id = $1 AND deleted_at IS NOT NULL")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(RepoError::NotFound);
+        }
+
+        record_product_changes(&mut tx, actor, AuditAction::Purge, vec![(id, before)]).await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn trashed_before(&self, cutoff: NaiveDateTime) -> RepoResult<Vec<i64>> {
+        let ids = sqlx::query_scalar::<_, i64>(
+            "SELECT id FROM products WHERE deleted_at IS NOT NULL AND deleted_at < $1 ORDER BY id",
+        )
+        .bind(cutoff)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(ids)
+    }
+}
+
+#[async_trait]
+impl ProductTypeRepository for SqlRepository {
+    async fn count(&self, search: &str, trashed: bool) -> RepoResult<i64> {
+        let count = sqlx::query_scalar::<_, i64>(&format!(
+            r#"
+            SELECT COUNT(*)
+            FROM products_type pt
+            WHERE pt.products_type_name {} $1 AND {}
+            "#,
+            LIKE,
+            trash_condition("pt.deleted_at", trashed)
+        ))
+        .bind(format!("%{}%", search))
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(count)
+    }
+
+    async fn list(
+        &self,
+        search: &str,
+        trashed: bool,
+        sort: ListSort,
+        limit: i64,
+        offset: i64,
+    ) -> RepoResult<Vec<ProductType>> {
+        let rows = sqlx::query(&format!(
+            r#"
+            SELECT
+                pt.id,
+                pt.products_type_name,
+                pt.reorder_threshold,
+                pt.reorder_quantity,
+                pt.deleted_at
+            FROM
+                products_type pt
+            WHERE
+                pt.products_type_name {} $1 AND {}
+            {}
+            LIMIT $2 OFFSET $3;
+            "#,
+            LIKE,
+            trash_condition("pt.deleted_at", trashed),
+            order_by(type_sort_column(sort), "pt.id", sort.is_desc())
+        ))
+        .bind(format!("%{}%", search))
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| ProductType {
+                id: row.get("id"),
+                name: row.get("products_type_name"),
+                images_path: Vec::new(),
+                reorder_threshold: row.get("reorder_threshold"),
+                reorder_quantity: row.get("reorder_quantity"),
+                deleted_at: row.get("deleted_at"),
+            })
+            .collect())
+    }
+
+    async fn find_id_by_name(&self, name: &str) -> RepoResult<Option<i64>> {
+        let id = sqlx::query_scalar::<_, i64>(
+            "SELECT id FROM products_type WHERE products_type_name = $1 AND deleted_at IS NULL",
+        )
+        .bind(name)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(id)
+    }
+
+    async fn create(&self, actor: &str, name: &str, image_paths: &[String]) -> RepoResult<i64> {
+        let mut tx = self.pool.begin().await?;
+        let type_id = insert_product_type(&mut tx, actor, name, image_paths).await?;
+        tx.commit().await?;
+        Ok(type_id)
+    }
+
+    async fn patch(&self, actor: &str, id: i64, changes: &ProductTypeChanges) -> RepoResult<()> {
+        let mut tx = self.pool.begin().await?;
+        let before = product_type_snapshot(&mut tx, id).await?;
+
+        let active = sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*) FROM products_type WHERE id = $1 AND deleted_at IS NULL",
+        )
+        .bind(id)
+        .fetch_one(&mut *tx)
+        .await?;
+        if active == 0 {
+            return Err(RepoError::NotFound);
+        }
+
+        let mut query = QueryBuilder::<Db>::new("UPDATE products_type SET ");
+        let mut columns = query.separated(", ");
+        let mut has_columns = false;
+        if let Some(reorder_threshold) = changes.reorder_threshold {
+            columns
+                .push("reorder_threshold = ")
+                .push_bind_unseparated(reorder_threshold);
+            has_columns = true;
+        }
+        if let Some(reorder_quantity) = changes.reorder_quantity {
+            columns
+                .push("reorder_quantity = ")
+                .push_bind_unseparated(reorder_quantity);
+            has_columns = true;
+        }
+        if has_columns {
+            query.push(" WHERE id = ").push_bind(id);
+            query.build().execute(&mut *tx).await?;
+        }
+
+        let after = product_type_snapshot(&mut tx, id).await?;
+        record_audit(
+            &mut tx,
+            actor,
+            AuditAction::Update,
+            AuditEntity::ProductType,
+            id,
+            before,
+            after,
+        )
+        .await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn soft_delete_with_products(&self, actor: &str, id: i64) -> RepoResult<()> {
+        let mut tx = self.pool.begin().await?;
+        let deleted_at = now();
+        let before = product_type_snapshot(&mut tx, id).await?;
+
+        let result = sqlx::query(
+            "UPDATE products_type SET deleted_at = $1 WHERE id = $2 AND deleted_at IS NULL",
+        )
+        .bind(deleted_at)
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(RepoError::NotFound);
+        }
+
+        let product_ids = sqlx::query_scalar::<_, i64>(
+            "SELECT id FROM products WHERE products_type_id = $1 AND deleted_at IS NULL ORDER BY id",
+        )
+        .bind(id)
+        .fetch_all(&mut *tx)
+        .await?;
+        let product_befores = product_snapshots(&mut tx, &product_ids).await?;
+
+        sqlx::query(
+            "UPDATE products SET deleted_at = $1 WHERE products_type_id = $2 AND deleted_at IS NULL",
+        )
+        .bind(deleted_at)
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+
+        let after = product_type_snapshot(&mut tx, id).await?;
+        record_audit(
+            &mut tx,
+            actor,
+            AuditAction::Delete,
+            AuditEntity::ProductType,
+            id,
+            before,
+            after,
+        )
+        .await?;
+        record_product_changes(&mut tx, actor, AuditAction::Delete, product_befores).await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn restore(&self, actor: &str, id: i64) -> RepoResult<()> {
+        let mut tx = self.pool.begin().await?;
+        let before = product_type_snapshot(&mut tx, id).await?;
+
+        // สินค้าที่ deleted_at ตรงกับของประเภทคือสินค้าที่ถูกลบไปพร้อมประเภท
+        let product_ids = sqlx::query_scalar::<_, i64>(
+            r#"
+            SELECT id FROM products
+            WHERE products_type_id = $1
+              AND deleted_at = (SELECT deleted_at FROM products_type WHERE id = $1)
+            ORDER BY id
+            "#,
+        )
+        .bind(id)
+        .fetch_all(&mut *tx)
+        .await?;
+        let product_befores = product_snapshots(&mut tx, &product_ids).await?;
+
+        sqlx::query(
+            r#"
+            UPDATE products SET deleted_at = NULL
+            WHERE products_type_id = $1
+              AND deleted_at = (SELECT deleted_at FROM products_type WHERE id = $1)
+            "#,
+        )
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+
+        let result = sqlx::query(
+            "UPDATE products_type SET deleted_at = NULL WHERE id = $1 AND deleted_at IS NOT NULL",
+        )
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(RepoError::NotFound);
+        }
+
+        let after = product_type_snapshot(&mut tx, id).await?;
+        record_audit(
+            &mut tx,
+            actor,
+            AuditAction::Restore,
+            AuditEntity::ProductType,
+            id,
+            before,
+            after,
+        )
+        .await?;
+        record_product_changes(&mut tx, actor, AuditAction::Restore, product_befores).await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn purge(&self, actor: &str, id: i64) -> RepoResult<()> {
+        let mut tx = self.pool.begin().await?;
+
+        let trashed = sqlx::query_scalar::<_, bool>(
+            "SELECT deleted_at IS NOT NULL FROM products_type WHERE id = $1",
+        )
+        .bind(id)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        if trashed != Some(true) {
+            return Err(RepoError::NotFound);
+        }
+
+        let before = product_type_snapshot(&mut tx, id).await?;
+        let product_ids = product_ids_of_type(&mut tx, id).await?;
+        let product_befores = product_snapshots(&mut tx, &product_ids).await?;
+
+        // ลบสินค้าก่อน เพราะถ้าลบประเภทก่อน products_type_id จะถูก SET NULL จนหาสินค้าไม่เจอ
+        sqlx::query("DELETE FROM products WHERE products_type_id = $1")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+
+        sqlx::query("DELETE FROM products_type WHERE id = $1")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+
+        record_audit(
+            &mut tx,
+            actor,
+            AuditAction::Purge,
+            AuditEntity::ProductType,
+            id,
+            before,
+            None,
+        )
+        .await?;
+        record_product_changes(&mut tx, actor, AuditAction::Purge, product_befores).await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn trashed_before(&self, cutoff: NaiveDateTime) -> RepoResult<Vec<i64>> {
+        let ids = sqlx::query_scalar::<_, i64>(
+            "SELECT id FROM products_type WHERE deleted_at IS NOT NULL AND deleted_at < $1 ORDER BY id",
+        )
+        .bind(cutoff)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(ids)
+    }
+
+    async fn soft_delete_keep_products(&self, actor: &str, id: i64) -> RepoResult<()> {
+        let mut tx = self.pool.begin().await?;
+        let before = product_type_snapshot(&mut tx, id).await?;
+
+        let result = sqlx::query(
+            "UPDATE products_type SET deleted_at = $1 WHERE id = $2 AND deleted_at IS NULL",
+        )
+        .bind(now())
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(RepoError::NotFound);
+        }
+
+        let product_ids = product_ids_of_type(&mut tx, id).await?;
+        let product_befores = product_snapshots(&mut tx, &product_ids).await?;
+        sqlx::query("UPDATE products SET products_type_id = NULL WHERE products_type_id = $1")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+
+        let after = product_type_snapshot(&mut tx, id).await?;
+        record_audit(
+            &mut tx,
+            actor,
+            AuditAction::Delete,
+            AuditEntity::ProductType,
+            id,
+            before,
+            after,
+        )
+        .await?;
+        record_product_changes(&mut tx, actor, AuditAction::Update, product_befores).await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ImageRepository for SqlRepository {
+    async fn paths_for_products(&self, product_ids: &[i64]) -> RepoResult<Vec<(i64, String)>> {
+        image_paths_in(&self.pool, "product_id", product_ids).await
+    }
+
+    async fn paths_for_types(&self, type_ids: &[i64]) -> RepoResult<Vec<(i64, String)>> {
+        image_paths_in(&self.pool, "product_type_id", type_ids).await
+    }
+
+    async fn paths_for_product(&self, product_id: i64) -> RepoResult<Vec<String>> {
+        let paths = sqlx::query_scalar::<_, String>(
+            "SELECT image_path FROM images WHERE product_id = $1 ORDER BY position, id",
+        )
+        .bind(product_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(paths)
+    }
+
+    async fn images_for_product(&self, product_id: i64) -> RepoResult<Vec<ImageRecord>> {
+        let rows = sqlx::query(
+            "SELECT id, image_path FROM images WHERE product_id = $1 ORDER BY position, id",
+        )
+        .bind(product_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| ImageRecord {
+                id: row.get("id"),
+                image_path: row.get("image_path"),
+            })
+            .collect())
+    }
+
+    async fn paths_for_type(&self, type_id: i64) -> RepoResult<Vec<String>> {
+        let paths = sqlx::query_scalar::<_, String>(
+            "SELECT image_path FROM images WHERE product_type_id = $1 ORDER BY id",
+        )
+        .bind(type_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(paths)
+    }
+
+    async fn product_images_for_type(&self, type_id: i64) -> RepoResult<Vec<ImageRecord>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT i.id, i.image_path
+            FROM images i
+            JOIN products p ON i.product_id = p.id
+            WHERE p.products_type_id = $1
+            ORDER BY i.id
+            "#,
+        )
+        .bind(type_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| ImageRecord {
+                id: row.get("id"),
+                image_path: row.get("image_path"),
+            })
+            .collect())
+    }
+}
+
+// เงื่อนไข WHERE ของ audit_log ที่ใช้ร่วมกันระหว่างการนับและการดึงข้อมูล
+fn push_audit_filters(builder: &mut QueryBuilder<'_, Db>, filter: &AuditFilter) {
+    builder.push(" WHERE 1 = 1");
+    if let Some(actor) = &filter.actor {
+        builder.push(" AND actor = ").push_bind(actor.clone());
+    }
+    if let Some(action) = filter.action {
+        builder.push(" AND action = ").push_bind(action.as_str());
+    }
+    if let Some(entity_type) = filter.entity_type {
+        builder
+            .push(" AND entity_type = ")
+            .push_bind(entity_type.as_str());
+    }
+    if let Some(entity_id) = filter.entity_id {
+        builder.push(" AND entity_id = ").push_bind(entity_id);
+    }
+    if let Some(from) = filter.from {
+        builder.push(" AND created_at >= ").push_bind(from);
+    }
+    if let Some(to) = filter.to {
+        builder.push(" AND created_at < ").push_bind(to);
+    }
+}
+
+#[async_trait]
+impl AuditRepository for SqlRepository {
+    async fn count(&self, filter: &AuditFilter) -> RepoResult<i64> {
+        let mut query = QueryBuilder::<Db>::new("SELECT COUNT(*) FROM audit_log");
+        push_audit_filters(&mut query, filter);
+        Ok(query
+            .build_query_scalar::<i64>()
+            .fetch_one(&self.pool)
+            .await?)
+    }
+
+    async fn list(
+        &self,
+        filter: &AuditFilter,
+        limit: i64,
+        offset: i64,
+    ) -> RepoResult<Vec<AuditEntry>> {
+        let mut query = QueryBuilder::<Db>::new(
+            r#"
+            SELECT id, actor, action, entity_type, entity_id, before_data, after_data, created_at
+            FROM audit_log
+            "#,
+        );
+        push_audit_filters(&mut query, filter);
+        query
+            .push(" ORDER BY id DESC LIMIT ")
+            .push_bind(limit)
+            .push(" OFFSET ")
+            .push_bind(offset);
+
+        let rows = query.build().fetch_all(&self.pool).await?;
+
+        let mut entries = Vec::with_capacity(rows.len());
+        for row in &rows {
+            let action: String = row.get("action");
+            let entity_type: String = row.get("entity_type");
+            entries.push(AuditEntry {
+                id: row.get("id"),
+                actor: row.get("actor"),
+                action: action.parse().map_err(RepoError::Conflict)?,
+                entity_type: entity_type.parse().map_err(RepoError::Conflict)?,
+                entity_id: row.get("entity_id"),
+                before: row.get("before_data"),
+                after: row.get("after_data"),
+                created_at: row.get("created_at"),
+            });
+        }
+        Ok(entries)
+    }
+}
+
+#[cfg(all(test, feature = "sqlite"))]
+mod tests {
+    use super::*;
+    use crate::db::memory_pool;
+    use crate::repository::Comparison;
+    use serde_json::json;
+
+    const ACTOR: &str = "test";
+
+    async fn repo() -> SqlRepository {
+        SqlRepository::new(memory_pool().await)
+    }
+
+    fn input(name: &str, stock: i64, products_type_id: Option<i64>) -> ProductInput {
+        ProductInput {
+            name: name.to_string(),
+            price: Money::new(1000, Currency::Thb),
+            detail: json!({}),
+            stock,
+            products_type_id,
+        }
+    }
+
+    fn paths(paths: &[&str]) -> Vec<String> {
+        paths.iter().map(|p| p.to_string()).collect()
+    }
+
+    fn filter(sort: ListSort) -> ProductFilter {
+        ProductFilter {
+            search: String::new(),
+            type_id: TypeIdFilter::None,
+            trashed: false,
+            sort,
+            min_price: None,
+            max_price: None,
+            min_stock: None,
+            max_stock: None,
+            created_from: None,
+            created_to: None,
+            detail: Vec::new(),
+        }
+    }
+
+    // SQLite เทียบเวลาเป็นข้อความ จึงใช้เวลาจริงแทน NaiveDateTime::MAX
+    fn tomorrow() -> NaiveDateTime {
+        now() + chrono::Duration::days(1)
+    }
+
+    fn adjustment(quantity: i64, reason: StockReason) -> StockAdjustment {
+        StockAdjustment {
+            quantity,
+            reason,
+            note: None,
+        }
+    }
+
+    async fn names(repo: &SqlRepository, filter: &ProductFilter, seek: &PageSeek) -> Vec<String> {
+        ProductRepository::list(repo, filter, seek, 100)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|(product, _)| product.name_product)
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn create_update_patch_and_duplicate_product() {
+        let repo = repo().await;
+        let id = ProductRepository::create(
+            &repo,
+            ACTOR,
+            &input("tee", 5, None),
+            &paths(&["other/tee/tee_0.jpg"]),
+        )
+        .await
+        .unwrap();
+        let product = ProductRepository::find(&repo, id).await.unwrap().unwrap();
+        assert_eq!(product.name_product, "tee");
+        assert_eq!(product.price, Money::new(1000, Currency::Thb));
+        assert_eq!(
+            repo.paths_for_product(id).await.unwrap(),
+            ["other/tee/tee_0.jpg"]
+        );
+
+        repo.update(
+            ACTOR,
+            id,
+            &input("polo", 8, None),
+            &paths(&["other/polo/polo_0.jpg", "other/polo/polo_1.jpg"]),
+        )
+        .await
+        .unwrap();
+        let product = ProductRepository::find(&repo, id).await.unwrap().unwrap();
+        assert_eq!((product.name_product.as_str(), product.stock), ("polo", 8));
+        assert_eq!(
+            repo.paths_for_product(id).await.unwrap(),
+            ["other/polo/polo_0.jpg", "other/polo/polo_1.jpg"]
+        );
+        // stock ที่เปลี่ยนจาก update บันทึกเป็น correction
+        assert_eq!(
+            repo.count_stock_movements(id, Some(StockReason::Correction))
+                .await
+                .unwrap(),
+            1
+        );
+
+        let changes = ProductChanges {
+            detail: Some(json!({"color": "red"})),
+            allow_backorder: Some(true),
+            sku: Some(Some("POLO-1".to_string())),
+            barcode: Some(Some((BarcodeFormat::Upca, "036000291452".to_string()))),
+            ..Default::default()
+        };
+        ProductRepository::patch(&repo, ACTOR, id, &changes)
+            .await
+            .unwrap();
+        let product = ProductRepository::find(&repo, id).await.unwrap().unwrap();
+        assert_eq!(product.detail, json!({"color": "red"}));
+        assert_eq!(product.sku.as_deref(), Some("POLO-1"));
+        assert!(product.allow_backorder);
+        // เครื่องสแกนอ่าน UPC-A เป็น EAN-13 ที่มี 0 นำหน้าได้
+        assert_eq!(
+            repo.find_id_by_barcode(&equivalent_codes("0036000291452"))
+                .await
+                .unwrap(),
+            Some(id)
+        );
+        assert_eq!(
+            repo.find_id_by_barcode(&paths(&["4006381333931"]))
+                .await
+                .unwrap(),
+            None
+        );
+
+        // สำเนาได้ allow_backorder แต่ไม่ได้ sku และ barcode
+        let copy_id = repo
+            .duplicate(
+                ACTOR,
+                id,
+                &input("polo copy", 0, None),
+                &paths(&["other/polo copy/polo copy_0.jpg"]),
+            )
+            .await
+            .unwrap();
+        let copy = ProductRepository::find(&repo, copy_id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(copy.allow_backorder);
+        assert_eq!((copy.sku, copy.barcode), (None, None));
+        assert!(matches!(
+            repo.duplicate(ACTOR, 999, &input("x", 0, None), &[]).await,
+            Err(RepoError::NotFound)
+        ));
+
+        let duplicate_sku = ProductChanges {
+            sku: Some(Some("POLO-1".to_string())),
+            ..Default::default()
+        };
+        assert!(matches!(
+            ProductRepository::patch(&repo, ACTOR, copy_id, &duplicate_sku).await,
+            Err(RepoError::Conflict(_))
+        ));
+        assert_eq!(
+            repo.paths_for_products(&[id, copy_id]).await.unwrap(),
+            [
+                (id, "other/polo/polo_0.jpg".to_string()),
+                (id, "other/polo/polo_1.jpg".to_string()),
+                (copy_id, "other/polo copy/polo copy_0.jpg".to_string()),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn update_images_reorders_adds_and_removes() {
+        let repo = repo().await;
+        let id = ProductRepository::create(
+            &repo,
+            ACTOR,
+            &input("tee", 1, None),
+            &paths(&["other/tee/tee_0.jpg", "other/tee/tee_1.jpg"]),
+        )
+        .await
+        .unwrap();
+        let other_id = ProductRepository::create(
+            &repo,
+            ACTOR,
+            &input("polo", 1, None),
+            &paths(&["other/polo/polo_0.jpg"]),
+        )
+        .await
+        .unwrap();
+        let images = repo.images_for_product(id).await.unwrap();
+        let other_image = repo.images_for_product(other_id).await.unwrap()[0].id;
+
+        repo.update_images(
+            ACTOR,
+            id,
+            &[
+                ImageSlot::New("other/tee/tee_2.jpg".to_string()),
+                ImageSlot::Existing(images[1].id),
+            ],
+        )
+        .await
+        .unwrap();
+        let updated = repo.images_for_product(id).await.unwrap();
+        assert_eq!(
+            updated
+                .iter()
+                .map(|image| image.image_path.as_str())
+                .collect::<Vec<_>>(),
+            ["other/tee/tee_2.jpg", "other/tee/tee_1.jpg"]
+        );
+        assert_eq!(updated[1].id, images[1].id);
+
+        // รูปที่ถูกลบไปแล้วหรือเป็นของสินค้าอื่นใช้ไม่ได้
+        for image_id in [images[0].id, other_image] {
+            assert!(matches!(
+                repo.update_images(ACTOR, id, &[ImageSlot::Existing(image_id)])
+                    .await,
+                Err(RepoError::Conflict(_))
+            ));
+        }
+    }
+
+    #[tokio::test]
+    async fn list_count_and_facets_follow_filters() {
+        let repo = repo().await;
+        let type_id = ProductTypeRepository::create(&repo, ACTOR, "laptop", &[])
+            .await
+            .unwrap();
+        for (name, price, stock, detail) in [
+            (
+                "thinkpad x1",
+                Money::new(500_000, Currency::Thb),
+                3,
+                json!({"ram_gb": 16, "color": "black"}),
+            ),
+            (
+                "thinkpad t14",
+                Money::new(300_000, Currency::Thb),
+                0,
+                json!({"ram_gb": "8", "color": "black"}),
+            ),
+            (
+                "macbook air",
+                Money::new(90_000, Currency::Usd),
+                2,
+                json!({"ram_gb": 8, "color": ["silver", "gold"]}),
+            ),
+        ] {
+            let product = ProductInput {
+                price,
+                detail,
+                ..input(name, stock, Some(type_id))
+            };
+            ProductRepository::create(&repo, ACTOR, &product, &[])
+                .await
+                .unwrap();
+        }
+        ProductRepository::create(&repo, ACTOR, &input("mouse", 10, None), &[])
+            .await
+            .unwrap();
+
+        // ค้นแบบขึ้นต้นคำ snippet เป็น HTML ที่ครอบคำที่ตรงด้วย <mark>
+        let search = ProductFilter {
+            search: "think".to_string(),
+            ..filter(ListSort::Relevance)
+        };
+        assert_eq!(ProductRepository::count(&repo, &search).await.unwrap(), 2);
+        let found = ProductRepository::list(&repo, &search, &PageSeek::Offset(0), 10)
+            .await
+            .unwrap();
+        assert!(found.iter().all(|(product, _)| {
+            product
+                .snippet
+                .as_deref()
+                .is_some_and(|s| s.contains("<mark>"))
+        }));
+
+        let detail = ProductFilter {
+            detail: vec![DetailFilter {
+                path: vec!["ram_gb".to_string()],
+                condition: DetailCondition::Compare(Comparison::GreaterOrEqual, 8.0),
+            }],
+            ..filter(ListSort::NameAsc)
+        };
+        assert_eq!(
+            names(&repo, &detail, &PageSeek::Offset(0)).await,
+            ["macbook air", "thinkpad t14", "thinkpad x1"]
+        );
+        let silver = ProductFilter {
+            detail: vec![DetailFilter {
+                path: vec!["color".to_string()],
+                condition: DetailCondition::Equals("silver".to_string()),
+            }],
+            ..filter(ListSort::IdAsc)
+        };
+        assert_eq!(
+            names(&repo, &silver, &PageSeek::Offset(0)).await,
+            ["macbook air"]
+        );
+        let in_type = ProductFilter {
+            type_id: TypeIdFilter::IsNull,
+            ..filter(ListSort::IdAsc)
+        };
+        assert_eq!(ProductRepository::count(&repo, &in_type).await.unwrap(), 1);
+
+        let facets = repo
+            .facets(
+                &filter(ListSort::IdAsc),
+                Currency::Thb,
+                &[1000, 400_000],
+                &[vec!["color".to_string()]],
+            )
+            .await
+            .unwrap();
+        assert_eq!(facets.total_items, 4);
+        assert_eq!(
+            facets
+                .types
+                .iter()
+                .map(|t| (t.id, t.count))
+                .collect::<Vec<_>>(),
+            [(Some(type_id), 3), (None, 1)]
+        );
+        assert_eq!(
+            facets.price.iter().map(|b| b.count).collect::<Vec<_>>(),
+            [0, 2, 1]
+        );
+        assert_eq!((facets.stock.in_stock, facets.stock.out_of_stock), (3, 1));
+        let colors: Vec<(&str, i64)> = facets.detail[0]
+            .values
+            .iter()
+            .map(|v| (v.value.as_str(), v.count))
+            .collect();
+        assert_eq!(colors[0], ("black", 2));
+        assert!(colors.contains(&("silver", 1)) && colors.contains(&("gold", 1)));
+    }
+
+    #[tokio::test]
+    async fn price_cursor_pages_currencies_in_order() {
+        let repo = repo().await;
+        for (name, price) in [
+            ("a", Money::new(500, Currency::Usd)),
+            ("b", Money::new(1000, Currency::Thb)),
+            ("c", Money::new(100, Currency::Usd)),
+            ("d", Money::new(100, Currency::Thb)),
+        ] {
+            let product = ProductInput {
+                price,
+                ..input(name, 1, None)
+            };
+            ProductRepository::create(&repo, ACTOR, &product, &[])
+                .await
+                .unwrap();
+        }
+
+        for (sort, expected) in [
+            (ListSort::PriceAsc, ["d", "b", "c", "a"]),
+            (ListSort::PriceDesc, ["a", "c", "b", "d"]),
+        ] {
+            let filter = filter(sort);
+            let mut seek = PageSeek::Offset(0);
+            let mut found = Vec::new();
+            while let Some((product, key)) = ProductRepository::list(&repo, &filter, &seek, 1)
+                .await
+                .unwrap()
+                .pop()
+            {
+                assert!(key.value.fits(sort));
+                found.push(product.name_product);
+                seek = PageSeek::After(key);
+            }
+            assert_eq!(found, expected);
+
+            let (_, last) = ProductRepository::list(&repo, &filter, &PageSeek::Offset(3), 1)
+                .await
+                .unwrap()
+                .remove(0);
+            assert_eq!(
+                names(&repo, &filter, &PageSeek::Before(last)).await,
+                expected[..3]
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn import_creates_types_and_products_together() {
+        let repo = repo().await;
+        let shirt_id = ProductTypeRepository::create(
+            &repo,
+            ACTOR,
+            "shirt",
+            &paths(&["shirt/main/main_0.jpg"]),
+        )
+        .await
+        .unwrap();
+        let imported = |name: &str, type_name: Option<&str>| ImportedProduct {
+            name: name.to_string(),
+            price: Money::new(100, Currency::Thb),
+            detail: json!({}),
+            stock: 1,
+            type_name: type_name.map(String::from),
+            image_paths: vec![format!(
+                "{}/{}/{}_0.jpg",
+                type_name.unwrap_or("other"),
+                name,
+                name
+            )],
+        };
+
+        let ids = repo
+            .import(
+                ACTOR,
+                &paths(&["shoe"]),
+                &[
+                    imported("tee", Some("shirt")),
+                    imported("boot", Some("shoe")),
+                ],
+            )
+            .await
+            .unwrap();
+        assert_eq!(ids.len(), 2);
+        let shoe_id = repo.find_id_by_name("shoe").await.unwrap().unwrap();
+        assert_eq!(
+            ProductRepository::find(&repo, ids[1])
+                .await
+                .unwrap()
+                .unwrap()
+                .products_type_id,
+            Some(shoe_id)
+        );
+
+        // ชื่อประเภทซ้ำหรือประเภทที่ไม่มี ไม่บันทึกอะไรเลย
+        assert!(matches!(
+            repo.import(
+                ACTOR,
+                &paths(&["shirt"]),
+                &[imported("polo", Some("shirt"))]
+            )
+            .await,
+            Err(RepoError::Conflict(_))
+        ));
+        assert!(matches!(
+            repo.import(ACTOR, &[], &[imported("sock", Some("sock"))])
+                .await,
+            Err(RepoError::Conflict(_))
+        ));
+        assert_eq!(
+            ProductRepository::count(&repo, &filter(ListSort::IdAsc))
+                .await
+                .unwrap(),
+            2
+        );
+
+        assert_eq!(
+            ProductTypeRepository::count(&repo, "sh", false)
+                .await
+                .unwrap(),
+            2
+        );
+        let types = ProductTypeRepository::list(&repo, "", false, ListSort::NameDesc, 10, 0)
+            .await
+            .unwrap();
+        assert_eq!(
+            types.iter().map(|t| t.name.as_str()).collect::<Vec<_>>(),
+            ["shoe", "shirt"]
+        );
+        assert_eq!(
+            repo.paths_for_types(&[shirt_id, shoe_id]).await.unwrap(),
+            [(shirt_id, "shirt/main/main_0.jpg".to_string())]
+        );
+        assert_eq!(
+            repo.paths_for_type(shirt_id).await.unwrap(),
+            ["shirt/main/main_0.jpg"]
+        );
+        assert_eq!(
+            repo.product_images_for_type(shoe_id)
+                .await
+                .unwrap()
+                .into_iter()
+                .map(|image| image.image_path)
+                .collect::<Vec<_>>(),
+            ["shoe/boot/boot_0.jpg"]
+        );
+    }
+
+    #[tokio::test]
+    async fn stock_movements_raise_reorder_alerts() {
+        let repo = repo().await;
+        let type_id = ProductTypeRepository::create(&repo, ACTOR, "shirt", &[])
+            .await
+            .unwrap();
+        let type_defaults = ProductTypeChanges {
+            reorder_threshold: Some(Some(2)),
+            reorder_quantity: Some(Some(10)),
+        };
+        ProductTypeRepository::patch(&repo, ACTOR, type_id, &type_defaults)
+            .await
+            .unwrap();
+        let id = ProductRepository::create(&repo, ACTOR, &input("tee", 5, Some(type_id)), &[])
+            .await
+            .unwrap();
+
+        let movement = repo
+            .adjust_stock(ACTOR, id, &adjustment(-3, StockReason::Sale))
+            .await
+            .unwrap();
+        assert_eq!(movement.stock_after, 2);
+        assert!(matches!(
+            repo.adjust_stock(ACTOR, id, &adjustment(-3, StockReason::Damage))
+                .await,
+            Err(RepoError::Conflict(_))
+        ));
+        repo.adjust_stock(ACTOR, id, &adjustment(1, StockReason::Restock))
+            .await
+            .unwrap();
+        assert_eq!(repo.count_stock_movements(id, None).await.unwrap(), 2);
+        let movements = repo.stock_movements(id, None, 10, 0).await.unwrap();
+        assert_eq!(
+            movements.iter().map(|m| m.reason).collect::<Vec<_>>(),
+            [StockReason::Restock, StockReason::Sale]
+        );
+        assert_eq!(
+            repo.stock_movements(id, Some(StockReason::Sale), 10, 0)
+                .await
+                .unwrap()[0]
+                .id,
+            movement.id
+        );
+
+        // จุดสั่งซื้อมาจากประเภท stock 3 ยังเหนือ 2 จึงไม่ใกล้หมด
+        assert_eq!(repo.count_low_stock().await.unwrap(), 0);
+        repo.adjust_stock(ACTOR, id, &adjustment(-2, StockReason::Sale))
+            .await
+            .unwrap();
+        assert_eq!(repo.count_low_stock().await.unwrap(), 1);
+        let low = repo.low_stock(10, 0).await.unwrap();
+        assert_eq!(
+            (low[0].id, low[0].reorder_threshold, low[0].reorder_quantity),
+            (id, 2, Some(10))
+        );
+
+        // ลดผ่านจุดสั่งซื้อสองครั้ง (5 -> 2 และ 3 -> 1)
+        assert_eq!(repo.count_stock_alerts(Some(true)).await.unwrap(), 2);
+        let alerts = repo.stock_alerts(None, 10, 0).await.unwrap();
+        assert_eq!(alerts[0].stock_movement_id, movement.id);
+        let acknowledged = repo.acknowledge_stock_alert(alerts[0].id).await.unwrap();
+        assert!(acknowledged.acknowledged_at.is_some());
+        assert_eq!(
+            repo.acknowledge_stock_alert(alerts[0].id)
+                .await
+                .unwrap()
+                .acknowledged_at,
+            acknowledged.acknowledged_at
+        );
+        assert_eq!(repo.count_stock_alerts(Some(false)).await.unwrap(), 1);
+        assert_eq!(repo.stock_alerts(Some(true), 10, 0).await.unwrap().len(), 1);
+        assert!(matches!(
+            repo.acknowledge_stock_alert(999).await,
+            Err(RepoError::NotFound)
+        ));
+    }
+
+    #[tokio::test]
+    async fn variants_follow_product_options() {
+        let repo = repo().await;
+        let id = ProductRepository::create(
+            &repo,
+            ACTOR,
+            &input("tee", 0, None),
+            &paths(&["other/tee/tee_0.jpg"]),
+        )
+        .await
+        .unwrap();
+        let image_id = repo.images_for_product(id).await.unwrap()[0].id;
+        let size = |values: &[&str]| VariantOption {
+            name: "size".to_string(),
+            values: paths(values),
+        };
+        let options = |value: &str| BTreeMap::from([("size".to_string(), value.to_string())]);
+
+        repo.set_variant_options(ACTOR, id, &[size(&["S", "M"])])
+            .await
+            .unwrap();
+        let variant_id = repo
+            .create_variant(
+                ACTOR,
+                id,
+                &VariantInput {
+                    sku: "TEE-S".to_string(),
+                    options: options("S"),
+                    price_minor: Some(1200),
+                    stock: 4,
+                    image_ids: vec![image_id],
+                },
+            )
+            .await
+            .unwrap();
+        let same_options = VariantInput {
+            sku: "TEE-S2".to_string(),
+            options: options("S"),
+            price_minor: None,
+            stock: 0,
+            image_ids: Vec::new(),
+        };
+        assert!(matches!(
+            repo.create_variant(ACTOR, id, &same_options).await,
+            Err(RepoError::Conflict(_))
+        ));
+        // แกนใหม่ที่ไม่มีค่าของ variant ที่มีอยู่ใช้ไม่ได้
+        assert!(matches!(
+            repo.set_variant_options(ACTOR, id, &[size(&["M", "L"])])
+                .await,
+            Err(RepoError::Conflict(_))
+        ));
+
+        let changes = VariantChanges {
+            options: Some(options("M")),
+            price_minor: Some(None),
+            ..Default::default()
+        };
+        repo.patch_variant(ACTOR, id, variant_id, &changes)
+            .await
+            .unwrap();
+        let variants = repo.variants(id).await.unwrap();
+        assert_eq!(variants.len(), 1);
+        assert_eq!(variants[0].options, options("M"));
+        assert_eq!(variants[0].price, None);
+        assert_eq!(variants[0].images[0].id, image_id);
+
+        repo.delete_variant(ACTOR, id, variant_id).await.unwrap();
+        assert!(repo.variants(id).await.unwrap().is_empty());
+        assert!(matches!(
+            repo.delete_variant(ACTOR, id, variant_id).await,
+            Err(RepoError::NotFound)
+        ));
+    }
+
+    #[tokio::test]
+    async fn trash_restore_and_purge() {
+        let repo = repo().await;
+        let type_id = ProductTypeRepository::create(&repo, ACTOR, "shirt", &[])
+            .await
+            .unwrap();
+        let id = ProductRepository::create(
+            &repo,
+            ACTOR,
+            &input("tee", 1, Some(type_id)),
+            &paths(&["shirt/tee/tee_0.jpg"]),
+        )
+        .await
+        .unwrap();
+
+        assert!(matches!(
+            ProductRepository::purge(&repo, ACTOR, id).await,
+            Err(RepoError::NotFound)
+        ));
+        ProductRepository::soft_delete(&repo, ACTOR, id)
+            .await
+            .unwrap();
+        assert!(ProductRepository::find(&repo, id).await.unwrap().is_none());
+        assert_eq!(
+            ProductRepository::trashed_before(&repo, tomorrow())
+                .await
+                .unwrap(),
+            [id]
+        );
+        ProductRepository::restore(&repo, ACTOR, id).await.unwrap();
+
+        // สินค้าที่ถูกลบพร้อมประเภทกู้คืนเดี่ยว ๆ ไม่ได้จนกว่าจะกู้คืนประเภท
+        repo.soft_delete_with_products(ACTOR, type_id)
+            .await
+            .unwrap();
+        assert!(matches!(
+            ProductRepository::restore(&repo, ACTOR, id).await,
+            Err(RepoError::Conflict(_))
+        ));
+        assert_eq!(
+            ProductTypeRepository::trashed_before(&repo, tomorrow())
+                .await
+                .unwrap(),
+            [type_id]
+        );
+        ProductTypeRepository::restore(&repo, ACTOR, type_id)
+            .await
+            .unwrap();
+        assert!(ProductRepository::find(&repo, id).await.unwrap().is_some());
+
+        ProductRepository::soft_delete(&repo, ACTOR, id)
+            .await
+            .unwrap();
+        ProductRepository::purge(&repo, ACTOR, id).await.unwrap();
+        assert!(repo.paths_for_product(id).await.unwrap().is_empty());
+
+        // ลบถาวรประเภทพร้อมสินค้าในประเภท
+        let other_id =
+            ProductRepository::create(&repo, ACTOR, &input("polo", 1, Some(type_id)), &[])
+                .await
+                .unwrap();
+        repo.soft_delete_with_products(ACTOR, type_id)
+            .await
+            .unwrap();
+        ProductTypeRepository::purge(&repo, ACTOR, type_id)
+            .await
+            .unwrap();
+        assert!(
+            ProductRepository::trashed_before(&repo, tomorrow())
+                .await
+                .unwrap()
+                .is_empty()
+        );
+        assert!(matches!(
+            ProductRepository::restore(&repo, ACTOR, other_id).await,
+            Err(RepoError::NotFound)
+        ));
+    }
+
+    #[tokio::test]
+    async fn deleting_type_keeps_products_without_type() {
+        let repo = repo().await;
+        let type_id = ProductTypeRepository::create(
+            &repo,
+            ACTOR,
+            "shirt",
+            &paths(&["shirt/main/main_0.jpg"]),
+        )
+        .await
+        .unwrap();
+        let id = ProductRepository::create(
+            &repo,
+            ACTOR,
+            &input("tee", 1, Some(type_id)),
+            &paths(&["shirt/tee/tee_0.jpg"]),
+        )
+        .await
+        .unwrap();
+
+        repo.soft_delete_keep_products(ACTOR, type_id)
+            .await
+            .unwrap();
+        assert!(repo.find_id_by_name("shirt").await.unwrap().is_none());
+        assert_eq!(
+            ProductTypeRepository::count(&repo, "", true).await.unwrap(),
+            1
+        );
+        let product = ProductRepository::find(&repo, id).await.unwrap().unwrap();
+        assert_eq!(product.products_type_id, None);
+        assert_eq!(
+            repo.paths_for_product(id).await.unwrap(),
+            ["shirt/tee/tee_0.jpg"]
+        );
+
+        ProductTypeRepository::purge(&repo, ACTOR, type_id)
+            .await
+            .unwrap();
+        assert!(ProductRepository::find(&repo, id).await.unwrap().is_some());
+        assert!(repo.paths_for_type(type_id).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn audit_log_records_every_change() {
+        let repo = repo().await;
+        let id = ProductRepository::create(&repo, ACTOR, &input("tee", 1, None), &[])
+            .await
+            .unwrap();
+        let changes = ProductChanges {
+            name: Some("polo".to_string()),
+            ..Default::default()
+        };
+        ProductRepository::patch(&repo, "other", id, &changes)
+            .await
+            .unwrap();
+        ProductRepository::soft_delete(&repo, ACTOR, id)
+            .await
+            .unwrap();
+
+        let all = AuditFilter {
+            actor: None,
+            action: None,
+            entity_type: Some(AuditEntity::Product),
+            entity_id: Some(id),
+            from: None,
+            to: None,
+        };
+        assert_eq!(AuditRepository::count(&repo, &all).await.unwrap(), 3);
+        let entries = AuditRepository::list(&repo, &all, 10, 0).await.unwrap();
+        assert_eq!(
+            entries.iter().map(|e| e.action).collect::<Vec<_>>(),
+            [
+                AuditAction::Delete,
+                AuditAction::Update,
+                AuditAction::Create
+            ]
+        );
+        assert_eq!(entries[1].before.as_ref().unwrap()["name_product"], "tee");
+        assert_eq!(entries[1].after.as_ref().unwrap()["name_product"], "polo");
+        assert_eq!(entries[2].before, None);
+
+        let by_actor = AuditFilter {
+            actor: Some("other".to_string()),
+            ..all
+        };
+        assert_eq!(AuditRepository::count(&repo, &by_actor).await.unwrap(), 1);
+        assert_eq!(
+            AuditRepository::list(&repo, &by_actor, 10, 1)
+                .await
+                .unwrap()
+                .len(),
+            0
+        );
+    }
+}
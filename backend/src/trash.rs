@@ -0,0 +1,148 @@
+use crate::config::Config;
+use crate::repository::{
+    ImageRepository, ProductRepository, ProductTypeRepository, RepoResult, Repositories, now,
+};
+use std::cmp::Reverse;
+use std::collections::BTreeSet;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+use std::time::Duration;
+
+// actor ใน audit_log ของการลบถาวรอัตโนมัติ
+const SYSTEM_ACTOR: &str = "system";
+
+// ลบถาวร: ลบแถวในฐานข้อมูลก่อนแล้วค่อยลบไฟล์รูป ถ้าลบไฟล์ไม่สำเร็จจะเหลือแค่ไฟล์ค้าง ไม่เสียข้อมูล
+// ลบเฉพาะไฟล์ของรายการที่ถูกลบ โฟลเดอร์ถูกลบเมื่อว่างแล้วเท่านั้น เพราะสินค้าอื่นอาจใช้โฟลเดอร์เดียวกัน
+pub async fn purge_product(
+    products_repo: &dyn ProductRepository,
+    images_repo: &dyn ImageRepository,
+    config: &Config,
+    actor: &str,
+    id: i64,
+) -> RepoResult<()> {
+    let image_paths = images_repo.paths_for_product(id).await?;
+    products_repo.purge(actor, id).await?;
+
+    // game/minecraft/minecraft_0.jpg -> ลบไฟล์ แล้วลบ minecraft ถ้าว่าง
+    remove_files(
+        image_paths
+            .iter()
+            .filter_map(|path| image_file(config, path)),
+        1,
+    );
+    Ok(())
+}
+
+pub async fn purge_product_type(
+    types_repo: &dyn ProductTypeRepository,
+    images_repo: &dyn ImageRepository,
+    config: &Config,
+    actor: &str,
+    id: i64,
+) -> RepoResult<()> {
+    let main_paths = images_repo.paths_for_type(id).await?;
+    let product_images = images_repo.product_images_for_type(id).await?;
+    types_repo.purge(actor, id).await?;
+
+    // game/main/game_0.jpg, game/minecraft/minecraft_0.jpg -> ลบไฟล์ แล้วลบ main, minecraft และ game ถ้าว่าง
+    let files = main_paths
+        .iter()
+        .chain(product_images.iter().map(|image| &image.image_path))
+        .filter_map(|path| image_file(config, path));
+    remove_files(files, 2);
+    Ok(())
+}
+
+// path ที่ออกนอก images_dir (ข้อมูลเก่าก่อนมีการตรวจชื่อ) ไม่ลบ แค่แจ้งเตือน
+fn image_file(config: &Config, path: &str) -> Option<PathBuf> {
+    config
+        .image_file(path)
+        .inspect_err(|e| eprintln!("⚠️ Skipped removing image: {}", e))
+        .ok()
+}
+
+// ลบไฟล์ แล้วลบโฟลเดอร์ที่ว่างแล้วขึ้นไป levels ชั้นจากแต่ละไฟล์ (โฟลเดอร์ลึกก่อน)
+fn remove_files(files: impl Iterator<Item = PathBuf>, levels: usize) {
+    let mut dirs = BTreeSet::new();
+    for file in files {
+        if let Err(e) = fs::remove_file(&file)
+            && e.kind() != ErrorKind::NotFound
+        {
+            eprintln!("⚠️ Failed to remove file {}: {}", file.display(), e);
+        }
+        dirs.extend(file.ancestors().skip(1).take(levels).map(PathBuf::from));
+    }
+
+    let mut dirs: Vec<PathBuf> = dirs.into_iter().collect();
+    dirs.sort_by_key(|dir| Reverse(dir.components().count()));
+    for dir in dirs {
+        let is_empty = fs::read_dir(&dir).is_ok_and(|mut entries| entries.next().is_none());
+        if is_empty && let Err(e) = fs::remove_dir(&dir) {
+            eprintln!("⚠️ Failed to remove folder {}: {}", dir.display(), e);
+        }
+    }
+}
+
+// ลบถาวรทุกรายการที่อยู่ในถังขยะนานเกิน trash_retention_days
+// รายการที่ลบไม่สำเร็จจะแจ้งเตือนแล้วข้ามไป ไม่ให้รายการเดียวค้างทั้งรอบ (รอบถัดไปจะลองใหม่)
+pub async fn purge_expired(repos: &Repositories, config: &Config) -> RepoResult<()> {
+    let cutoff = now() - chrono::Duration::days(config.trash_retention_days.into());
+
+    let mut purged_types = 0;
+    for id in repos.product_types.trashed_before(cutoff).await? {
+        match purge_product_type(
+            repos.product_types.as_ref(),
+            repos.images.as_ref(),
+            config,
+            SYSTEM_ACTOR,
+            id,
+        )
+        .await
+        {
+            Ok(()) => purged_types += 1,
+            Err(e) => eprintln!("⚠️ Failed to purge product type {}: {}", id, e),
+        }
+    }
+
+    let mut purged_products = 0;
+    for id in repos.products.trashed_before(cutoff).await? {
+        match purge_product(
+            repos.products.as_ref(),
+            repos.images.as_ref(),
+            config,
+            SYSTEM_ACTOR,
+            id,
+        )
+        .await
+        {
+            Ok(()) => purged_products += 1,
+            Err(e) => eprintln!("⚠️ Failed to purge product {}: {}", id, e),
+        }
+    }
+
+    if purged_types > 0 || purged_products > 0 {
+        println!(
+            "🗑️ Purged {} product types and {} products from trash",
+            purged_types, purged_products
+        );
+    }
+    Ok(())
+}
+
+// ตรวจถังขยะตอนเริ่มและทุกชั่วโมง (ไม่ทำงานถ้า trash_retention_days = 0)
+pub fn spawn_auto_purge(repos: Repositories, config: Config) {
+    if config.trash_retention_days == 0 {
+        return;
+    }
+
+    actix_web::rt::spawn(async move {
+        let mut interval = actix_web::rt::time::interval(Duration::from_secs(60 * 60));
+        loop {
+            interval.tick().await;
+            if let Err(e) = purge_expired(&repos, &config).await {
+                eprintln!("❌ Failed to purge trash: {}", e);
+            }
+        }
+    });
+}
@@ -51,7 +51,7 @@ pub async fn fetch_products(
 
 pub async fn post_products(
     name: &str,
-    price: &f64,
+    price: &str,
     stock: &usize,
     detail: &str,
     product_type_name: &str,
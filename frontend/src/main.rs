@@ -6,7 +6,7 @@ use actix_web::{App, Error, HttpResponse, HttpServer, Result, get, post, web};
 use dotenvy::dotenv;
 use futures_util::{StreamExt, TryStreamExt};
 use image::ImageFormat;
-use models::PageQuery;
+use models::{Money, PageQuery};
 use serde_json::Value;
 use std::fs;
 use std::io::Write;
@@ -108,7 +108,7 @@ async fn get_product_types(tmpl: web::Data<Tera>, query: web::Query<PageQuery>)
 #[post("/api/product/upload")]
 async fn post_product(mut payload: Multipart) -> Result<HttpResponse, Error> {
     let mut product_name = String::new();
-    let mut product_price = 0.0;
+    let mut product_price = String::new();
     let mut product_detail = String::new();
     let mut temp_files = Vec::new();
     let mut product_stock = 0usize;
@@ -154,8 +154,8 @@ async fn post_product(mut payload: Multipart) -> Result<HttpResponse, Error> {
                 })?;
                 data.extend_from_slice(&chunk_data);
             }
-            let string_data = String::from_utf8(data).unwrap_or_default();
-            product_price = string_data.parse::<f64>().unwrap_or(0.0);
+            // ส่งต่อข้อความราคาไปให้ backend ตรวจแบบเข้มงวด ไม่แปลงเป็น f64
+            product_price = String::from_utf8(data).unwrap_or_default();
         } else if name == "stock" {
             let mut data = Vec::new();
             while let Some(chunk) = field.next().await {
@@ -465,11 +465,22 @@ async fn proxy_images(path: web::Path<String>) -> Result<HttpResponse> {
     Ok(HttpResponse::BadRequest().body("Invalid or missing image"))
 }
 
+// filter สำหรับ template: {{ product.price | money }}
+fn money_filter(
+    value: &Value,
+    _: &std::collections::HashMap<String, Value>,
+) -> tera::Result<Value> {
+    let money: Money = serde_json::from_value(value.clone())
+        .map_err(|e| tera::Error::msg(format!("Invalid money value: {}", e)))?;
+    Ok(Value::String(money.format()))
+}
+
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
     dotenv().ok();
 
-    let tera = Tera::new("public/**/*.html").unwrap();
+    let mut tera = Tera::new("public/**/*.html").unwrap();
+    tera.register_filter("money", money_filter);
 
     HttpServer::new(move || {
         App::new()
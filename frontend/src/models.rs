@@ -11,7 +11,7 @@ pub struct ApiResponse<T> {
 pub struct Products {
     pub id: i32,
     pub name_product: String,
-    pub price: f64,
+    pub price: Money,
     pub detail: Value,
     pub images_path: Vec<String>,
     pub stock: i32,
@@ -20,6 +20,52 @@ pub struct Products {
     pub products_type_name: Option<String>,
 }
 
+// ราคาจาก backend เป็นจำนวนเต็มหน่วยย่อย (สตางค์/เซนต์) พร้อมรหัสสกุลเงิน
+#[derive(Deserialize, Serialize, Debug)]
+pub struct Money {
+    pub amount: i64,
+    pub currency: String,
+}
+
+impl Money {
+    // แสดงผลเช่น 1,250.50฿ หรือ 1,250 JPY
+    pub fn format(&self) -> String {
+        let digits = match self.currency.as_str() {
+            "JPY" => 0,
+            _ => 2,
+        };
+        let scale = 10i64.pow(digits);
+        let whole = (self.amount / scale).abs().to_string();
+        let fraction = (self.amount % scale).abs();
+
+        let mut grouped = String::new();
+        for (i, c) in whole.chars().enumerate() {
+            if i > 0 && (whole.len() - i).is_multiple_of(3) {
+                grouped.push(',');
+            }
+            grouped.push(c);
+        }
+
+        let sign = if self.amount < 0 { "-" } else { "" };
+        let number = if digits > 0 {
+            format!(
+                "{}{}.{:0width$}",
+                sign,
+                grouped,
+                fraction,
+                width = digits as usize
+            )
+        } else {
+            format!("{}{}", sign, grouped)
+        };
+
+        match self.currency.as_str() {
+            "THB" => format!("{}฿", number),
+            code => format!("{} {}", number, code),
+        }
+    }
+}
+
 #[derive(Serialize ,Deserialize)]
 pub struct ProductType {
     pub id: i64,